@@ -36,10 +36,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let is_super_admin = admin_count.0 == 0; // First admin is super admin
 
     // Insert admin
-    sqlx::query("INSERT INTO admins (username, password_hash, is_super_admin) VALUES (?, ?, ?)")
+    let role = if is_super_admin { "super_admin" } else { "editor" };
+
+    sqlx::query("INSERT INTO admins (username, password_hash, is_super_admin, role) VALUES (?, ?, ?, ?)")
         .bind(&args.username)
         .bind(&password_hash)
         .bind(is_super_admin as i64)
+        .bind(role)
         .execute(&pool)
         .await?;
 
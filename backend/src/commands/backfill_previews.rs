@@ -0,0 +1,42 @@
+use crate::{
+    storage,
+    utils::{previews, uploads::download_to_temp},
+};
+use sqlx::SqlitePool;
+
+/// Generate the missing previews of every media file, archived ones included,
+/// or all of them with `force`. Files that fail are reported and skipped.
+pub async fn run(pool: &SqlitePool, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let media: Vec<(i64, String, String)> = sqlx::query_as(
+        "SELECT id, storage_key, media_type FROM media_files WHERE media_type IN ('image', 'audio') ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let storage = storage::get();
+    let mut generated = 0;
+    let mut failed = 0;
+    for (id, storage_key, media_type) in media {
+        let Some((preview_key, _)) = previews::preview(&storage_key, &media_type) else {
+            continue;
+        };
+        if !force && storage.size(&preview_key).await?.is_some() {
+            continue;
+        }
+
+        let result = match download_to_temp(storage, &storage_key).await {
+            Ok(original) => previews::generate(original.path(), &storage_key, &media_type).await,
+            Err(e) => Err(e.to_string()),
+        };
+        match result {
+            Ok(()) => generated += 1,
+            Err(e) => {
+                failed += 1;
+                eprintln!("Media {} ({}): {}", id, storage_key, e);
+            }
+        }
+    }
+
+    println!("Generated previews for {} media file(s), {} failed", generated, failed);
+    Ok(())
+}
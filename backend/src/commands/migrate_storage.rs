@@ -0,0 +1,73 @@
+use crate::{
+    storage,
+    utils::{previews, uploads::download_to_temp},
+};
+use sqlx::SqlitePool;
+
+/// Copy every media file and its preview from one storage backend to another.
+/// Keys stay the same, so switching STORAGE_BACKEND afterwards is all that's
+/// left to do. Objects already in the target with the same size are skipped,
+/// so an interrupted run (even one with `delete_source`) can simply be repeated.
+pub async fn run(pool: &SqlitePool, from: &str, to: &str, delete_source: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let source = storage::from_name(from)?;
+    let target = storage::from_name(to)?;
+    if source.name() == target.name() {
+        return Err("Source and target storage backends must differ".into());
+    }
+
+    let media: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT storage_key, media_type, mime_type FROM media_files ORDER BY id"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // (key, content type, whether the object may be missing)
+    let mut objects: Vec<(String, String, bool)> = Vec::new();
+    for (storage_key, media_type, mime_type) in media {
+        if let Some((preview_key, preview_type)) = previews::preview(&storage_key, &media_type) {
+            objects.push((preview_key, preview_type.to_string(), true));
+        }
+        objects.push((storage_key, mime_type, false));
+    }
+
+    let (mut copied, mut skipped, mut failed) = (0, 0, 0);
+    for (key, content_type, optional) in &objects {
+        let Some(size) = source.size(key).await? else {
+            if target.size(key).await?.is_some() {
+                skipped += 1;
+            } else if !optional {
+                eprintln!("{}: not found in {} storage", key, source.name());
+                failed += 1;
+            }
+            continue;
+        };
+
+        if target.size(key).await? != Some(size) {
+            let copy = match download_to_temp(source.as_ref(), key).await {
+                Ok(temp) => target.put_file(key, temp.path(), content_type).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = copy {
+                eprintln!("{}: {}", key, e);
+                failed += 1;
+                continue;
+            }
+            copied += 1;
+        } else {
+            skipped += 1;
+        }
+
+        if delete_source {
+            source.delete(key).await?;
+        }
+    }
+
+    println!(
+        "Copied {} object(s) from {} to {} storage, {} already there, {} failed",
+        copied, source.name(), target.name(), skipped, failed
+    );
+    if failed > 0 {
+        return Err(format!("{} object(s) could not be copied", failed).into());
+    }
+    Ok(())
+}
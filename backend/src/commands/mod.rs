@@ -0,0 +1,38 @@
+//! Maintenance commands run with `server <command>` instead of starting the
+//! HTTP server
+
+pub mod backfill_previews;
+pub mod migrate_storage;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(name = "server")]
+#[command(about = "Media ranking backend", long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Generate image thumbnails and audio waveforms for media uploaded before
+    /// previews existed
+    BackfillPreviews {
+        /// Regenerate previews that already exist
+        #[arg(long)]
+        force: bool,
+    },
+    /// Copy media files and previews to another storage backend
+    MigrateStorage {
+        /// Backend to copy from: local or s3
+        #[arg(long)]
+        from: String,
+        /// Backend to copy to: local or s3
+        #[arg(long)]
+        to: String,
+        /// Delete each object from the source once it is in the target
+        #[arg(long)]
+        delete_source: bool,
+    },
+}
@@ -9,6 +9,20 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool, sqlx::Error>
     Ok(pool)
 }
 
+/// Empty in-memory database with every migration applied
+#[cfg(test)]
+pub async fn test_pool() -> SqlitePool {
+    // Each connection to `sqlite::memory:` opens its own database, so keep just one
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Failed to open in-memory database");
+    run_migrations(&pool).await.expect("Failed to run migrations");
+
+    pool
+}
+
 pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
@@ -30,7 +44,7 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         CREATE TABLE IF NOT EXISTS media_files (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             filename TEXT NOT NULL,
-            file_path TEXT NOT NULL,
+            storage_key TEXT NOT NULL,
             media_type TEXT NOT NULL,
             mime_type TEXT NOT NULL,
             uploaded_at TEXT NOT NULL DEFAULT (datetime('now'))
@@ -109,6 +123,57 @@ pub async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     // Migration: Add loop_media column to tests table for media playback control
     add_loop_media_to_tests(pool).await?;
 
+    // Migration: Create admin_sessions table for refresh tokens and revocation
+    create_admin_sessions_table(pool).await?;
+
+    // Migration: Add role column to admins table for role-based permissions
+    add_role_to_admins(pool).await?;
+
+    // Migration: Create login_attempts table for brute-force protection
+    create_login_attempts_table(pool).await?;
+
+    // Migration: Add TOTP two-factor fields to admins and a recovery code table
+    add_totp_fields(pool).await?;
+
+    // Migration: Create admin_password_history table for the password reuse policy
+    create_password_history_table(pool).await?;
+
+    // Migration: Add suspended_at column to admins for suspending accounts without deleting them
+    add_suspended_at_to_admins(pool).await?;
+
+    // Migration: Create admin_api_keys table and attribute activity to API keys
+    create_api_keys_table(pool).await?;
+
+    // Migration: Link admins to SSO identities and track in-flight OIDC logins
+    add_oidc_fields(pool).await?;
+
+    // Migration: Add email to admins and tables for self-service password resets
+    add_email_and_password_resets(pool).await?;
+
+    // Migration: Add parent_id to categories for nested categories
+    add_parent_id_to_categories(pool).await?;
+
+    // Migration: Add archived_at to categories and media_files so data used by tests can be archived instead of deleted
+    add_archived_at_columns(pool).await?;
+
+    // Migration: Add rating instructions and star anchor labels to categories
+    add_rubric_to_categories(pool).await?;
+
+    // Migration: Add content_hash to media_files for deduplicating uploads
+    add_content_hash_to_media_files(pool).await?;
+
+    // Migration: Add metadata to media_files for technical details extracted on upload
+    add_metadata_to_media_files(pool).await?;
+
+    // Migration: Replace media_files.file_path with a storage key for pluggable storage backends
+    add_storage_key_to_media_files(pool).await?;
+
+    // Migration: Add file_size to media_files so library sizes don't need a storage lookup per file
+    add_file_size_to_media_files(pool).await?;
+
+    // Migration: Make content_hash unique, recording existing copies in duplicate_of
+    make_content_hash_unique(pool).await?;
+
     Ok(())
 }
 
@@ -384,3 +449,605 @@ async fn add_loop_media_to_tests(pool: &SqlitePool) -> Result<(), sqlx::Error> {
 
     Ok(())
 }
+
+async fn create_admin_sessions_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if admin_sessions table exists
+    let table_exists: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name='admin_sessions'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !table_exists {
+        sqlx::query(
+            r#"
+            CREATE TABLE admin_sessions (
+                id TEXT PRIMARY KEY,
+                admin_id INTEGER NOT NULL,
+                refresh_token_hash TEXT NOT NULL,
+                ip_address TEXT,
+                user_agent TEXT,
+                created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                last_used_at TEXT NOT NULL DEFAULT (datetime('now')),
+                expires_at TEXT NOT NULL,
+                revoked_at TEXT,
+                FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+            )
+            "#
+        )
+        .execute(pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX idx_admin_sessions_admin ON admin_sessions(admin_id)")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn add_role_to_admins(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('admins')
+         WHERE name = 'role'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // Existing admins keep full content access as editors; super admins map to super_admin
+        sqlx::query("ALTER TABLE admins ADD COLUMN role TEXT NOT NULL DEFAULT 'editor'")
+            .execute(pool)
+            .await?;
+
+        sqlx::query("UPDATE admins SET role = 'super_admin' WHERE is_super_admin = 1")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn create_login_attempts_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Keys are "user:<username>" or "ip:<address>" so both can be throttled independently
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS login_attempts (
+            key TEXT PRIMARY KEY,
+            failed_count INTEGER NOT NULL DEFAULT 0,
+            last_failed_at TEXT NOT NULL DEFAULT (datetime('now')),
+            locked_until TEXT
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn add_totp_fields(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let columns = [
+        ("totp_secret", "ALTER TABLE admins ADD COLUMN totp_secret TEXT"),
+        ("totp_pending_secret", "ALTER TABLE admins ADD COLUMN totp_pending_secret TEXT"),
+        ("totp_enabled", "ALTER TABLE admins ADD COLUMN totp_enabled INTEGER NOT NULL DEFAULT 0"),
+        ("totp_required", "ALTER TABLE admins ADD COLUMN totp_required INTEGER NOT NULL DEFAULT 0"),
+        ("totp_last_used_step", "ALTER TABLE admins ADD COLUMN totp_last_used_step INTEGER"),
+    ];
+
+    for (column, statement) in columns {
+        // Check if column exists
+        let has_column: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('admins')
+             WHERE name = ?"
+        )
+        .bind(column)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(false);
+
+        if !has_column {
+            sqlx::query(statement)
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS admin_recovery_codes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            admin_id INTEGER NOT NULL,
+            code_hash TEXT NOT NULL,
+            used_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn create_password_history_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Previous bcrypt hashes, newest first by id; pruned to PASSWORD_HISTORY_COUNT
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS admin_password_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            admin_id INTEGER NOT NULL,
+            password_hash TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_admin_password_history_admin ON admin_password_history(admin_id)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn add_suspended_at_to_admins(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if suspended_at column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('admins')
+         WHERE name = 'suspended_at'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // NULL means active; suspended admins keep their row so tests.created_by stays valid
+        sqlx::query("ALTER TABLE admins ADD COLUMN suspended_at TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn create_api_keys_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS admin_api_keys (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            admin_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            key_prefix TEXT NOT NULL,
+            key_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            last_used_at TEXT,
+            last_used_ip TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            revoked_at TEXT,
+            FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Check if api_key_id column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('activity_logs')
+         WHERE name = 'api_key_id'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        sqlx::query("ALTER TABLE activity_logs ADD COLUMN api_key_id INTEGER")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn add_oidc_fields(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if oidc_subject column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('admins')
+         WHERE name = 'oidc_subject'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // The provider's `sub` claim; NULL for local accounts
+        sqlx::query("ALTER TABLE admins ADD COLUMN oidc_subject TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_admins_oidc_subject ON admins(oidc_subject)")
+        .execute(pool)
+        .await?;
+
+    // state -> PKCE verifier and nonce between the redirect and the callback
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS oidc_auth_requests (
+            state TEXT PRIMARY KEY,
+            nonce TEXT NOT NULL,
+            code_verifier TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Single-use tickets handed to the frontend, exchanged for tokens
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS oidc_login_tickets (
+            ticket_hash TEXT PRIMARY KEY,
+            admin_id INTEGER NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn add_email_and_password_resets(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if email column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('admins')
+         WHERE name = 'email'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // Stored lowercased; used to deliver password reset links
+        sqlx::query("ALTER TABLE admins ADD COLUMN email TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_admins_email ON admins(email) WHERE email IS NOT NULL")
+        .execute(pool)
+        .await?;
+
+    // Single-use reset tokens, stored as SHA-256 hashes
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS admin_password_resets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            admin_id INTEGER NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            expires_at TEXT NOT NULL,
+            used_at TEXT,
+            requested_ip TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (admin_id) REFERENCES admins(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Every reset request, whether or not the email belongs to an admin, for rate limiting
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS password_reset_requests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            email TEXT NOT NULL,
+            ip_address TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_reset_requests_created ON password_reset_requests(created_at)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn add_parent_id_to_categories(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('categories')
+         WHERE name = 'parent_id'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // Deleting a parent turns its children into top-level categories
+        sqlx::query("ALTER TABLE categories ADD COLUMN parent_id INTEGER REFERENCES categories(id) ON DELETE SET NULL")
+            .execute(pool)
+            .await?;
+    }
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn add_archived_at_columns(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    for table in ["categories", "media_files"] {
+        // Check if column exists
+        let has_column: bool = sqlx::query_scalar(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info(?)
+             WHERE name = 'archived_at'"
+        )
+        .bind(table)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(false);
+
+        if !has_column {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN archived_at TEXT", table))
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn add_rubric_to_categories(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('categories')
+         WHERE name = 'instructions'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        sqlx::query("ALTER TABLE categories ADD COLUMN instructions TEXT")
+            .execute(pool)
+            .await?;
+
+        // JSON object of star value to label
+        sqlx::query("ALTER TABLE categories ADD COLUMN star_labels TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn add_content_hash_to_media_files(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'content_hash'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // SHA-256 of the file; NULL for files uploaded before this column existed until they are hashed
+        sqlx::query("ALTER TABLE media_files ADD COLUMN content_hash TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    // Replaced by a unique index in make_content_hash_unique once existing copies are set aside
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_media_files_content_hash ON media_files(content_hash)")
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+async fn add_metadata_to_media_files(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'metadata'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // JSON object of duration, dimensions, counts etc.; NULL when nothing could be read
+        sqlx::query("ALTER TABLE media_files ADD COLUMN metadata TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn add_storage_key_to_media_files(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'storage_key'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        sqlx::query("ALTER TABLE media_files ADD COLUMN storage_key TEXT")
+            .execute(pool)
+            .await?;
+    }
+
+    let has_file_path: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'file_path'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if has_file_path {
+        // All or nothing: the old column is only dropped once every row has a key
+        let mut tx = pool.begin().await?;
+
+        // Files were stored directly in UPLOAD_DIR, so the key is the file name
+        let files: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT id, file_path FROM media_files WHERE storage_key IS NULL"
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for (id, file_path) in files {
+            let storage_key = std::path::Path::new(&file_path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(&file_path)
+                .to_string();
+            sqlx::query("UPDATE media_files SET storage_key = ? WHERE id = ?")
+                .bind(&storage_key)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let missing: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM media_files WHERE storage_key IS NULL OR storage_key = ''"
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if missing > 0 {
+            return Err(sqlx::Error::Protocol(format!(
+                "{} media file(s) have no storage key; file_path was left in place",
+                missing
+            )));
+        }
+
+        sqlx::query("ALTER TABLE media_files DROP COLUMN file_path")
+            .execute(&mut *tx)
+            .await?;
+
+        // The column was added nullable; SQLite can't add NOT NULL afterwards
+        // without rebuilding the table, so enforce it with triggers instead
+        sqlx::query(
+            "CREATE TRIGGER IF NOT EXISTS media_files_storage_key_insert
+             BEFORE INSERT ON media_files WHEN NEW.storage_key IS NULL
+             BEGIN SELECT RAISE(ABORT, 'media_files.storage_key may not be NULL'); END"
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "CREATE TRIGGER IF NOT EXISTS media_files_storage_key_update
+             BEFORE UPDATE OF storage_key ON media_files WHEN NEW.storage_key IS NULL
+             BEGIN SELECT RAISE(ABORT, 'media_files.storage_key may not be NULL'); END"
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+async fn add_file_size_to_media_files(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'file_size'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // Bytes; NULL for files uploaded before this column existed until the
+        // startup backfill has asked storage for their size
+        sqlx::query("ALTER TABLE media_files ADD COLUMN file_size INTEGER")
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+async fn make_content_hash_unique(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    // Check if column exists
+    let has_column: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('media_files')
+         WHERE name = 'duplicate_of'"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !has_column {
+        // Copies of a file that were in the library before deduplication (or turned up in
+        // the hash backfill) point to the file holding the hash, so they can still be reported
+        sqlx::query("ALTER TABLE media_files ADD COLUMN duplicate_of INTEGER REFERENCES media_files(id) ON DELETE SET NULL")
+            .execute(pool)
+            .await?;
+    }
+
+    let is_unique: bool = sqlx::query_scalar(
+        "SELECT COUNT(*) > 0 FROM pragma_index_list('media_files')
+         WHERE name = 'idx_media_files_content_hash' AND \"unique\" = 1"
+    )
+    .fetch_one(pool)
+    .await
+    .unwrap_or(false);
+
+    if !is_unique {
+        let mut tx = pool.begin().await?;
+
+        // The oldest file with each content keeps the hash; the others become its duplicates
+        sqlx::query(
+            "UPDATE media_files SET
+                 duplicate_of = (SELECT MIN(original.id) FROM media_files original
+                                 WHERE original.content_hash = media_files.content_hash),
+                 content_hash = NULL
+             WHERE content_hash IS NOT NULL
+               AND id > (SELECT MIN(original.id) FROM media_files original
+                         WHERE original.content_hash = media_files.content_hash)"
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP INDEX IF EXISTS idx_media_files_content_hash")
+            .execute(&mut *tx)
+            .await?;
+
+        // Two uploads of the same content can't both be recorded, even when they race
+        sqlx::query(
+            "CREATE UNIQUE INDEX idx_media_files_content_hash ON media_files(content_hash)
+             WHERE content_hash IS NOT NULL"
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
@@ -12,9 +12,27 @@ pub enum AppError {
     Forbidden(String),
     NotFound(String),
     Conflict(String),
+    PayloadTooLarge(String),
+    TooManyRequests(String),
     InternalServerError(String),
 }
 
+impl AppError {
+    /// The message sent to the client as `details`
+    pub fn details(&self) -> &str {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::TooManyRequests(msg)
+            | AppError::InternalServerError(msg) => msg,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message, details) = match self {
@@ -38,6 +56,14 @@ impl IntoResponse for AppError {
                 tracing::warn!("Conflict: {}", msg);
                 (StatusCode::CONFLICT, "Conflict", Some(msg))
             }
+            AppError::PayloadTooLarge(msg) => {
+                tracing::warn!("Payload Too Large: {}", msg);
+                (StatusCode::PAYLOAD_TOO_LARGE, "Payload Too Large", Some(msg))
+            }
+            AppError::TooManyRequests(msg) => {
+                tracing::warn!("Too Many Requests: {}", msg);
+                (StatusCode::TOO_MANY_REQUESTS, "Too Many Requests", Some(msg))
+            }
             AppError::InternalServerError(msg) => {
                 tracing::error!("Internal Server Error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error", Some(msg))
@@ -52,3 +78,19 @@ impl IntoResponse for AppError {
         (status, body).into_response()
     }
 }
+
+/// For handlers that still answer with a bare status code
+impl From<AppError> for StatusCode {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
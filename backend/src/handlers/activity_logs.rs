@@ -14,6 +14,7 @@ pub struct ActivityLogQuery {
     pub user_email: Option<String>,
     pub action: Option<String>,
     pub entity_type: Option<String>,
+    pub api_key_id: Option<i64>,
     pub from_date: Option<String>,
     pub to_date: Option<String>,
     #[serde(default = "default_limit")]
@@ -54,6 +55,11 @@ pub async fn list_activity_logs(
         bind_values.push(entity_type.clone());
     }
 
+    if let Some(api_key_id) = params.api_key_id {
+        where_clauses.push("api_key_id = ?");
+        bind_values.push(api_key_id.to_string());
+    }
+
     if let Some(from_date) = &params.from_date {
         where_clauses.push("timestamp >= ?");
         bind_values.push(from_date.clone());
@@ -84,7 +90,10 @@ pub async fn list_activity_logs(
     // Get paginated logs
     let limit = params.limit.min(200); // Max 200 per request
     let logs_query = format!(
-        "SELECT * FROM activity_logs {} ORDER BY timestamp DESC LIMIT ? OFFSET ?",
+        "SELECT activity_logs.*, admin_api_keys.name AS api_key_name
+         FROM activity_logs
+         LEFT JOIN admin_api_keys ON admin_api_keys.id = activity_logs.api_key_id
+         {} ORDER BY timestamp DESC LIMIT ? OFFSET ?",
         where_clause
     );
 
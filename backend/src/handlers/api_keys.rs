@@ -0,0 +1,188 @@
+use crate::{
+    error::AppError,
+    models::{ApiKey, ApiKeyResponse, Claims, CreateApiKeyRequest, CreatedApiKeyResponse},
+    utils::{
+        activity_logger::log_activity,
+        api_keys::{default_lifetime_days, format_scopes, generate_key, max_lifetime_days, parse_scopes},
+        permissions::Permission,
+    },
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(key: ApiKey) -> Self {
+        let scopes = parse_scopes(&key.scopes);
+        ApiKeyResponse { key, scopes }
+    }
+}
+
+async fn admin_id_for(pool: &SqlitePool, username: &str) -> Result<i64, AppError> {
+    sqlx::query_scalar("SELECT id FROM admins WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?
+        .ok_or_else(|| AppError::NotFound("Admin account no longer exists".to_string()))
+}
+
+/// List the signed-in admin's API keys, including revoked and expired ones
+pub async fn list_api_keys(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<ApiKeyResponse>>, AppError> {
+    let admin_id = admin_id_for(&pool, &claims.sub).await?;
+
+    let keys = sqlx::query_as::<_, ApiKey>(
+        "SELECT * FROM admin_api_keys WHERE admin_id = ? ORDER BY created_at DESC, id DESC"
+    )
+    .bind(admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Json(keys.into_iter().map(ApiKeyResponse::from).collect()))
+}
+
+/// Create an API key for the signed-in admin. Scopes can't exceed the admin's own role.
+pub async fn create_api_key(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreatedApiKeyResponse>), AppError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("API key name is required".to_string()));
+    }
+
+    if payload.scopes.is_empty() {
+        return Err(AppError::BadRequest("Select at least one scope".to_string()));
+    }
+
+    if let Some(scope) = payload.scopes.iter().find(|scope| !claims.role.has_permission(**scope)) {
+        return Err(AppError::Forbidden(format!(
+            "Role '{}' cannot grant the '{}' scope",
+            claims.role.as_str(),
+            scope.as_str()
+        )));
+    }
+
+    let lifetime_days = payload.expires_in_days.unwrap_or_else(default_lifetime_days);
+    let max_days = max_lifetime_days();
+    if lifetime_days < 1 || lifetime_days > max_days {
+        return Err(AppError::BadRequest(format!(
+            "API key lifetime must be between 1 and {} days",
+            max_days
+        )));
+    }
+
+    let mut scopes: Vec<Permission> = Vec::new();
+    for scope in payload.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let admin_id = admin_id_for(&pool, &claims.sub).await?;
+    let generated = generate_key();
+
+    let result = sqlx::query(
+        "INSERT INTO admin_api_keys (admin_id, name, key_prefix, key_hash, scopes, expires_at)
+         VALUES (?, ?, ?, ?, ?, datetime('now', ?))"
+    )
+    .bind(admin_id)
+    .bind(name)
+    .bind(&generated.prefix)
+    .bind(&generated.hash)
+    .bind(format_scopes(&scopes))
+    .bind(format!("+{} days", lifetime_days))
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to create API key: {}", e)))?;
+
+    let key_id = result.last_insert_rowid();
+
+    let key = sqlx::query_as::<_, ApiKey>("SELECT * FROM admin_api_keys WHERE id = ?")
+        .bind(key_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    // Log key creation
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "create_api_key",
+        Some("api_key"),
+        Some(key_id),
+        Some(json!({
+            "name": name,
+            "scopes": format_scopes(&scopes),
+            "expires_at": key.expires_at,
+        })),
+        None,
+        None,
+    ).await.ok();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            api_key: generated.key,
+            key: key.into(),
+        }),
+    ))
+}
+
+/// Revoke an API key. Admins revoke their own keys; super admins can revoke anyone's.
+pub async fn revoke_api_key(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let key: Option<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT k.name, a.username, k.revoked_at
+         FROM admin_api_keys k
+         INNER JOIN admins a ON a.id = k.admin_id
+         WHERE k.id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    // Someone else's key looks the same as a missing one unless the caller manages admins
+    let (name, owner, revoked_at) = key
+        .filter(|(_, owner, _)| *owner == claims.sub || claims.has_permission(Permission::ManageAdmins))
+        .ok_or_else(|| AppError::NotFound(format!("API key with id {} does not exist", id)))?;
+
+    if revoked_at.is_some() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+
+    sqlx::query("UPDATE admin_api_keys SET revoked_at = datetime('now') WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke API key: {}", e)))?;
+
+    // Log key revocation
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "revoke_api_key",
+        Some("api_key"),
+        Some(id),
+        Some(json!({"name": name, "owner": owner})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
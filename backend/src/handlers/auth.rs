@@ -1,76 +1,307 @@
 use crate::{
-    models::{Admin, ChangePasswordRequest, CreateAdminRequest, LoginRequest, LoginResponse, Claims},
+    error::AppError,
+    models::{
+        AccountResponse, Admin, ChangePasswordRequest, Claims, CreateAdminRequest, LoginOutcome,
+        LoginRequest, LoginResponse, MfaChallengeResponse, PasswordResetResponse,
+        RefreshTokenRequest, UpdateAdminRoleRequest, UpdateEmailRequest,
+    },
     utils::{
-        auth::{create_jwt, hash_password, verify_password},
+        auth::{
+            access_token_ttl_secs, create_mfa_token, generate_temporary_password, hash_password,
+            verify_dummy_password, verify_password,
+        },
         activity_logger::log_activity,
+        api_keys::revoke_admin_api_keys,
+        login_throttle,
+        oidc,
+        password_policy,
+        password_reset::normalize_email,
+        permissions::Role,
+        request_meta::{client_ip, user_agent},
+        session::{create_session, refresh_session, revoke_admin_sessions, revoke_session},
+        session_cookies,
     },
 };
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, Method, StatusCode},
+    Json,
+};
 use serde_json::json;
 use sqlx::SqlitePool;
 
 pub async fn login(
     State(pool): State<SqlitePool>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, StatusCode> {
-    let admin = sqlx::query_as::<_, (i64, String, String, i64, String, i64)>(
-        "SELECT id, username, password_hash, is_super_admin, created_at, password_must_change FROM admins WHERE username = ?",
+) -> Result<(HeaderMap, Json<LoginOutcome>), AppError> {
+    let ip_address = client_ip(&headers);
+    let user_agent = user_agent(&headers);
+
+    if oidc::password_login_disabled() {
+        return Err(AppError::Forbidden("Password login is disabled, sign in with SSO".to_string()));
+    }
+
+    // Count the attempt before checking the password so a burst of parallel
+    // guesses can't all get past the throttle
+    let reservation = match login_throttle::reserve(&pool, &payload.username, ip_address.as_deref()).await {
+        Ok(reservation) => reservation,
+        Err(e) => {
+            // Log the rejected attempt
+            log_activity(
+                &pool,
+                None,
+                None,
+                "login_failed",
+                Some("admin"),
+                None,
+                Some(json!({"username": payload.username, "reason": "throttled"})),
+                ip_address.as_deref(),
+                user_agent.as_deref(),
+            ).await.ok();
+
+            return Err(e);
+        }
+    };
+
+    let admin = sqlx::query_as::<_, (i64, String, String, String, bool, bool, bool)>(
+        "SELECT id, username, password_hash, role, totp_enabled, totp_required, suspended_at IS NOT NULL
+         FROM admins WHERE username = ?",
     )
     .bind(&payload.username)
     .fetch_optional(&pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
 
-    if let Some((_, _, password_hash, is_super_admin_int, _, password_must_change_int)) = admin {
-        match verify_password(&payload.password, &password_hash) {
-            Ok(true) => {
-                let is_super_admin = is_super_admin_int == 1;
-                let password_must_change = password_must_change_int == 1;
-                let jwt_secret = std::env::var("JWT_SECRET")
-                    .unwrap_or_else(|_| "default-secret".to_string());
-                let token = create_jwt(&payload.username, is_super_admin, &jwt_secret)
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let verified = match &admin {
+        Some((_, _, password_hash, _, _, _, _)) => {
+            matches!(verify_password(&payload.password, password_hash), Ok(true))
+        }
+        None => {
+            verify_dummy_password(&payload.password);
+            false
+        }
+    };
 
-                // Log successful login
-                log_activity(
-                    &pool,
-                    Some(&payload.username),
-                    None,
-                    "login",
-                    Some("admin"),
-                    None,
-                    Some(json!({"is_super_admin": is_super_admin})),
-                    None,
-                    None,
-                ).await.ok();
+    let Some((admin_id, _, _, role, totp_enabled, totp_required, suspended)) = admin.filter(|_| verified) else {
+        // Log failed login
+        log_activity(
+            &pool,
+            None,
+            None,
+            "login_failed",
+            Some("admin"),
+            None,
+            Some(json!({
+                "username": payload.username,
+                "reason": "invalid_credentials",
+                "locked": reservation.locked,
+            })),
+            ip_address.as_deref(),
+            user_agent.as_deref(),
+        ).await.ok();
 
-                Ok(Json(LoginResponse {
-                    token,
-                    is_super_admin,
-                    password_must_change,
-                }))
-            }
-            _ => Err(StatusCode::UNAUTHORIZED),
-        }
-    } else {
-        Err(StatusCode::UNAUTHORIZED)
+        return Err(AppError::Unauthorized("Invalid username or password".to_string()));
+    };
+
+    // The password was right, so the attempt doesn't count
+    reservation.release(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+
+    // Only reveal the suspension to someone who knows the password
+    if suspended {
+        // Log the refused login
+        log_activity(
+            &pool,
+            None,
+            None,
+            "login_failed",
+            Some("admin"),
+            Some(admin_id),
+            Some(json!({"username": payload.username, "reason": "suspended"})),
+            ip_address.as_deref(),
+            user_agent.as_deref(),
+        ).await.ok();
+
+        return Err(AppError::Forbidden("Account is suspended".to_string()));
+    }
+
+    // Password is correct; hold back tokens until the second factor is verified.
+    // Failure counters are only cleared once the second factor succeeds, otherwise
+    // re-entering the password would reset the throttle on TOTP guesses.
+    if totp_enabled || totp_required {
+        let mfa_token = create_mfa_token(&payload.username, admin_id)
+            .map_err(|e| AppError::InternalServerError(format!("Failed to create token: {}", e)))?;
+
+        return Ok((HeaderMap::new(), Json(LoginOutcome::MfaRequired(MfaChallengeResponse {
+            mfa_required: true,
+            enrollment_required: !totp_enabled,
+            mfa_token,
+        }))));
     }
+
+    login_throttle::clear_user(&pool, &payload.username)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+
+    let role = Role::parse(&role)
+        .ok_or_else(|| AppError::InternalServerError(format!("Unknown role '{}'", role)))?;
+
+    let mut response = issue_login(
+        &pool,
+        admin_id,
+        &payload.username,
+        role,
+        ip_address.as_deref(),
+        user_agent.as_deref(),
+    ).await?;
+    let cookies = session_cookies::issue(&mut response);
+
+    Ok((cookies, Json(LoginOutcome::Authenticated(response))))
+}
+
+/// Final step of every successful login: open a session and record the login
+pub(crate) async fn issue_login(
+    pool: &SqlitePool,
+    admin_id: i64,
+    username: &str,
+    role: Role,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<LoginResponse, AppError> {
+    // The account may have been suspended between the password and TOTP steps
+    let suspended: bool = sqlx::query_scalar("SELECT suspended_at IS NOT NULL FROM admins WHERE id = ?")
+        .bind(admin_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+    if suspended {
+        return Err(AppError::Forbidden("Account is suspended".to_string()));
+    }
+
+    let password_must_change = password_policy::password_change_required(pool, admin_id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let tokens = create_session(pool, admin_id, username, role, password_must_change, ip_address, user_agent).await?;
+
+    // Log successful login
+    log_activity(
+        pool,
+        Some(username),
+        None,
+        "login",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"role": role.as_str(), "session_id": tokens.session_id})),
+        ip_address,
+        user_agent,
+    ).await.ok();
+
+    Ok(LoginResponse {
+        token: Some(tokens.access_token),
+        refresh_token: Some(tokens.refresh_token),
+        csrf_token: None,
+        expires_in: access_token_ttl_secs(),
+        is_super_admin: role == Role::SuperAdmin,
+        role,
+        password_must_change,
+        recovery_codes: None,
+    })
+}
+
+pub async fn refresh_token(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Json(payload): Json<RefreshTokenRequest>,
+) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
+    let ip_address = client_ip(&headers);
+    let user_agent = user_agent(&headers);
+
+    let refresh_token = match payload.refresh_token {
+        Some(refresh_token) => refresh_token,
+        None => {
+            // The refresh cookie is sent automatically, so it needs the CSRF check
+            let refresh_token = session_cookies::get(&headers, session_cookies::REFRESH_COOKIE)
+                .ok_or_else(|| AppError::Unauthorized("Missing refresh token".to_string()))?
+                .to_string();
+            session_cookies::verify_csrf(&Method::POST, &headers)?;
+            refresh_token
+        }
+    };
+
+    let (tokens, admin) = refresh_session(
+        &pool,
+        &refresh_token,
+        ip_address.as_deref(),
+        user_agent.as_deref(),
+    ).await?;
+
+    tracing::debug!("Rotated refresh token for admin {} (id {})", admin.username, admin.id);
+
+    let mut response = LoginResponse {
+        token: Some(tokens.access_token),
+        refresh_token: Some(tokens.refresh_token),
+        csrf_token: None,
+        expires_in: access_token_ttl_secs(),
+        is_super_admin: admin.role == Role::SuperAdmin,
+        role: admin.role,
+        password_must_change: admin.password_must_change,
+        recovery_codes: None,
+    };
+    let cookies = session_cookies::issue(&mut response);
+
+    Ok((cookies, Json(response)))
+}
+
+pub async fn logout(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    headers: HeaderMap,
+) -> Result<(HeaderMap, StatusCode), AppError> {
+    revoke_session(&pool, &claims.sid)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke session: {}", e)))?;
+
+    // Log logout
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "logout",
+        Some("admin"),
+        None,
+        Some(json!({"session_id": claims.sid})),
+        client_ip(&headers).as_deref(),
+        user_agent(&headers).as_deref(),
+    ).await.ok();
+
+    Ok((session_cookies::clear(), StatusCode::NO_CONTENT))
 }
 
 pub async fn create_admin(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Json(payload): Json<CreateAdminRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, AppError> {
+    password_policy::validate_new_password(&pool, None, &payload.password).await?;
+
     let password_hash = hash_password(&payload.password)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
 
-    let result = sqlx::query("INSERT INTO admins (username, password_hash, is_super_admin, password_must_change) VALUES (?, ?, 0, 1)")
+    let role = payload.role.unwrap_or(Role::Editor);
+    let email = check_email_available(&pool, payload.email.as_deref(), None).await?;
+
+    let result = sqlx::query("INSERT INTO admins (username, password_hash, is_super_admin, role, password_must_change, email) VALUES (?, ?, ?, ?, 1, ?)")
         .bind(&payload.username)
         .bind(&password_hash)
+        .bind(role == Role::SuperAdmin)
+        .bind(role.as_str())
+        .bind(&email)
         .execute(&pool)
         .await
-        .map_err(|_| StatusCode::CONFLICT)?;
+        .map_err(|_| AppError::Conflict(format!("Admin '{}' already exists", payload.username)))?;
 
     let admin_id = result.last_insert_rowid();
 
@@ -82,7 +313,7 @@ pub async fn create_admin(
         "create_admin",
         Some("admin"),
         Some(admin_id),
-        Some(json!({"username": payload.username})),
+        Some(json!({"username": payload.username, "role": role.as_str(), "email": email})),
         None,
         None,
     ).await.ok();
@@ -111,6 +342,11 @@ pub async fn delete_admin(
                 // Cannot delete super admin
                 Err(StatusCode::FORBIDDEN)
             } else {
+                // Revoke sessions first so outstanding access tokens stop working immediately
+                let revoked_sessions = revoke_admin_sessions(&pool, id)
+                    .await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
                 // Delete the admin
                 sqlx::query("DELETE FROM admins WHERE id = ?")
                     .bind(id)
@@ -126,7 +362,7 @@ pub async fn delete_admin(
                     "delete_admin",
                     Some("admin"),
                     Some(id),
-                    Some(json!({"username": username})),
+                    Some(json!({"username": username, "revoked_sessions": revoked_sessions})),
                     None,
                     None,
                 ).await.ok();
@@ -137,11 +373,394 @@ pub async fn delete_admin(
     }
 }
 
+pub async fn update_admin_role(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(payload): Json<UpdateAdminRoleRequest>,
+) -> Result<StatusCode, AppError> {
+    let admin: Option<(String, String)> = sqlx::query_as(
+        "SELECT username, role FROM admins WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (username, previous_role) = admin
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    // Prevent a super admin from locking themselves out of admin management
+    if username == claims.sub {
+        return Err(AppError::Forbidden("You cannot change your own role".to_string()));
+    }
+
+    sqlx::query("UPDATE admins SET role = ?, is_super_admin = ? WHERE id = ?")
+        .bind(payload.role.as_str())
+        .bind(payload.role == Role::SuperAdmin)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to update role: {}", e)))?;
+
+    // Log role change
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "update_admin_role",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": username, "previous_role": previous_role, "role": payload.role.as_str()})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unlock_admin(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let username: Option<String> = sqlx::query_scalar("SELECT username FROM admins WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let username = username
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    let was_locked = login_throttle::clear_user(&pool, &username)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to unlock admin: {}", e)))?;
+
+    // Log unlock
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "unlock_admin",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": username, "had_failed_attempts": was_locked})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Super admin: replace another admin's password with a random temporary one.
+/// The admin has to pick a new password on their next login; existing sessions end.
+pub async fn reset_admin_password(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<Json<PasswordResetResponse>, AppError> {
+    let admin: Option<(String, String)> = sqlx::query_as(
+        "SELECT username, password_hash FROM admins WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (username, previous_hash) = admin
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    if username == claims.sub {
+        return Err(AppError::Forbidden("Use change password to change your own password".to_string()));
+    }
+
+    let temporary_password = generate_temporary_password(password_policy::min_length().max(16));
+    let password_hash = hash_password(&temporary_password)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
+
+    sqlx::query(
+        "UPDATE admins SET password_hash = ?, password_must_change = 1, last_password_change = datetime('now') WHERE id = ?"
+    )
+    .bind(&password_hash)
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to reset password: {}", e)))?;
+
+    password_policy::record_previous_hash(&pool, id, &previous_hash)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to record password history: {}", e)))?;
+
+    let revoked_sessions = revoke_admin_sessions(&pool, id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+    let revoked_api_keys = revoke_admin_api_keys(&pool, id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke API keys: {}", e)))?;
+
+    // A reset is usually requested by someone who got locked out
+    login_throttle::clear_user(&pool, &username)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+
+    // Log password reset
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "reset_admin_password",
+        Some("admin"),
+        Some(id),
+        Some(json!({
+            "username": username,
+            "revoked_sessions": revoked_sessions,
+            "revoked_api_keys": revoked_api_keys
+        })),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(Json(PasswordResetResponse { temporary_password }))
+}
+
+/// Super admin: block an admin from signing in without deleting the account.
+/// Their tests stay attributed to them through `tests.created_by`.
+pub async fn suspend_admin(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let admin: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT username, suspended_at FROM admins WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (username, suspended_at) = admin
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    if username == claims.sub {
+        return Err(AppError::Forbidden("You cannot suspend your own account".to_string()));
+    }
+
+    if suspended_at.is_some() {
+        return Err(AppError::Conflict(format!("Admin '{}' is already suspended", username)));
+    }
+
+    sqlx::query("UPDATE admins SET suspended_at = datetime('now') WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to suspend admin: {}", e)))?;
+
+    let revoked_sessions = revoke_admin_sessions(&pool, id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+
+    // Log suspension
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "suspend_admin",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": username, "revoked_sessions": revoked_sessions})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Super admin: lift a suspension
+pub async fn reactivate_admin(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let admin: Option<(String, Option<String>)> = sqlx::query_as(
+        "SELECT username, suspended_at FROM admins WHERE id = ?"
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (username, suspended_at) = admin
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    let Some(suspended_at) = suspended_at else {
+        return Err(AppError::Conflict(format!("Admin '{}' is not suspended", username)));
+    };
+
+    sqlx::query("UPDATE admins SET suspended_at = NULL WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reactivate admin: {}", e)))?;
+
+    // Log reactivation
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "reactivate_admin",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": username, "suspended_at": suspended_at})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Validate an email address for an admin and make sure no other admin uses it.
+/// Returns the normalized address, or `None` when the address is being removed.
+async fn check_email_available(
+    pool: &SqlitePool,
+    email: Option<&str>,
+    admin_id: Option<i64>,
+) -> Result<Option<String>, AppError> {
+    let Some(email) = email.map(str::trim).filter(|email| !email.is_empty()) else {
+        return Ok(None);
+    };
+
+    let email = normalize_email(email)
+        .ok_or_else(|| AppError::BadRequest(format!("'{}' is not a valid email address", email)))?;
+
+    let taken: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM admins WHERE email = ? AND id IS NOT ?")
+        .bind(&email)
+        .bind(admin_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    if taken {
+        return Err(AppError::Conflict(format!("Another admin already uses {}", email)));
+    }
+
+    Ok(Some(email))
+}
+
+async fn set_admin_email(
+    pool: &SqlitePool,
+    admin_id: i64,
+    email: Option<&str>,
+) -> Result<Option<String>, AppError> {
+    let email = check_email_available(pool, email, Some(admin_id)).await?;
+
+    sqlx::query("UPDATE admins SET email = ? WHERE id = ?")
+        .bind(&email)
+        .bind(admin_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to update email: {}", e)))?;
+
+    Ok(email)
+}
+
+/// The signed-in admin's own account details
+pub async fn get_account(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<AccountResponse>, AppError> {
+    let account = sqlx::query_as::<_, (String, Option<String>, bool)>(
+        "SELECT username, email, oidc_subject IS NOT NULL FROM admins WHERE username = ?"
+    )
+    .bind(&claims.sub)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (username, email, sso) = account
+        .ok_or_else(|| AppError::NotFound("Admin account no longer exists".to_string()))?;
+
+    Ok(Json(AccountResponse { username, email, sso }))
+}
+
+/// Set or remove the signed-in admin's email address (used for password resets)
+pub async fn update_account_email(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateEmailRequest>,
+) -> Result<StatusCode, AppError> {
+    let admin_id: Option<i64> = sqlx::query_scalar("SELECT id FROM admins WHERE username = ?")
+        .bind(&claims.sub)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let admin_id = admin_id
+        .ok_or_else(|| AppError::NotFound("Admin account no longer exists".to_string()))?;
+
+    let email = set_admin_email(&pool, admin_id, payload.email.as_deref()).await?;
+
+    // Log email change
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "update_email",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"email": email})),
+        client_ip(&headers).as_deref(),
+        user_agent(&headers).as_deref(),
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Super admin: set or remove another admin's email address
+pub async fn update_admin_email(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(payload): Json<UpdateEmailRequest>,
+) -> Result<StatusCode, AppError> {
+    let username: Option<String> = sqlx::query_scalar("SELECT username FROM admins WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let username = username
+        .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", id)))?;
+
+    let email = set_admin_email(&pool, id, payload.email.as_deref()).await?;
+
+    // Log email change
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "update_admin_email",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": username, "email": email})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 pub async fn list_admins(
     State(pool): State<SqlitePool>,
 ) -> Result<Json<Vec<Admin>>, StatusCode> {
     let admins = sqlx::query_as::<_, Admin>(
-        "SELECT id, username, password_hash, is_super_admin, created_at, password_must_change, last_password_change FROM admins ORDER BY created_at DESC"
+        "SELECT a.id, a.username, a.password_hash, a.is_super_admin, a.role, a.created_at, a.password_must_change, a.last_password_change,
+                a.totp_enabled, a.totp_required, a.suspended_at, a.email, a.oidc_subject IS NOT NULL AS sso,
+                CASE WHEN la.locked_until > datetime('now') THEN la.locked_until END AS locked_until
+         FROM admins a
+         LEFT JOIN login_attempts la ON la.key = 'user:' || a.username
+         ORDER BY a.created_at DESC"
     )
     .fetch_all(&pool)
     .await
@@ -154,7 +773,7 @@ pub async fn change_password(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     Json(payload): Json<ChangePasswordRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, AppError> {
     // Get current admin
     let admin = sqlx::query_as::<_, (i64, String)>(
         "SELECT id, password_hash FROM admins WHERE username = ?"
@@ -162,19 +781,21 @@ pub async fn change_password(
     .bind(&claims.sub)
     .fetch_optional(&pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
 
     let (admin_id, current_password_hash) = match admin {
         Some((id, hash)) => (id, hash),
-        None => return Err(StatusCode::NOT_FOUND),
+        None => return Err(AppError::NotFound("Admin account no longer exists".to_string())),
     };
 
     // Verify current password
     match verify_password(&payload.current_password, &current_password_hash) {
         Ok(true) => {
+            password_policy::validate_new_password(&pool, Some(admin_id), &payload.new_password).await?;
+
             // Hash new password
             let new_password_hash = hash_password(&payload.new_password)
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
 
             // Update password and clear password_must_change flag
             sqlx::query(
@@ -184,7 +805,26 @@ pub async fn change_password(
             .bind(admin_id)
             .execute(&pool)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|e| AppError::InternalServerError(format!("Failed to update password: {}", e)))?;
+
+            password_policy::record_previous_hash(&pool, admin_id, &current_password_hash)
+                .await
+                .map_err(|e| AppError::InternalServerError(format!("Failed to record password history: {}", e)))?;
+
+            // Sign out every other session; the one making this request stays valid
+            sqlx::query(
+                "UPDATE admin_sessions SET revoked_at = datetime('now') WHERE admin_id = ? AND id != ? AND revoked_at IS NULL"
+            )
+            .bind(admin_id)
+            .bind(&claims.sid)
+            .execute(&pool)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+
+            // Keys minted with the old password go too
+            let revoked_api_keys = revoke_admin_api_keys(&pool, admin_id)
+                .await
+                .map_err(|e| AppError::InternalServerError(format!("Failed to revoke API keys: {}", e)))?;
 
             // Log password change
             log_activity(
@@ -194,13 +834,13 @@ pub async fn change_password(
                 "change_password",
                 Some("admin"),
                 Some(admin_id),
-                None,
+                Some(json!({"revoked_api_keys": revoked_api_keys})),
                 None,
                 None,
             ).await.ok();
 
             Ok(StatusCode::NO_CONTENT)
         }
-        _ => Err(StatusCode::UNAUTHORIZED),
+        _ => Err(AppError::Unauthorized("Current password is incorrect".to_string())),
     }
 }
@@ -1,32 +1,66 @@
 use crate::{
     error::AppError,
-    models::{Category, CreateCategoryRequest, Claims},
-    utils::activity_logger::log_activity,
+    models::{
+        Category, CategoryNode, CategoryStats, CategoryTestUsage, Claims, CreateCategoryRequest, DeleteQuery,
+        MergeCategoryRequest, MergeCategoryResponse, MimeTypeCount, UpdateCategoryRequest,
+    },
+    utils::{
+        activity_logger::log_activity,
+        category_tree::{build_tree, descendant_ids, validate_parent, CATEGORY_SUBTREES},
+        rubric::{normalize_instructions, normalize_star_labels},
+        test_references::{self, tests_using_category},
+    },
 };
-use axum::{extract::State, http::StatusCode, Json};
+use axum::{extract::{Query, State}, http::StatusCode, Json};
 use serde_json::json;
 use sqlx::SqlitePool;
+use std::collections::HashMap;
 
-pub async fn create_category(
-    State(pool): State<SqlitePool>,
-    axum::Extension(claims): axum::Extension<Claims>,
-    Json(payload): Json<CreateCategoryRequest>,
-) -> Result<StatusCode, AppError> {
-    // Validate media_type
+fn validate_media_type(media_type: &str) -> Result<(), AppError> {
     let valid_types = ["audio", "video", "image", "text"];
-    if !valid_types.contains(&payload.media_type.as_str()) {
+    if !valid_types.contains(&media_type) {
         return Err(AppError::BadRequest(format!(
             "Invalid media_type '{}'. Must be one of: audio, video, image, text",
-            payload.media_type
+            media_type
         )));
     }
+    Ok(())
+}
 
-    let result = sqlx::query("INSERT INTO categories (name, media_type) VALUES (?, ?)")
-        .bind(&payload.name)
-        .bind(&payload.media_type)
-        .execute(&pool)
+async fn fetch_category(pool: &SqlitePool, id: i64) -> Result<Category, AppError> {
+    sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = ? AND archived_at IS NULL")
+        .bind(id)
+        .fetch_optional(pool)
         .await
-        .map_err(|e| AppError::Conflict(format!("Category name already exists: {}", e)))?;
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?
+        .ok_or_else(|| AppError::NotFound(format!("Category with id {} does not exist", id)))
+}
+
+pub async fn create_category(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<CreateCategoryRequest>,
+) -> Result<StatusCode, AppError> {
+    validate_media_type(&payload.media_type)?;
+
+    if let Some(parent_id) = payload.parent_id {
+        validate_parent(&pool, None, parent_id, &payload.media_type).await?;
+    }
+
+    let instructions = normalize_instructions(payload.instructions)?;
+    let star_labels = normalize_star_labels(payload.star_labels)?;
+
+    let result = sqlx::query(
+        "INSERT INTO categories (name, media_type, parent_id, instructions, star_labels) VALUES (?, ?, ?, ?, ?)"
+    )
+    .bind(&payload.name)
+    .bind(&payload.media_type)
+    .bind(payload.parent_id)
+    .bind(&instructions)
+    .bind(star_labels.map(sqlx::types::Json))
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::Conflict(format!("Category name already exists: {}", e)))?;
 
     let category_id = result.last_insert_rowid();
 
@@ -38,7 +72,7 @@ pub async fn create_category(
         "create_category",
         Some("category"),
         Some(category_id),
-        Some(json!({"name": payload.name, "media_type": payload.media_type})),
+        Some(json!({"name": payload.name, "media_type": payload.media_type, "parent_id": payload.parent_id})),
         None,
         None,
     ).await.ok();
@@ -46,57 +80,493 @@ pub async fn create_category(
     Ok(StatusCode::CREATED)
 }
 
+/// All categories that are not archived as a tree: top-level categories with
+/// their subcategories nested under `children`, each level sorted by name
 pub async fn list_categories(
     State(pool): State<SqlitePool>,
-) -> Result<Json<Vec<Category>>, StatusCode> {
-    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY name")
+) -> Result<Json<Vec<CategoryNode>>, StatusCode> {
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE archived_at IS NULL ORDER BY name")
         .fetch_all(&pool)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(categories))
+    Ok(Json(build_tree(categories)))
+}
+
+/// Rename a category, change its media type, move it in the tree or edit its
+/// rating rubric. The type can only change when none of the media already in the
+/// category would contradict it.
+pub async fn update_category(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(payload): Json<UpdateCategoryRequest>,
+) -> Result<Json<Category>, AppError> {
+    let category = fetch_category(&pool, id).await?;
+
+    let name = match payload.name.as_deref().map(str::trim) {
+        Some("") => return Err(AppError::BadRequest("Category name cannot be empty".to_string())),
+        Some(name) => name.to_string(),
+        None => category.name.clone(),
+    };
+    let media_type = payload.media_type.unwrap_or_else(|| category.media_type.clone());
+    let parent_id = payload.parent_id.unwrap_or(category.parent_id);
+    let instructions = match payload.instructions {
+        Some(instructions) => normalize_instructions(instructions)?,
+        None => category.instructions.clone(),
+    };
+    let star_labels = match payload.star_labels {
+        Some(star_labels) => normalize_star_labels(star_labels)?,
+        None => category.star_labels.as_ref().map(|labels| labels.0.clone()),
+    };
+
+    if media_type != category.media_type {
+        validate_media_type(&media_type)?;
+
+        if !descendant_ids(&pool, id).await?.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "Cannot change the media type of '{}' while it has subcategories",
+                category.name
+            )));
+        }
+
+        let mismatched: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM media_files mf
+             INNER JOIN media_file_categories mfc ON mfc.media_file_id = mf.id
+             WHERE mfc.category_id = ? AND mf.media_type != ?"
+        )
+        .bind(id)
+        .bind(&media_type)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+        if mismatched > 0 {
+            return Err(AppError::Conflict(format!(
+                "Cannot change category '{}' to {}: it contains {} media file(s) of another type",
+                category.name, media_type, mismatched
+            )));
+        }
+    }
+
+    if let Some(parent_id) = parent_id {
+        if Some(parent_id) != category.parent_id || media_type != category.media_type {
+            validate_parent(&pool, Some(id), parent_id, &media_type).await?;
+        }
+    }
+
+    sqlx::query(
+        "UPDATE categories SET name = ?, media_type = ?, parent_id = ?, instructions = ?, star_labels = ? WHERE id = ?"
+    )
+    .bind(&name)
+    .bind(&media_type)
+    .bind(parent_id)
+    .bind(&instructions)
+    .bind(star_labels.clone().map(sqlx::types::Json))
+    .bind(id)
+    .execute(&pool)
+    .await
+    .map_err(|_| AppError::Conflict(format!("Category name '{}' already exists", name)))?;
+
+    // Log category update
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "update_category",
+        Some("category"),
+        Some(id),
+        Some(json!({
+            "previous_name": category.name,
+            "name": name,
+            "previous_media_type": category.media_type,
+            "media_type": media_type,
+            "previous_parent_id": category.parent_id,
+            "parent_id": parent_id,
+            "rubric_changed": instructions != category.instructions
+                || star_labels.as_ref() != category.star_labels.as_ref().map(|labels| &labels.0),
+        })),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(Json(fetch_category(&pool, id).await?))
+}
+
+/// Move every media and test association, and every subcategory, from one
+/// category into another, then delete the emptied category. Runs in a single
+/// transaction.
+pub async fn merge_category(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(payload): Json<MergeCategoryRequest>,
+) -> Result<Json<MergeCategoryResponse>, AppError> {
+    if payload.target_id == id {
+        return Err(AppError::BadRequest("Cannot merge a category into itself".to_string()));
+    }
+
+    let source = fetch_category(&pool, id).await?;
+    let target = fetch_category(&pool, payload.target_id).await?;
+
+    if source.media_type != target.media_type {
+        return Err(AppError::BadRequest(format!(
+            "Cannot merge {} category '{}' into {} category '{}'",
+            source.media_type, source.name, target.media_type, target.name
+        )));
+    }
+
+    // The subcategories would end up under one of their own descendants
+    if descendant_ids(&pool, source.id).await?.contains(&target.id) {
+        return Err(AppError::BadRequest(format!(
+            "Cannot merge '{}' into its own subcategory '{}'",
+            source.name, target.name
+        )));
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    sqlx::query("UPDATE categories SET parent_id = ? WHERE parent_id = ?")
+        .bind(target.id)
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to move subcategories: {}", e)))?;
+
+    // Links the target already has are skipped; the source's copies go with it below
+    let media_moved = sqlx::query(
+        "INSERT OR IGNORE INTO media_file_categories (media_file_id, category_id, assigned_at)
+         SELECT media_file_id, ?, assigned_at FROM media_file_categories WHERE category_id = ?"
+    )
+    .bind(target.id)
+    .bind(source.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to move media: {}", e)))?
+    .rows_affected();
+
+    let tests_moved = sqlx::query(
+        "INSERT OR IGNORE INTO test_categories (test_id, category_id)
+         SELECT test_id, ? FROM test_categories WHERE category_id = ?"
+    )
+    .bind(target.id)
+    .bind(source.id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to move tests: {}", e)))?
+    .rows_affected();
+
+    sqlx::query("DELETE FROM media_file_categories WHERE category_id = ?")
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    sqlx::query("DELETE FROM test_categories WHERE category_id = ?")
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    sqlx::query("DELETE FROM categories WHERE id = ?")
+        .bind(source.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to delete merged category: {}", e)))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    // Log category merge
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "merge_category",
+        Some("category"),
+        Some(target.id),
+        Some(json!({
+            "merged_id": source.id,
+            "merged_name": source.name,
+            "name": target.name,
+            "media_moved": media_moved,
+            "tests_moved": tests_moved,
+        })),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(Json(MergeCategoryResponse {
+        category: target,
+        media_moved,
+        tests_moved,
+    }))
+}
+
+/// Size and usage of a category: the media of the category and its
+/// subcategories by MIME type and their size on disk (archived media left
+/// out), the tests linked to it and the ratings those tests have collected
+pub async fn get_category_stats(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<Json<CategoryStats>, AppError> {
+    let category = fetch_category(&pool, id).await?;
+
+    let stats = collect_category_stats(&pool, vec![category], Some(id))
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::InternalServerError("Category stats are missing".to_string()))?;
+
+    Ok(Json(stats))
 }
 
+/// The same stats for every category in the library, ordered by name
+pub async fn list_category_stats(
+    State(pool): State<SqlitePool>,
+) -> Result<Json<Vec<CategoryStats>>, AppError> {
+    let categories = sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE archived_at IS NULL ORDER BY name")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Json(collect_category_stats(&pool, categories, None).await?))
+}
+
+/// Stats for the given categories from a fixed number of grouped queries;
+/// `only` narrows the queries when stats for a single category are wanted.
+/// Sizes come from `media_files.file_size`, so storage is never asked.
+async fn collect_category_stats(
+    pool: &SqlitePool,
+    categories: Vec<Category>,
+    only: Option<i64>,
+) -> Result<Vec<CategoryStats>, AppError> {
+    // Each file once per category, however many of its subcategories hold it
+    let subtree_media = format!(
+        "WITH RECURSIVE {},
+             subtree_media(category_id, media_file_id, file_size, mime_type) AS (
+                 SELECT DISTINCT t.root_id, mf.id, mf.file_size, mf.mime_type
+                 FROM category_subtree t
+                 INNER JOIN media_file_categories mfc ON mfc.category_id = t.id
+                 INNER JOIN media_files mf ON mf.id = mfc.media_file_id
+                 WHERE mf.archived_at IS NULL
+             )",
+        CATEGORY_SUBTREES
+    );
+
+    let sizes: Vec<(i64, i64, i64, i64)> = sqlx::query_as(&format!(
+        "{}
+         SELECT category_id, COUNT(*), COALESCE(SUM(file_size), 0), COUNT(*) - COUNT(file_size)
+         FROM subtree_media
+         GROUP BY category_id",
+        subtree_media
+    ))
+    .bind(only)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let mime_types: Vec<(i64, String, i64)> = sqlx::query_as(&format!(
+        "{}
+         SELECT category_id, mime_type, COUNT(*) AS count
+         FROM subtree_media
+         GROUP BY category_id, mime_type
+         ORDER BY count DESC, mime_type",
+        subtree_media
+    ))
+    .bind(only)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let tests: Vec<(i64, i64, String, String, i64)> = sqlx::query_as(
+        "SELECT tc.category_id, t.id, t.name, t.status,
+                (SELECT COUNT(*) FROM ratings r
+                 INNER JOIN test_users tu ON tu.id = r.test_user_id
+                 WHERE tu.test_id = t.id) AS ratings
+         FROM tests t
+         INNER JOIN test_categories tc ON tc.test_id = t.id
+         WHERE ?1 IS NULL OR tc.category_id = ?1
+         ORDER BY t.created_at DESC"
+    )
+    .bind(only)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let mut sizes: HashMap<i64, (i64, i64, i64)> = sizes
+        .into_iter()
+        .map(|(category_id, count, bytes, missing)| (category_id, (count, bytes, missing)))
+        .collect();
+
+    let mut by_mime_type: HashMap<i64, Vec<MimeTypeCount>> = HashMap::new();
+    for (category_id, mime_type, count) in mime_types {
+        by_mime_type.entry(category_id).or_default().push(MimeTypeCount { mime_type, count });
+    }
+
+    let mut tests_by_category: HashMap<i64, Vec<CategoryTestUsage>> = HashMap::new();
+    for (category_id, id, name, status, ratings) in tests {
+        tests_by_category.entry(category_id).or_default().push(CategoryTestUsage { id, name, status, ratings });
+    }
+
+    Ok(categories
+        .into_iter()
+        .map(|category| {
+            let (media_count, total_bytes, missing_files) = sizes.remove(&category.id).unwrap_or_default();
+            let tests = tests_by_category.remove(&category.id).unwrap_or_default();
+            CategoryStats {
+                media_count,
+                media_by_mime_type: by_mime_type.remove(&category.id).unwrap_or_default(),
+                total_bytes: total_bytes as u64,
+                missing_files,
+                total_ratings: tests.iter().map(|t| t.ratings).sum(),
+                tests,
+                category,
+            }
+        })
+        .collect())
+}
+
+/// Delete a category. Refused with a conflict listing the tests involved while
+/// open tests or collected ratings depend on its media; with `?force=true` such
+/// a category is archived instead: hidden from the admin lists but kept, with
+/// its media links, for the tests that use it.
 pub async fn delete_category(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path(id): axum::extract::Path<i64>,
-) -> Result<StatusCode, StatusCode> {
-    // Get category info before deleting
+    Query(params): Query<DeleteQuery>,
+) -> Result<StatusCode, AppError> {
+    // Get category info before deleting; archived categories can still be deleted for good
     let category: Option<(String, String)> = sqlx::query_as(
         "SELECT name, media_type FROM categories WHERE id = ?"
     )
     .bind(id)
     .fetch_optional(&pool)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (name, media_type) = category
+        .ok_or_else(|| AppError::NotFound(format!("Category with id {} does not exist", id)))?;
+
+    let tests = tests_using_category(&pool, id).await?;
+    if !tests.is_empty() && !params.force {
+        return Err(test_references::conflict(&format!("Category '{}'", name), &tests));
+    }
 
-    if let Some((name, media_type)) = category {
-        let result = sqlx::query("DELETE FROM categories WHERE id = ?")
+    let action = if tests.is_empty() {
+        sqlx::query("DELETE FROM categories WHERE id = ?")
             .bind(id)
             .execute(&pool)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        if result.rows_affected() == 0 {
-            Err(StatusCode::NOT_FOUND)
-        } else {
-            // Log category deletion
-            log_activity(
-                &pool,
-                Some(&claims.sub),
-                None,
-                "delete_category",
-                Some("category"),
-                Some(id),
-                Some(json!({"name": name, "media_type": media_type})),
-                None,
-                None,
-            ).await.ok();
-
-            Ok(StatusCode::NO_CONTENT)
-        }
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+        "delete_category"
     } else {
-        Err(StatusCode::NOT_FOUND)
+        sqlx::query("UPDATE categories SET archived_at = COALESCE(archived_at, datetime('now')) WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+        "archive_category"
+    };
+
+    let test_ids: Vec<i64> = tests.iter().map(|t| t.id).collect();
+
+    // Log category deletion or archiving
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        action,
+        Some("category"),
+        Some(id),
+        Some(json!({"name": name, "media_type": media_type, "test_ids": test_ids})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    async fn category(pool: &SqlitePool, name: &str, parent_id: Option<i64>) -> i64 {
+        sqlx::query_scalar("INSERT INTO categories (name, media_type, parent_id) VALUES (?, 'image', ?) RETURNING id")
+            .bind(name)
+            .bind(parent_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    async fn media(pool: &SqlitePool, name: &str, mime_type: &str, size: i64, category_ids: &[i64]) -> i64 {
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO media_files (filename, storage_key, media_type, mime_type, file_size)
+             VALUES (?1, ?1, 'image', ?2, ?3) RETURNING id"
+        )
+        .bind(name)
+        .bind(mime_type)
+        .bind(size)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        for category_id in category_ids {
+            sqlx::query("INSERT INTO media_file_categories (media_file_id, category_id) VALUES (?, ?)")
+                .bind(id)
+                .bind(category_id)
+                .execute(pool)
+                .await
+                .unwrap();
+        }
+        id
+    }
+
+    #[tokio::test]
+    async fn stats_cover_the_subtree_without_archived_media() {
+        let pool = test_pool().await;
+        let parent = category(&pool, "parent", None).await;
+        let child = category(&pool, "child", Some(parent)).await;
+        let grandchild = category(&pool, "grandchild", Some(child)).await;
+        let archived_child = category(&pool, "archived", Some(parent)).await;
+        sqlx::query("UPDATE categories SET archived_at = datetime('now') WHERE id = ?")
+            .bind(archived_child)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        media(&pool, "a.png", "image/png", 100, &[parent]).await;
+        // In two categories of the subtree, but counted once
+        media(&pool, "b.png", "image/png", 20, &[child, grandchild]).await;
+        media(&pool, "c.jpg", "image/jpeg", 3, &[grandchild]).await;
+        media(&pool, "hidden.png", "image/png", 1000, &[archived_child]).await;
+        let archived = media(&pool, "old.png", "image/png", 1000, &[child]).await;
+        sqlx::query("UPDATE media_files SET archived_at = datetime('now') WHERE id = ?")
+            .bind(archived)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let stats = list_category_stats(State(pool.clone())).await.unwrap().0;
+        let of = |id: i64| stats.iter().find(|s| s.category.id == id).unwrap();
+
+        assert_eq!((of(parent).media_count, of(parent).total_bytes), (3, 123));
+        assert_eq!((of(child).media_count, of(child).total_bytes), (2, 23));
+        assert_eq!((of(grandchild).media_count, of(grandchild).total_bytes), (2, 23));
+        let by_mime_type: Vec<(&str, i64)> = of(parent)
+            .media_by_mime_type
+            .iter()
+            .map(|m| (m.mime_type.as_str(), m.count))
+            .collect();
+        assert_eq!(by_mime_type, vec![("image/png", 2), ("image/jpeg", 1)]);
+
+        let single = get_category_stats(State(pool.clone()), axum::extract::Path(child)).await.unwrap().0;
+        assert_eq!((single.media_count, single.total_bytes), (2, 23));
     }
 }
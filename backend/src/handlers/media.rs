@@ -1,20 +1,29 @@
 use crate::{
     error::AppError,
-    models::{Category, Claims, MediaFile, MediaFileWithCategories, UpdateMediaCategoriesRequest},
-    utils::activity_logger::log_activity,
+    models::{
+        Category, Claims, DeleteQuery, DuplicateGroup, MediaFile, MediaFileWithCategories, MediaMetadata,
+        MediaUrlSignature, UpdateMediaCategoriesRequest, UploadFailure, UploadMediaResponse,
+    },
+    storage,
+    utils::{
+        activity_logger::log_activity,
+        file_type::{canonical_mime, ensure_allowed, verify_content, SNIFF_BYTES},
+        media_metadata,
+        media_urls,
+        previews,
+        test_references::{self, tests_using_media},
+        uploads::stream_to_temp,
+    },
 };
 use axum::{
-    body::Body,
-    extract::{Multipart, Query, State},
-    http::{header, StatusCode},
-    response::{IntoResponse, Response},
+    extract::{multipart::Field, Multipart, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
     Json,
 };
 use serde_json::json;
 use sqlx::SqlitePool;
 use std::{collections::HashMap, path::PathBuf};
-use tokio::{fs::File, io::AsyncWriteExt};
-use tokio_util::io::ReaderStream;
 
 fn determine_media_type(mime_type: &str) -> String {
     if mime_type.starts_with("audio/") {
@@ -30,24 +39,41 @@ fn determine_media_type(mime_type: &str) -> String {
     }
 }
 
+/// What to do with an uploaded file whose content is already in the library
+/// (multipart field `on_duplicate`)
+#[derive(Clone, Copy, PartialEq)]
+enum DuplicatePolicy {
+    /// Refuse the file with a conflict naming the existing record (default)
+    Reject,
+    /// Keep the existing record and add the upload's categories to it
+    Reuse,
+}
+
+/// Upload files into categories. Files whose SHA-256 matches an existing media
+/// file are rejected or merged into it, depending on `on_duplicate`; copies of
+/// the same content within one upload are stored once.
+///
+/// The form is read as a stream, so `category_ids` and `on_duplicate` must come
+/// before the file fields. Each file succeeds or fails on its own: the response
+/// lists the files that were stored, reused or refused. Only when no file could
+/// be stored is the first file's error returned instead.
 pub async fn upload_media(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     mut multipart: Multipart,
-) -> Result<StatusCode, AppError> {
+) -> Result<(StatusCode, Json<UploadMediaResponse>), AppError> {
     tracing::info!("Starting media upload");
 
-    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "../uploads".to_string());
-    std::fs::create_dir_all(&upload_dir).map_err(|e| {
-        AppError::InternalServerError(format!("Failed to create upload directory: {}", e))
-    })?;
-
     let mut category_ids: Vec<i64> = Vec::new();
-    let mut files_uploaded = 0;
     let mut uploaded_file_ids: Vec<i64> = Vec::new();
     let mut uploaded_filenames: Vec<String> = Vec::new();
+    let mut reused_file_ids: Vec<i64> = Vec::new();
+    let mut failures: Vec<(String, AppError)> = Vec::new();
+    let mut on_duplicate = DuplicatePolicy::Reject;
+    // Content hash -> media id of every file handled so far in this upload
+    let mut batch: HashMap<String, i64> = HashMap::new();
 
-    while let Some(field) = multipart
+    while let Some(mut field) = multipart
         .next_field()
         .await
         .map_err(|e| AppError::BadRequest(format!("Failed to parse multipart field: {}", e)))?
@@ -55,7 +81,12 @@ pub async fn upload_media(
         let name = field.name().unwrap_or("").to_string();
         tracing::debug!("Processing field: {}", name);
 
+        let files_seen = !uploaded_file_ids.is_empty() || !reused_file_ids.is_empty() || !failures.is_empty();
+
         if name == "category_ids" {
+            if files_seen {
+                return Err(AppError::BadRequest("category_ids must be provided before file fields".to_string()));
+            }
             let data = field.text().await.map_err(|e| {
                 AppError::BadRequest(format!("Failed to read category_ids field: {}", e))
             })?;
@@ -68,6 +99,22 @@ pub async fn upload_media(
             if category_ids.is_empty() {
                 return Err(AppError::BadRequest("At least one valid category_id is required".to_string()));
             }
+        } else if name == "on_duplicate" {
+            // Refuse rather than silently apply the policy to only some of the files
+            if files_seen {
+                return Err(AppError::BadRequest("on_duplicate must be provided before file fields".to_string()));
+            }
+            let data = field.text().await.map_err(|e| {
+                AppError::BadRequest(format!("Failed to read on_duplicate field: {}", e))
+            })?;
+            on_duplicate = match data.trim() {
+                "reject" => DuplicatePolicy::Reject,
+                "reuse" => DuplicatePolicy::Reuse,
+                other => return Err(AppError::BadRequest(format!(
+                    "Invalid on_duplicate '{}'. Must be one of: reject, reuse",
+                    other
+                ))),
+            };
         } else if name == "file" {
             let filename = field
                 .file_name()
@@ -76,120 +123,327 @@ pub async fn upload_media(
 
             tracing::debug!("Processing file: {}", filename);
 
-            let content_type = field
-                .content_type()
-                .unwrap_or("application/octet-stream")
-                .to_string();
-
-            let data = field.bytes().await.map_err(|e| {
-                AppError::BadRequest(format!("Failed to read file data for '{}': {}", filename, e))
-            })?;
-
             if category_ids.is_empty() {
                 return Err(AppError::BadRequest("category_ids must be provided before file fields".to_string()));
             }
 
-            // Verify all categories exist and get their media_types
-            for cat_id in &category_ids {
-                let category: Option<(String,)> = sqlx::query_as("SELECT media_type FROM categories WHERE id = ?")
-                    .bind(cat_id)
-                    .fetch_optional(&pool)
-                    .await
-                    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
-
-                let category_media_type = match category {
-                    Some((media_type,)) => media_type,
-                    None => return Err(AppError::BadRequest(format!("Category with id {} does not exist", cat_id))),
-                };
-
-                // Determine file's media type from mime type
-                let file_media_type = determine_media_type(&content_type);
-
-                // Validate media type matches category
-                if file_media_type != category_media_type {
-                    return Err(AppError::BadRequest(format!(
-                        "Cannot upload {} files to a {}-only category (id {}). File '{}' is type '{}'.",
-                        file_media_type, category_media_type, cat_id, filename, file_media_type
-                    )));
+            match store_upload(&pool, &mut field, &filename, &category_ids, on_duplicate, &mut batch).await {
+                Ok(StoredUpload::New(media_file_id)) => {
+                    uploaded_file_ids.push(media_file_id);
+                    uploaded_filenames.push(filename.clone());
+                    tracing::info!("Successfully uploaded file: {} with {} categories", filename, category_ids.len());
+                }
+                Ok(StoredUpload::Reused(media_file_id)) => {
+                    reused_file_ids.push(media_file_id);
+                    tracing::info!("File {} is a duplicate of media {}; reused it", filename, media_file_id);
+                }
+                Err(e) => {
+                    tracing::warn!("Upload of {} failed: {}", filename, e.details());
+                    failures.push((filename, e));
                 }
             }
+        }
+    }
 
-            let file_media_type = determine_media_type(&content_type);
+    if uploaded_file_ids.is_empty() && reused_file_ids.is_empty() {
+        return Err(match failures.into_iter().next() {
+            Some((_, e)) => e,
+            None => AppError::BadRequest("No files were uploaded".to_string()),
+        });
+    }
 
-            // Save file
-            let file_id = uuid::Uuid::new_v4();
-            let extension = PathBuf::from(&filename)
-                .extension()
-                .and_then(|e| e.to_str())
-                .unwrap_or("bin")
-                .to_string();
-            let stored_filename = format!("{}.{}", file_id, extension);
-            let file_path = format!("{}/{}", upload_dir, stored_filename);
+    tracing::info!(
+        "Upload completed: {} file(s) uploaded, {} reused, {} failed",
+        uploaded_file_ids.len(),
+        reused_file_ids.len(),
+        failures.len()
+    );
 
-            let mut file = File::create(&file_path)
-                .await
-                .map_err(|e| AppError::InternalServerError(format!("Failed to create file: {}", e)))?;
-            file.write_all(&data)
-                .await
-                .map_err(|e| AppError::InternalServerError(format!("Failed to write file: {}", e)))?;
-
-            // Save to database
-            let result = sqlx::query(
-                "INSERT INTO media_files (filename, file_path, media_type, mime_type) VALUES (?, ?, ?, ?)"
-            )
-            .bind(&filename)
-            .bind(&file_path)
-            .bind(&file_media_type)
-            .bind(&content_type)
-            .execute(&pool)
+    let failed: Vec<UploadFailure> = failures
+        .into_iter()
+        .map(|(filename, e)| UploadFailure { filename, error: e.details().to_string() })
+        .collect();
+
+    // Log media upload
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "upload_media",
+        Some("media"),
+        None,
+        Some(json!({
+            "files_count": uploaded_file_ids.len(),
+            "filenames": uploaded_filenames,
+            "category_ids": category_ids,
+            "reused_media_ids": reused_file_ids,
+            "failed_filenames": failed.iter().map(|f| f.filename.as_str()).collect::<Vec<_>>(),
+        })),
+        None,
+        None,
+    ).await.ok();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(UploadMediaResponse {
+            uploaded: uploaded_file_ids,
+            reused: reused_file_ids,
+            failed,
+        }),
+    ))
+}
+
+/// Where an uploaded file ended up
+enum StoredUpload {
+    /// A new media file
+    New(i64),
+    /// An existing media file, or one earlier in the same upload, with the same content
+    Reused(i64),
+}
+
+/// Check, store and record a single file of an upload
+async fn store_upload(
+    pool: &SqlitePool,
+    field: &mut Field<'_>,
+    filename: &str,
+    category_ids: &[i64],
+    on_duplicate: DuplicatePolicy,
+    batch: &mut HashMap<String, i64>,
+) -> Result<StoredUpload, AppError> {
+    // The declared type is only a claim by the client; it is checked against the content below
+    let content_type = canonical_mime(field.content_type().unwrap_or("application/octet-stream"));
+    ensure_allowed(&content_type, filename)?;
+
+    // Determine file's media type from mime type
+    let file_media_type = determine_media_type(&content_type);
+
+    // Verify all categories exist and accept this media type
+    for cat_id in category_ids {
+        let category: Option<(String,)> = sqlx::query_as("SELECT media_type FROM categories WHERE id = ? AND archived_at IS NULL")
+            .bind(cat_id)
+            .fetch_optional(pool)
             .await
-            .map_err(|e| AppError::InternalServerError(format!("Failed to save file to database: {}", e)))?;
-
-            let media_file_id = result.last_insert_rowid();
-
-            // Insert category associations
-            for cat_id in &category_ids {
-                sqlx::query(
-                    "INSERT INTO media_file_categories (media_file_id, category_id) VALUES (?, ?)"
-                )
-                .bind(media_file_id)
-                .bind(cat_id)
-                .execute(&pool)
-                .await
-                .map_err(|e| AppError::InternalServerError(format!("Failed to associate categories: {}", e)))?;
-            }
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+        let category_media_type = match category {
+            Some((media_type,)) => media_type,
+            None => return Err(AppError::BadRequest(format!("Category with id {} does not exist", cat_id))),
+        };
+
+        // Validate media type matches category
+        if file_media_type != category_media_type {
+            return Err(AppError::BadRequest(format!(
+                "Cannot upload {} files to a {}-only category (id {}). File '{}' is type '{}'.",
+                file_media_type, category_media_type, cat_id, filename, file_media_type
+            )));
+        }
+    }
+
+    // Stream to a temp file; it is removed again if anything below fails
+    let upload = stream_to_temp(field, filename).await?;
+    tracing::debug!("Received {} bytes for {}", upload.size, filename);
+
+    // Reject content that isn't what the client says it is (e.g. a renamed executable)
+    verify_content(&content_type, &upload.head(SNIFF_BYTES).await?, filename)?;
+
+    // A second copy in the same upload already has its categories
+    if let Some(media_file_id) = batch.get(&upload.sha256) {
+        return Ok(StoredUpload::Reused(*media_file_id));
+    }
+
+    if let Some(existing) = find_by_content_hash(pool, &upload.sha256).await? {
+        // The temp file is dropped; the existing record gets the categories
+        let media_file_id = use_existing(pool, existing, filename, &file_media_type, on_duplicate, category_ids).await?;
+        batch.insert(upload.sha256.clone(), media_file_id);
+        return Ok(StoredUpload::Reused(media_file_id));
+    }
+
+    // Save file, with an extension that fits the verified type
+    let file_id = uuid::Uuid::new_v4();
+    let known_extensions = mime_guess::get_mime_extensions_str(&content_type).unwrap_or(&[]);
+    let extension = PathBuf::from(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .filter(|e| known_extensions.contains(&e.as_str()))
+        .or_else(|| known_extensions.first().map(|e| e.to_string()))
+        .unwrap_or_else(|| "bin".to_string());
+    let storage_key = format!("{}.{}", file_id, extension);
+
+    // Duration, dimensions etc.; a file that can't be parsed is still accepted
+    let metadata = media_metadata::extract(upload.path(), &file_media_type, &content_type).await;
+
+    // Thumbnail or waveform; the admin pages fall back to the original without one
+    if let Err(e) = previews::generate(upload.path(), &storage_key, &file_media_type).await {
+        tracing::warn!("Failed to generate preview for {}: {}", filename, e);
+    }
 
-            uploaded_file_ids.push(media_file_id);
-            uploaded_filenames.push(filename.clone());
-            files_uploaded += 1;
-            tracing::info!("Successfully uploaded file: {} with {} categories", filename, category_ids.len());
+    let upload_hash = upload.sha256.clone();
+    let upload_size = upload.size;
+    if let Err(e) = upload.store(&storage_key, &content_type).await {
+        previews::remove(&storage_key).await;
+        return Err(AppError::InternalServerError(format!("Failed to store file: {}", e)));
+    }
+
+    // Save to database; the record and its categories go in together, and
+    // the stored file is removed if they can't be saved
+    let new_media_file = NewMediaFile {
+        filename,
+        storage_key: &storage_key,
+        media_type: &file_media_type,
+        mime_type: &content_type,
+        content_hash: &upload_hash,
+        file_size: upload_size,
+        metadata,
+    };
+    let inserted = insert_media_record(pool, new_media_file, category_ids).await;
+    if !matches!(inserted, Ok(Some(_))) {
+        let _ = storage::get().delete(&storage_key).await;
+        previews::remove(&storage_key).await;
+    }
+
+    match inserted? {
+        Some(media_file_id) => {
+            batch.insert(upload_hash, media_file_id);
+            Ok(StoredUpload::New(media_file_id))
+        }
+        None => {
+            // A concurrent upload of the same content was recorded first
+            let existing = find_by_content_hash(pool, &upload_hash).await?.ok_or_else(|| {
+                AppError::Conflict(format!("File '{}' was uploaded concurrently, please try again", filename))
+            })?;
+            let media_file_id = use_existing(pool, existing, filename, &file_media_type, on_duplicate, category_ids).await?;
+            batch.insert(upload_hash, media_file_id);
+            Ok(StoredUpload::Reused(media_file_id))
         }
     }
+}
 
-    if files_uploaded > 0 {
-        tracing::info!("Upload completed: {} file(s) uploaded", files_uploaded);
+/// The media file holding some content: id, filename and media type
+async fn find_by_content_hash(pool: &SqlitePool, content_hash: &str) -> Result<Option<(i64, String, String)>, AppError> {
+    sqlx::query_as("SELECT id, filename, media_type FROM media_files WHERE content_hash = ?")
+        .bind(content_hash)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))
+}
 
-        // Log media upload
-        log_activity(
-            &pool,
-            Some(&claims.sub),
-            None,
-            "upload_media",
-            Some("media"),
-            None,
-            Some(json!({
-                "files_count": files_uploaded,
-                "filenames": uploaded_filenames,
-                "category_ids": category_ids
-            })),
-            None,
-            None,
-        ).await.ok();
-
-        Ok(StatusCode::CREATED)
-    } else {
-        Err(AppError::BadRequest("No files were uploaded".to_string()))
+/// Apply the duplicate policy to an upload whose content is already in the library
+async fn use_existing(
+    pool: &SqlitePool,
+    (existing_id, existing_filename, existing_media_type): (i64, String, String),
+    filename: &str,
+    file_media_type: &str,
+    on_duplicate: DuplicatePolicy,
+    category_ids: &[i64],
+) -> Result<i64, AppError> {
+    if on_duplicate == DuplicatePolicy::Reject {
+        return Err(AppError::Conflict(format!(
+            "File '{}' is identical to '{}' (id {}) already in the library. Upload with on_duplicate=reuse to add the categories to that file instead",
+            filename, existing_filename, existing_id
+        )));
+    }
+    if existing_media_type != file_media_type {
+        return Err(AppError::Conflict(format!(
+            "File '{}' is identical to '{}' (id {}), which is stored as {} rather than {}",
+            filename, existing_filename, existing_id, existing_media_type, file_media_type
+        )));
+    }
+
+    reuse_media_record(pool, existing_id, category_ids).await?;
+    Ok(existing_id)
+}
+
+/// A stored upload about to be recorded in the library
+struct NewMediaFile<'a> {
+    filename: &'a str,
+    storage_key: &'a str,
+    media_type: &'a str,
+    mime_type: &'a str,
+    content_hash: &'a str,
+    file_size: u64,
+    metadata: Option<MediaMetadata>,
+}
+
+/// Record a stored upload with its categories. `None` when another media file
+/// already has the same content hash.
+async fn insert_media_record(
+    pool: &SqlitePool,
+    media_file: NewMediaFile<'_>,
+    category_ids: &[i64],
+) -> Result<Option<i64>, AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let media_file_id: Option<i64> = sqlx::query_scalar(
+        "INSERT INTO media_files (filename, storage_key, media_type, mime_type, content_hash, file_size, metadata) VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (content_hash) WHERE content_hash IS NOT NULL DO NOTHING
+         RETURNING id"
+    )
+    .bind(media_file.filename)
+    .bind(media_file.storage_key)
+    .bind(media_file.media_type)
+    .bind(media_file.mime_type)
+    .bind(media_file.content_hash)
+    .bind(media_file.file_size as i64)
+    .bind(media_file.metadata.map(sqlx::types::Json))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to save file to database: {}", e)))?;
+
+    let Some(media_file_id) = media_file_id else {
+        return Ok(None);
+    };
+
+    // Insert category associations
+    for cat_id in category_ids {
+        sqlx::query(
+            "INSERT INTO media_file_categories (media_file_id, category_id) VALUES (?, ?)"
+        )
+        .bind(media_file_id)
+        .bind(cat_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to associate categories: {}", e)))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Some(media_file_id))
+}
+
+/// Add categories to an existing media file for a duplicate upload. Uploading
+/// an archived file again brings it back into the library.
+async fn reuse_media_record(pool: &SqlitePool, media_file_id: i64, category_ids: &[i64]) -> Result<(), AppError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    for cat_id in category_ids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO media_file_categories (media_file_id, category_id) VALUES (?, ?)"
+        )
+        .bind(media_file_id)
+        .bind(cat_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to associate categories: {}", e)))?;
     }
+
+    sqlx::query("UPDATE media_files SET archived_at = NULL WHERE id = ?")
+        .bind(media_file_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))
 }
 
 pub async fn list_media(
@@ -200,7 +454,8 @@ pub async fn list_media(
     let category_id = params.get("category_id").and_then(|s| s.parse::<i64>().ok());
 
     // Base query for media files
-    let mut media_query = "SELECT * FROM media_files WHERE 1=1".to_string();
+    // Archived files stay out of the library
+    let mut media_query = "SELECT * FROM media_files WHERE archived_at IS NULL".to_string();
 
     if media_type.is_some() {
         media_query.push_str(" AND media_type = ?");
@@ -226,11 +481,12 @@ pub async fn list_media(
 
     // Fetch categories for each media file
     let mut result = Vec::new();
-    for media_file in media_files {
+    for mut media_file in media_files {
+        media_file.url_signature = Some(media_urls::sign(media_file.id));
         let categories = sqlx::query_as::<_, Category>(
             "SELECT c.* FROM categories c
              INNER JOIN media_file_categories mfc ON c.id = mfc.category_id
-             WHERE mfc.media_file_id = ?
+             WHERE mfc.media_file_id = ? AND c.archived_at IS NULL
              ORDER BY c.name"
         )
         .bind(media_file.id)
@@ -247,46 +503,113 @@ pub async fn list_media(
     Ok(Json(result))
 }
 
+/// Report media files with identical content, archived ones included: each
+/// file holding a content hash together with the copies marked as its
+/// duplicates. Files stored before hashes were recorded only show up once the
+/// startup backfill has hashed them.
+pub async fn list_duplicate_media(State(pool): State<SqlitePool>) -> Result<Json<Vec<DuplicateGroup>>, AppError> {
+    let media_files = sqlx::query_as::<_, MediaFile>(
+        "SELECT * FROM media_files
+         WHERE duplicate_of IS NOT NULL
+            OR id IN (SELECT duplicate_of FROM media_files WHERE duplicate_of IS NOT NULL)
+         ORDER BY COALESCE(duplicate_of, id), duplicate_of IS NOT NULL, id"
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    for mut media_file in media_files {
+        media_file.url_signature = Some(media_urls::sign(media_file.id));
+        let categories = sqlx::query_as::<_, Category>(
+            "SELECT c.* FROM categories c
+             INNER JOIN media_file_categories mfc ON c.id = mfc.category_id
+             WHERE mfc.media_file_id = ? AND c.archived_at IS NULL
+             ORDER BY c.name"
+        )
+        .bind(media_file.id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+        // Each group starts with the file holding the hash; the copies share it
+        match (media_file.content_hash.clone(), groups.last_mut()) {
+            (None, Some(group)) => {
+                media_file.content_hash = Some(group.content_hash.clone());
+                group.media.push(MediaFileWithCategories { media_file, categories });
+            }
+            (content_hash, _) => groups.push(DuplicateGroup {
+                content_hash: content_hash.unwrap_or_default(),
+                media: vec![MediaFileWithCategories { media_file, categories }],
+            }),
+        }
+    }
+
+    Ok(Json(groups))
+}
+
+/// Delete a media file and its file on disk. Refused with a conflict listing the
+/// tests involved while an open test includes it or ratings exist for it; with
+/// `?force=true` such a file is archived instead: hidden from the library but
+/// kept, with its file, for the tests and results that use it.
 pub async fn delete_media(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path(id): axum::extract::Path<i64>,
-) -> Result<StatusCode, StatusCode> {
-    // Get file path before deleting
+    Query(params): Query<DeleteQuery>,
+) -> Result<StatusCode, AppError> {
+    // Get file path before deleting; archived files can still be deleted for good
     let media: Option<MediaFile> = sqlx::query_as::<_, MediaFile>("SELECT * FROM media_files WHERE id = ?")
         .bind(id)
         .fetch_optional(&pool)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let media = media.ok_or_else(|| AppError::NotFound(format!("Media file with id {} does not exist", id)))?;
 
-    if let Some(media) = media {
-        // Delete file from disk
-        let _ = tokio::fs::remove_file(&media.file_path).await;
+    let tests = tests_using_media(&pool, id).await?;
+    if !tests.is_empty() && !params.force {
+        return Err(test_references::conflict(&format!("Media file '{}'", media.filename), &tests));
+    }
 
-        // Delete from database
+    let action = if tests.is_empty() {
+        // Delete from database, then the file from disk
         sqlx::query("DELETE FROM media_files WHERE id = ?")
             .bind(id)
             .execute(&pool)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
 
-        // Log media deletion
-        log_activity(
-            &pool,
-            Some(&claims.sub),
-            None,
-            "delete_media",
-            Some("media"),
-            Some(id),
-            Some(json!({"filename": media.filename, "media_type": media.media_type})),
-            None,
-            None,
-        ).await.ok();
-
-        Ok(StatusCode::NO_CONTENT)
+        if let Err(e) = storage::get().delete(&media.storage_key).await {
+            tracing::warn!("Failed to delete {} from storage: {}", media.storage_key, e);
+        }
+        previews::remove(&media.storage_key).await;
+        "delete_media"
     } else {
-        Err(StatusCode::NOT_FOUND)
-    }
+        sqlx::query("UPDATE media_files SET archived_at = COALESCE(archived_at, datetime('now')) WHERE id = ?")
+            .bind(id)
+            .execute(&pool)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+        "archive_media"
+    };
+
+    let test_ids: Vec<i64> = tests.iter().map(|t| t.id).collect();
+
+    // Log media deletion or archiving
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        action,
+        Some("media"),
+        Some(id),
+        Some(json!({"filename": media.filename, "media_type": media.media_type, "test_ids": test_ids})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn update_media_categories(
@@ -308,8 +631,11 @@ pub async fn update_media_categories(
 
     let media = media.unwrap();
 
-    // Delete existing category associations
-    sqlx::query("DELETE FROM media_file_categories WHERE media_file_id = ?")
+    // Delete existing category associations; links to archived categories stay for their tests
+    sqlx::query(
+        "DELETE FROM media_file_categories
+         WHERE media_file_id = ? AND category_id NOT IN (SELECT id FROM categories WHERE archived_at IS NOT NULL)"
+    )
         .bind(id)
         .execute(&pool)
         .await
@@ -318,7 +644,7 @@ pub async fn update_media_categories(
     // Insert new category associations
     for cat_id in &payload.category_ids {
         // Verify category exists
-        let category_exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM categories WHERE id = ?")
+        let category_exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM categories WHERE id = ? AND archived_at IS NULL")
             .bind(cat_id)
             .fetch_one(&pool)
             .await
@@ -355,32 +681,313 @@ pub async fn update_media_categories(
     Ok(StatusCode::NO_CONTENT)
 }
 
-pub async fn serve_media(
-    State(pool): State<SqlitePool>,
-    axum::extract::Path(id): axum::extract::Path<i64>,
+/// What to send for a media file: the file itself or one of its previews
+#[derive(Clone, Copy)]
+pub enum MediaObject {
+    /// The file, with byte ranges for seeking and validators for caching
+    File,
+    /// The thumbnail of an image (404 for other media or before it exists)
+    Thumbnail,
+    /// The waveform peaks of an audio file as JSON (404 for other media or
+    /// before it exists)
+    Waveform,
+}
+
+/// Serve a media file or one of its previews from storage. Callers decide who
+/// may see the file.
+pub async fn serve_object(
+    pool: &SqlitePool,
+    id: i64,
+    object: MediaObject,
+    headers: &HeaderMap,
 ) -> Result<Response, StatusCode> {
     let media: Option<MediaFile> = sqlx::query_as::<_, MediaFile>("SELECT * FROM media_files WHERE id = ?")
         .bind(id)
-        .fetch_optional(&pool)
+        .fetch_optional(pool)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    if let Some(media) = media {
-        let file = File::open(&media.file_path)
+    let media = media.ok_or(StatusCode::NOT_FOUND)?;
+    let (key, content_type) = match object {
+        MediaObject::File => (media.storage_key, media.mime_type),
+        MediaObject::Thumbnail => (previews::thumbnail_key(&media.storage_key), "image/png".to_string()),
+        MediaObject::Waveform => (previews::waveform_key(&media.storage_key), "application/json".to_string()),
+    };
+    storage::get().serve(&key, &content_type, headers).await
+}
+
+/// Legacy open route (PUBLIC_MEDIA_ROUTES): any media file by id, no token needed
+pub async fn serve_media(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_object(&pool, id, MediaObject::File, &headers).await
+}
+
+/// Serve a media file to an admin through a signed, expiring URL. Media
+/// elements can't send an Authorization header, so admin listings carry a
+/// `url_signature` for each file instead.
+async fn serve_signed(
+    pool: &SqlitePool,
+    id: i64,
+    object: MediaObject,
+    signature: &MediaUrlSignature,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    if !media_urls::verify(id, signature) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    serve_object(pool, id, object, headers).await
+}
+
+pub async fn serve_signed_media(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Query(signature): Query<MediaUrlSignature>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_signed(&pool, id, MediaObject::File, &signature, &headers).await
+}
+
+pub async fn serve_signed_thumbnail(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Query(signature): Query<MediaUrlSignature>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_signed(&pool, id, MediaObject::Thumbnail, &signature, &headers).await
+}
+
+pub async fn serve_signed_waveform(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Query(signature): Query<MediaUrlSignature>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_signed(&pool, id, MediaObject::Waveform, &signature, &headers).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, utils::permissions::Role};
+    use axum::{
+        body::Body,
+        extract::FromRequest,
+        http::Request,
+        response::IntoResponse,
+        Extension,
+    };
+
+    const BOUNDARY: &str = "test-boundary";
+
+    fn claims() -> Claims {
+        Claims {
+            sub: "admin".to_string(),
+            exp: usize::MAX,
+            is_super_admin: true,
+            role: Role::SuperAdmin,
+            sid: "session".to_string(),
+            password_must_change: false,
+            api_key_id: None,
+            scopes: None,
+        }
+    }
+
+    async fn setup() -> (SqlitePool, i64, i64) {
+        storage::init_for_tests();
+        let pool = test_pool().await;
+
+        let mut categories = Vec::new();
+        for name in ["first", "second"] {
+            let id: i64 = sqlx::query_scalar("INSERT INTO categories (name, media_type) VALUES (?, 'text') RETURNING id")
+                .bind(name)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            categories.push(id);
+        }
+
+        (pool, categories[0], categories[1])
+    }
+
+    /// A form field: `(name, Some(filename), value)` for files, `(name, None, value)` otherwise
+    type Part<'a> = (&'a str, Option<&'a str>, String);
+
+    /// Send a multipart upload and return the status and JSON body
+    async fn upload(pool: &SqlitePool, parts: Vec<Part<'_>>) -> (StatusCode, serde_json::Value) {
+        let mut body = String::new();
+        for (name, filename, value) in parts {
+            body.push_str(&format!("--{}\r\n", BOUNDARY));
+            match filename {
+                Some(filename) => body.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n",
+                    name, filename
+                )),
+                None => body.push_str(&format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name)),
+            }
+            body.push_str(&value);
+            body.push_str("\r\n");
+        }
+        body.push_str(&format!("--{}--\r\n", BOUNDARY));
+
+        let request = Request::post("/api/admin/media/upload")
+            .header("content-type", format!("multipart/form-data; boundary={}", BOUNDARY))
+            .body(Body::from(body))
+            .unwrap();
+        let multipart = Multipart::from_request(request, &()).await.unwrap();
+
+        let response = upload_media(State(pool.clone()), Extension(claims()), multipart)
             .await
-            .map_err(|_| StatusCode::NOT_FOUND)?;
+            .into_response();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    fn field(name: &str, value: impl ToString) -> Part<'_> {
+        (name, None, value.to_string())
+    }
+
+    fn file<'a>(filename: &'a str, content: &str) -> Part<'a> {
+        ("file", Some(filename), content.to_string())
+    }
+
+    async fn category_ids_of(pool: &SqlitePool, media_file_id: i64) -> Vec<i64> {
+        sqlx::query_scalar("SELECT category_id FROM media_file_categories WHERE media_file_id = ? ORDER BY category_id")
+            .bind(media_file_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_duplicates_by_default() {
+        let (pool, first, _) = setup().await;
+        let content = "rejects duplicates by default";
+
+        let (status, body) = upload(&pool, vec![field("category_ids", first), file("a.txt", content)]).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["uploaded"].as_array().unwrap().len(), 1);
+
+        let (status, body) = upload(&pool, vec![field("category_ids", first), file("b.txt", content)]).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert!(body["details"].as_str().unwrap().contains("a.txt"));
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM media_files").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn reuse_adds_categories_to_existing_file() {
+        let (pool, first, second) = setup().await;
+        let content = "reuse adds categories";
 
-        let stream = ReaderStream::new(file);
-        let body = Body::from_stream(stream);
+        let (_, body) = upload(&pool, vec![field("category_ids", first), file("a.txt", content)]).await;
+        let media_file_id = body["uploaded"][0].as_i64().unwrap();
 
-        Ok((
-            [
-                (header::CONTENT_TYPE, media.mime_type),
-                (header::CACHE_CONTROL, "no-cache, no-store, must-revalidate".to_string()),
+        let (status, body) = upload(
+            &pool,
+            vec![field("category_ids", second), field("on_duplicate", "reuse"), file("b.txt", content)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["uploaded"], json!([]));
+        assert_eq!(body["reused"], json!([media_file_id]));
+        assert_eq!(category_ids_of(&pool, media_file_id).await, vec![first, second]);
+    }
+
+    #[tokio::test]
+    async fn reports_each_file() {
+        let (pool, first, _) = setup().await;
+        upload(&pool, vec![field("category_ids", first), file("existing.txt", "reports each file: old")]).await;
+
+        let (status, body) = upload(
+            &pool,
+            vec![
+                field("category_ids", first),
+                file("new.txt", "reports each file: new"),
+                file("copy.txt", "reports each file: new"),
+                file("existing-again.txt", "reports each file: old"),
             ],
-            body,
-        ).into_response())
-    } else {
-        Err(StatusCode::NOT_FOUND)
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::CREATED);
+        let new_id = body["uploaded"][0].as_i64().unwrap();
+        // A copy within the same upload is stored once
+        assert_eq!(body["reused"], json!([new_id]));
+        assert_eq!(body["failed"][0]["filename"], "existing-again.txt");
+        assert!(body["failed"][0]["error"].as_str().unwrap().contains("existing.txt"));
+    }
+
+    #[tokio::test]
+    async fn refuses_options_after_files() {
+        let (pool, first, second) = setup().await;
+
+        let (status, _) = upload(
+            &pool,
+            vec![field("category_ids", first), file("a.txt", "options after files"), field("on_duplicate", "reuse")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = upload(
+            &pool,
+            vec![field("category_ids", first), file("a.txt", "categories after files"), field("category_ids", second)],
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_duplicate_policy() {
+        let (pool, first, _) = setup().await;
+
+        let (status, body) = upload(
+            &pool,
+            vec![field("category_ids", first), field("on_duplicate", "keep"), file("a.txt", "unknown policy")],
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["details"].as_str().unwrap().contains("keep"));
+    }
+
+    #[tokio::test]
+    async fn rejects_file_over_the_size_limit() {
+        let (pool, first, _) = setup().await;
+        // Only this test uploads more than a few bytes
+        std::env::set_var("MAX_UPLOAD_FILE_MB", "1");
+
+        let content = "x".repeat(1024 * 1024 + 1);
+        let (status, body) = upload(&pool, vec![field("category_ids", first), file("big.txt", &content)]).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(body["details"].as_str().unwrap().contains("big.txt"));
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM media_files").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn second_insert_of_same_content_is_not_recorded() {
+        let (pool, first, _) = setup().await;
+        let record = |storage_key| NewMediaFile {
+            filename: "a.txt",
+            storage_key,
+            media_type: "text",
+            mime_type: "text/plain",
+            content_hash: "same-hash",
+            file_size: 1,
+            metadata: None,
+        };
+
+        // As when two uploads of the same file both pass the duplicate check
+        let winner = insert_media_record(&pool, record("one.txt"), &[first]).await.unwrap();
+        assert!(winner.is_some());
+        assert_eq!(insert_media_record(&pool, record("two.txt"), &[first]).await.unwrap(), None);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM media_file_categories").fetch_one(&pool).await.unwrap();
+        assert_eq!(count, 1);
     }
 }
@@ -0,0 +1,533 @@
+use crate::{
+    error::AppError,
+    handlers::auth::issue_login,
+    models::{
+        Claims, LoginResponse, MfaClaims, MfaLoginRequest, MfaTokenRequest, RecoveryCodesResponse,
+        TotpCodeRequest, TotpRequirementRequest, TotpSetupResponse, TotpStatusResponse,
+    },
+    utils::{
+        activity_logger::log_activity,
+        auth::{hash_token, verify_mfa_token},
+        login_throttle,
+        permissions::Role,
+        request_meta::{client_ip, user_agent},
+        session::revoke_admin_sessions,
+        session_cookies,
+        totp,
+    },
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+/// Number of recovery codes handed out on enrollment or regeneration
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(sqlx::FromRow)]
+struct TotpState {
+    id: i64,
+    username: String,
+    role: String,
+    totp_secret: Option<String>,
+    totp_pending_secret: Option<String>,
+    totp_enabled: bool,
+    totp_required: bool,
+    totp_last_used_step: Option<i64>,
+}
+
+fn totp_issuer() -> String {
+    std::env::var("TOTP_ISSUER").unwrap_or_else(|_| "Media Ranking".to_string())
+}
+
+async fn load_totp_state_by_id(pool: &SqlitePool, admin_id: i64) -> Result<TotpState, AppError> {
+    sqlx::query_as::<_, TotpState>(
+        "SELECT id, username, role, totp_secret, totp_pending_secret,
+                totp_enabled, totp_required, totp_last_used_step
+         FROM admins WHERE id = ?"
+    )
+    .bind(admin_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?
+    .ok_or_else(|| AppError::NotFound(format!("Admin with id {} does not exist", admin_id)))
+}
+
+async fn load_totp_state_by_username(pool: &SqlitePool, username: &str) -> Result<TotpState, AppError> {
+    let admin_id: Option<i64> = sqlx::query_scalar("SELECT id FROM admins WHERE username = ?")
+        .bind(username)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let admin_id = admin_id
+        .ok_or_else(|| AppError::NotFound("Admin account no longer exists".to_string()))?;
+
+    load_totp_state_by_id(pool, admin_id).await
+}
+
+fn decode_mfa_token(token: &str) -> Result<MfaClaims, AppError> {
+    verify_mfa_token(token)
+        .map_err(|e| AppError::Unauthorized(format!("Invalid or expired MFA token: {}", e)))
+}
+
+/// Check a TOTP code and remember its time step so the same code can't be replayed
+async fn verify_and_consume_code(
+    pool: &SqlitePool,
+    state: &TotpState,
+    secret: &str,
+    code: &str,
+) -> Result<bool, AppError> {
+    let Some(step) = totp::verify_code(secret, code, state.totp_last_used_step) else {
+        return Ok(false);
+    };
+
+    sqlx::query("UPDATE admins SET totp_last_used_step = ? WHERE id = ?")
+        .bind(step)
+        .bind(state.id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(true)
+}
+
+/// Mark a recovery code as used; returns false if it doesn't exist or was already used
+async fn consume_recovery_code(pool: &SqlitePool, admin_id: i64, code: &str) -> Result<bool, AppError> {
+    let result = sqlx::query(
+        "UPDATE admin_recovery_codes SET used_at = datetime('now')
+         WHERE admin_id = ? AND code_hash = ? AND used_at IS NULL"
+    )
+    .bind(admin_id)
+    .bind(hash_token(&totp::normalize_recovery_code(code)))
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Replace an admin's recovery codes with a fresh set and return them in plaintext (shown once)
+async fn replace_recovery_codes(pool: &SqlitePool, admin_id: i64) -> Result<Vec<String>, AppError> {
+    let codes = totp::generate_recovery_codes(RECOVERY_CODE_COUNT);
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = ?")
+        .bind(admin_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    for code in &codes {
+        sqlx::query("INSERT INTO admin_recovery_codes (admin_id, code_hash) VALUES (?, ?)")
+            .bind(admin_id)
+            .bind(hash_token(&totp::normalize_recovery_code(code)))
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(codes)
+}
+
+/// Promote the pending secret to the active one and issue recovery codes
+async fn activate_totp(pool: &SqlitePool, state: &TotpState) -> Result<Vec<String>, AppError> {
+    sqlx::query(
+        "UPDATE admins SET totp_secret = totp_pending_secret, totp_pending_secret = NULL, totp_enabled = 1
+         WHERE id = ? AND totp_pending_secret IS NOT NULL"
+    )
+    .bind(state.id)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to enable TOTP: {}", e)))?;
+
+    replace_recovery_codes(pool, state.id).await
+}
+
+async fn start_enrollment(pool: &SqlitePool, state: &TotpState) -> Result<TotpSetupResponse, AppError> {
+    if state.totp_enabled {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = totp::generate_secret();
+
+    sqlx::query("UPDATE admins SET totp_pending_secret = ? WHERE id = ?")
+        .bind(&secret)
+        .bind(state.id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to start enrollment: {}", e)))?;
+
+    Ok(TotpSetupResponse {
+        otpauth_uri: totp::otpauth_uri(&totp_issuer(), &state.username, &secret),
+        secret,
+    })
+}
+
+/// Begin TOTP enrollment during login when a super admin has made it mandatory
+pub async fn login_mfa_setup(
+    State(pool): State<SqlitePool>,
+    Json(payload): Json<MfaTokenRequest>,
+) -> Result<Json<TotpSetupResponse>, AppError> {
+    let mfa_claims = decode_mfa_token(&payload.mfa_token)?;
+    let state = load_totp_state_by_id(&pool, mfa_claims.admin_id).await?;
+
+    if !state.totp_required {
+        return Err(AppError::BadRequest("Two-factor enrollment is not required for this account".to_string()));
+    }
+
+    Ok(Json(start_enrollment(&pool, &state).await?))
+}
+
+/// Second login step: exchange the pre-auth token and a TOTP or recovery code for a session
+pub async fn login_mfa(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Json(payload): Json<MfaLoginRequest>,
+) -> Result<(HeaderMap, Json<LoginResponse>), AppError> {
+    let ip_address = client_ip(&headers);
+    let user_agent = user_agent(&headers);
+
+    let mfa_claims = decode_mfa_token(&payload.mfa_token)?;
+    let state = load_totp_state_by_id(&pool, mfa_claims.admin_id).await?;
+
+    if !state.totp_enabled && !state.totp_required {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled for this account".to_string()));
+    }
+    if !state.totp_enabled && (payload.code.is_none() || state.totp_pending_secret.is_none()) {
+        return Err(AppError::BadRequest("Start enrollment and submit a code from your authenticator app".to_string()));
+    }
+
+    // Count the attempt before checking the code, like the password step
+    let reservation = login_throttle::reserve(&pool, &state.username, ip_address.as_deref()).await?;
+
+    let mut recovery_codes = None;
+    let mut used_recovery_code = false;
+
+    let verified = if state.totp_enabled {
+        match (&payload.code, &payload.recovery_code, &state.totp_secret) {
+            (Some(code), _, Some(secret)) => verify_and_consume_code(&pool, &state, secret, code).await?,
+            (None, Some(recovery_code), _) => {
+                used_recovery_code = consume_recovery_code(&pool, state.id, recovery_code).await?;
+                used_recovery_code
+            }
+            _ => false,
+        }
+    } else if let (Some(code), Some(pending)) = (&payload.code, &state.totp_pending_secret) {
+        // Enforced enrollment: the first valid code confirms the pending secret
+
+        let confirmed = verify_and_consume_code(&pool, &state, pending, code).await?;
+        if confirmed {
+            recovery_codes = Some(activate_totp(&pool, &state).await?);
+
+            // Log enrollment
+            log_activity(
+                &pool,
+                Some(&state.username),
+                None,
+                "totp_enroll",
+                Some("admin"),
+                Some(state.id),
+                Some(json!({"during_login": true})),
+                ip_address.as_deref(),
+                user_agent.as_deref(),
+            ).await.ok();
+        }
+        confirmed
+    } else {
+        false
+    };
+
+    if !verified {
+        // Log failed second factor
+        log_activity(
+            &pool,
+            None,
+            None,
+            "login_failed",
+            Some("admin"),
+            Some(state.id),
+            Some(json!({
+                "username": state.username,
+                "reason": "invalid_totp",
+                "locked": reservation.locked,
+            })),
+            ip_address.as_deref(),
+            user_agent.as_deref(),
+        ).await.ok();
+
+        return Err(AppError::Unauthorized("Invalid authentication code".to_string()));
+    }
+
+    if used_recovery_code {
+        // Log recovery code use
+        log_activity(
+            &pool,
+            Some(&state.username),
+            None,
+            "totp_recovery_code_used",
+            Some("admin"),
+            Some(state.id),
+            None,
+            ip_address.as_deref(),
+            user_agent.as_deref(),
+        ).await.ok();
+    }
+
+    reservation.release(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+    login_throttle::clear_user(&pool, &state.username)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+
+    let role = Role::parse(&state.role)
+        .ok_or_else(|| AppError::InternalServerError(format!("Unknown role '{}'", state.role)))?;
+
+    let mut response = issue_login(
+        &pool,
+        state.id,
+        &state.username,
+        role,
+        ip_address.as_deref(),
+        user_agent.as_deref(),
+    ).await?;
+    response.recovery_codes = recovery_codes;
+    let cookies = session_cookies::issue(&mut response);
+
+    Ok((cookies, Json(response)))
+}
+
+/// Two-factor status for the signed-in admin
+pub async fn totp_status(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<TotpStatusResponse>, AppError> {
+    let state = load_totp_state_by_username(&pool, &claims.sub).await?;
+
+    let recovery_codes_remaining: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM admin_recovery_codes WHERE admin_id = ? AND used_at IS NULL"
+    )
+    .bind(state.id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Json(TotpStatusResponse {
+        enabled: state.totp_enabled,
+        required: state.totp_required,
+        recovery_codes_remaining,
+    }))
+}
+
+/// Start voluntary TOTP enrollment for the signed-in admin
+pub async fn setup_totp(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<TotpSetupResponse>, AppError> {
+    let state = load_totp_state_by_username(&pool, &claims.sub).await?;
+    Ok(Json(start_enrollment(&pool, &state).await?))
+}
+
+/// Confirm enrollment with a first code from the authenticator app
+pub async fn enable_totp(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let state = load_totp_state_by_username(&pool, &claims.sub).await?;
+
+    if state.totp_enabled {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let pending = state
+        .totp_pending_secret
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Start enrollment before confirming a code".to_string()))?;
+
+    if !verify_and_consume_code(&pool, &state, &pending, &payload.code).await? {
+        return Err(AppError::BadRequest("Invalid authentication code".to_string()));
+    }
+
+    let recovery_codes = activate_totp(&pool, &state).await?;
+
+    // Log enrollment
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "totp_enroll",
+        Some("admin"),
+        Some(state.id),
+        Some(json!({"during_login": false})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Turn off TOTP for the signed-in admin (not allowed while a super admin requires it)
+pub async fn disable_totp(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<StatusCode, AppError> {
+    let state = load_totp_state_by_username(&pool, &claims.sub).await?;
+
+    if state.totp_required {
+        return Err(AppError::Forbidden("Two-factor authentication is required for this account".to_string()));
+    }
+
+    let Some(secret) = state.totp_secret.clone().filter(|_| state.totp_enabled) else {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".to_string()));
+    };
+
+    if !verify_and_consume_code(&pool, &state, &secret, &payload.code).await? {
+        return Err(AppError::BadRequest("Invalid authentication code".to_string()));
+    }
+
+    clear_totp(&pool, state.id).await?;
+
+    // Log disable
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "totp_disable",
+        Some("admin"),
+        Some(state.id),
+        None,
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Replace the signed-in admin's recovery codes
+pub async fn regenerate_recovery_codes(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Json(payload): Json<TotpCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, AppError> {
+    let state = load_totp_state_by_username(&pool, &claims.sub).await?;
+
+    let Some(secret) = state.totp_secret.clone().filter(|_| state.totp_enabled) else {
+        return Err(AppError::BadRequest("Two-factor authentication is not enabled".to_string()));
+    };
+
+    if !verify_and_consume_code(&pool, &state, &secret, &payload.code).await? {
+        return Err(AppError::BadRequest("Invalid authentication code".to_string()));
+    }
+
+    let recovery_codes = replace_recovery_codes(&pool, state.id).await?;
+
+    // Log regeneration
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "totp_recovery_codes_regenerated",
+        Some("admin"),
+        Some(state.id),
+        None,
+        None,
+        None,
+    ).await.ok();
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+async fn clear_totp(pool: &SqlitePool, admin_id: i64) -> Result<(), AppError> {
+    sqlx::query(
+        "UPDATE admins SET totp_secret = NULL, totp_pending_secret = NULL, totp_enabled = 0, totp_last_used_step = NULL
+         WHERE id = ?"
+    )
+    .bind(admin_id)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to clear TOTP: {}", e)))?;
+
+    sqlx::query("DELETE FROM admin_recovery_codes WHERE admin_id = ?")
+        .bind(admin_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to clear recovery codes: {}", e)))?;
+
+    Ok(())
+}
+
+/// Super admin: remove another admin's authenticator (lost device) and sign them out
+pub async fn reset_admin_totp(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+) -> Result<StatusCode, AppError> {
+    let state = load_totp_state_by_id(&pool, id).await?;
+
+    clear_totp(&pool, id).await?;
+    revoke_admin_sessions(&pool, id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+
+    // Log reset
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "totp_reset",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": state.username, "was_enabled": state.totp_enabled})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Super admin: require (or stop requiring) TOTP for an admin.
+/// A required admin without TOTP is walked through enrollment on their next login.
+pub async fn set_totp_requirement(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    axum::extract::Path(id): axum::extract::Path<i64>,
+    Json(payload): Json<TotpRequirementRequest>,
+) -> Result<StatusCode, AppError> {
+    let state = load_totp_state_by_id(&pool, id).await?;
+
+    sqlx::query("UPDATE admins SET totp_required = ? WHERE id = ?")
+        .bind(payload.required)
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to update TOTP requirement: {}", e)))?;
+
+    // Log requirement change
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "totp_requirement_change",
+        Some("admin"),
+        Some(id),
+        Some(json!({"username": state.username, "required": payload.required})),
+        None,
+        None,
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
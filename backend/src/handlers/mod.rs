@@ -1,6 +1,11 @@
 pub mod activity_logs;
+pub mod api_keys;
 pub mod auth;
 pub mod categories;
 pub mod media;
+pub mod mfa;
+pub mod oidc;
+pub mod password_reset;
+pub mod sessions;
 pub mod tests;
 pub mod user;
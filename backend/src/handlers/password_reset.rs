@@ -0,0 +1,184 @@
+use crate::{
+    error::AppError,
+    models::{ForgotPasswordRequest, ForgotPasswordResponse, ResetPasswordRequest},
+    utils::{
+        activity_logger::log_activity,
+        api_keys::revoke_admin_api_keys,
+        auth::{generate_secret_token, hash_password, hash_token},
+        email_service,
+        login_throttle,
+        password_policy,
+        password_reset::{check_and_record_request, normalize_email, token_ttl_minutes},
+        request_meta::{client_ip, user_agent},
+        session::revoke_admin_sessions,
+    },
+};
+use axum::{extract::State, http::{HeaderMap, StatusCode}, Json};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+/// Same answer whether or not an admin has the address, so the endpoint can't
+/// be used to find out which addresses are registered
+const GENERIC_RESPONSE: &str = "If an admin account uses that address, a password reset link has been sent to it";
+
+/// Public: email a single-use password reset link to the admin with this address.
+/// Suspended and SSO accounts get no link (SSO admins reset their password with
+/// the identity provider), but the response is the same.
+pub async fn request_password_reset(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<(StatusCode, Json<ForgotPasswordResponse>), AppError> {
+    let ip_address = client_ip(&headers);
+    let user_agent = user_agent(&headers);
+    let accepted = (
+        StatusCode::ACCEPTED,
+        Json(ForgotPasswordResponse { message: GENERIC_RESPONSE.to_string() }),
+    );
+
+    let email = normalize_email(&payload.email).unwrap_or_else(|| payload.email.trim().to_lowercase());
+    check_and_record_request(&pool, &email, ip_address.as_deref()).await?;
+
+    let admin: Option<(i64, String)> = sqlx::query_as(
+        "SELECT id, username FROM admins WHERE email = ? AND suspended_at IS NULL AND oidc_subject IS NULL"
+    )
+    .bind(&email)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let Some((admin_id, username)) = admin else {
+        return Ok(accepted);
+    };
+
+    let token = generate_secret_token();
+    let valid_minutes = token_ttl_minutes();
+
+    sqlx::query(
+        "INSERT INTO admin_password_resets (admin_id, token_hash, expires_at, requested_ip)
+         VALUES (?, ?, datetime('now', ?), ?)"
+    )
+    .bind(admin_id)
+    .bind(hash_token(&token))
+    .bind(format!("+{} minutes", valid_minutes))
+    .bind(&ip_address)
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to create reset token: {}", e)))?;
+
+    let frontend_url = std::env::var("FRONTEND_URL")
+        .unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let link = format!("{}/admin/reset-password?token={}", frontend_url, token);
+
+    // Send in the background so the response time doesn't reveal whether the account exists
+    let recipient = email.clone();
+    let recipient_username = username.clone();
+    tokio::spawn(async move {
+        match email_service::send_password_reset_email(&recipient, &recipient_username, &link, valid_minutes).await {
+            Ok(_) => tracing::info!("Password reset email sent to {}", recipient),
+            Err(e) => tracing::error!("Failed to send password reset email to {}: {}", recipient, e),
+        }
+    });
+
+    // Log reset request
+    log_activity(
+        &pool,
+        None,
+        None,
+        "password_reset_requested",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"username": username})),
+        ip_address.as_deref(),
+        user_agent.as_deref(),
+    ).await.ok();
+
+    Ok(accepted)
+}
+
+/// Public: set a new password with a token from a reset email. The token works
+/// once; every session of the admin ends.
+pub async fn confirm_password_reset(
+    State(pool): State<SqlitePool>,
+    headers: HeaderMap,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, AppError> {
+    let invalid = || AppError::BadRequest("Reset link is invalid or has expired".to_string());
+    let token_hash = hash_token(&payload.token);
+
+    let reset: Option<(i64, i64, String, String)> = sqlx::query_as(
+        "SELECT r.id, a.id, a.username, a.password_hash
+         FROM admin_password_resets r
+         JOIN admins a ON a.id = r.admin_id
+         WHERE r.token_hash = ? AND r.used_at IS NULL AND r.expires_at > datetime('now')
+           AND a.suspended_at IS NULL AND a.oidc_subject IS NULL"
+    )
+    .bind(&token_hash)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (reset_id, admin_id, username, previous_hash) = reset.ok_or_else(invalid)?;
+
+    // Check the password before using up the token, so a rejected password can be retried
+    password_policy::validate_new_password(&pool, Some(admin_id), &payload.new_password).await?;
+
+    let new_password_hash = hash_password(&payload.new_password)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to hash password: {}", e)))?;
+
+    // Claim the token; a concurrent request with the same token updates nothing
+    let claimed = sqlx::query("UPDATE admin_password_resets SET used_at = datetime('now') WHERE id = ? AND used_at IS NULL")
+        .bind(reset_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+    if claimed.rows_affected() == 0 {
+        return Err(invalid());
+    }
+
+    sqlx::query(
+        "UPDATE admins SET password_hash = ?, password_must_change = 0, last_password_change = datetime('now') WHERE id = ?"
+    )
+    .bind(&new_password_hash)
+    .bind(admin_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to update password: {}", e)))?;
+
+    password_policy::record_previous_hash(&pool, admin_id, &previous_hash)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to record password history: {}", e)))?;
+
+    // Any other links sent before this one are no longer needed
+    sqlx::query("UPDATE admin_password_resets SET used_at = datetime('now') WHERE admin_id = ? AND used_at IS NULL")
+        .bind(admin_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let revoked_sessions = revoke_admin_sessions(&pool, admin_id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+    let revoked_api_keys = revoke_admin_api_keys(&pool, admin_id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke API keys: {}", e)))?;
+
+    login_throttle::clear_user(&pool, &username)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to reset login attempts: {}", e)))?;
+
+    // Log completed reset
+    log_activity(
+        &pool,
+        Some(&username),
+        None,
+        "password_reset_completed",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"revoked_sessions": revoked_sessions, "revoked_api_keys": revoked_api_keys})),
+        client_ip(&headers).as_deref(),
+        user_agent(&headers).as_deref(),
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
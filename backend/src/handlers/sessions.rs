@@ -0,0 +1,147 @@
+use crate::{
+    error::AppError,
+    models::{AdminSession, Claims, SessionListQuery},
+    utils::{
+        activity_logger::log_activity,
+        permissions::Permission,
+        request_meta::{client_ip, user_agent},
+        session::revoke_session,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde_json::json;
+use sqlx::SqlitePool;
+
+/// Sessions that can still be used: not revoked and not past their refresh expiry
+const LIVE_SESSIONS_QUERY: &str =
+    "SELECT s.id, s.admin_id, a.username, s.ip_address, s.user_agent, s.created_at, s.last_used_at, s.expires_at,
+            s.id = ? AS current
+     FROM admin_sessions s
+     INNER JOIN admins a ON a.id = s.admin_id
+     WHERE s.revoked_at IS NULL AND s.expires_at > datetime('now')";
+
+/// Super admin: every live session, optionally for one admin (`?admin_id=`)
+pub async fn list_sessions(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    Query(params): Query<SessionListQuery>,
+) -> Result<Json<Vec<AdminSession>>, AppError> {
+    let sessions = sqlx::query_as::<_, AdminSession>(&format!(
+        "{} AND (? IS NULL OR s.admin_id = ?) ORDER BY s.last_used_at DESC",
+        LIVE_SESSIONS_QUERY
+    ))
+    .bind(&claims.sid)
+    .bind(params.admin_id)
+    .bind(params.admin_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Json(sessions))
+}
+
+/// The signed-in admin's live sessions; the one making the request is marked `current`
+pub async fn list_own_sessions(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+) -> Result<Json<Vec<AdminSession>>, AppError> {
+    let sessions = sqlx::query_as::<_, AdminSession>(&format!(
+        "{} AND a.username = ? ORDER BY s.last_used_at DESC",
+        LIVE_SESSIONS_QUERY
+    ))
+    .bind(&claims.sid)
+    .bind(&claims.sub)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(Json(sessions))
+}
+
+/// End a session: one of the caller's own, or any session for super admins.
+/// Its access token stops working on the next request.
+pub async fn terminate_session(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let session: Option<(i64, String)> = sqlx::query_as(
+        "SELECT s.admin_id, a.username
+         FROM admin_sessions s
+         INNER JOIN admins a ON a.id = s.admin_id
+         WHERE s.id = ? AND s.revoked_at IS NULL AND s.expires_at > datetime('now')"
+    )
+    .bind(&id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    // Someone else's session looks the same as a missing one unless the caller manages admins
+    let (admin_id, owner) = session
+        .filter(|(_, owner)| *owner == claims.sub || claims.has_permission(Permission::ManageAdmins))
+        .ok_or_else(|| AppError::NotFound("Session does not exist or has already ended".to_string()))?;
+
+    revoke_session(&pool, &id)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to revoke session: {}", e)))?;
+
+    // Log session termination
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "terminate_session",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"username": owner, "session_id": id})),
+        client_ip(&headers).as_deref(),
+        user_agent(&headers).as_deref(),
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Sign out everywhere except the session making the request
+pub async fn terminate_other_sessions(
+    State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
+    headers: HeaderMap,
+) -> Result<StatusCode, AppError> {
+    let admin_id: Option<i64> = sqlx::query_scalar("SELECT id FROM admins WHERE username = ?")
+        .bind(&claims.sub)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let admin_id = admin_id
+        .ok_or_else(|| AppError::NotFound("Admin account no longer exists".to_string()))?;
+
+    let result = sqlx::query(
+        "UPDATE admin_sessions SET revoked_at = datetime('now') WHERE admin_id = ? AND id != ? AND revoked_at IS NULL"
+    )
+    .bind(admin_id)
+    .bind(&claims.sid)
+    .execute(&pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to revoke sessions: {}", e)))?;
+
+    // Log sign-out of other sessions
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "terminate_other_sessions",
+        Some("admin"),
+        Some(admin_id),
+        Some(json!({"revoked_sessions": result.rows_affected()})),
+        client_ip(&headers).as_deref(),
+        user_agent(&headers).as_deref(),
+    ).await.ok();
+
+    Ok(StatusCode::NO_CONTENT)
+}
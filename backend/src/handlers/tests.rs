@@ -1,14 +1,41 @@
 use crate::{
+    error::AppError,
     models::{
-        AddTestUserRequest, Claims, CreateTestRequest, MediaFile, MediaFileStats, Rating, RatingWithUser,
+        AddTestUserRequest, Claims, CreateTestRequest, MediaFile, MediaFileStats, MediaMetadata, Rating, RatingWithUser,
         Test, TestResultsResponse, TestUser, TestUserResponse,
     },
-    utils::{auth::generate_one_time_token, activity_logger::log_activity, email_service},
+    utils::{
+        auth::generate_one_time_token, activity_logger::log_activity, category_tree::TEST_MEDIA_FILE_IDS,
+        email_service, media_urls, permissions::Permission,
+    },
 };
 use axum::{extract::State, http::StatusCode, Json};
 use serde_json::json;
 use sqlx::SqlitePool;
 
+/// Admins with `ManageAllTests` may manage any test; everyone else only the tests they created
+async fn ensure_can_manage_test(
+    pool: &SqlitePool,
+    claims: &Claims,
+    test_id: i64,
+) -> Result<(), AppError> {
+    if claims.has_permission(Permission::ManageAllTests) {
+        return Ok(());
+    }
+
+    let test: Option<(Option<String>,)> = sqlx::query_as("SELECT created_by FROM tests WHERE id = ?")
+        .bind(test_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    match test {
+        None => Err(AppError::NotFound("Test not found".to_string())),
+        Some((created_by,)) if created_by.as_deref() == Some(claims.sub.as_str()) => Ok(()),
+        Some(_) => Err(AppError::Forbidden("You can only manage tests you created".to_string())),
+    }
+}
+
 pub async fn create_test(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
@@ -72,6 +99,8 @@ pub async fn add_test_user(
     axum::extract::Path(test_id): axum::extract::Path<i64>,
     Json(payload): Json<AddTestUserRequest>,
 ) -> Result<Json<TestUserResponse>, StatusCode> {
+    ensure_can_manage_test(&pool, &claims, test_id).await?;
+
     // Check if user already exists for this test
     let existing_user: Option<TestUser> = sqlx::query_as::<_, TestUser>(
         "SELECT * FROM test_users WHERE test_id = ? AND email = ?"
@@ -152,8 +181,12 @@ pub async fn add_test_user(
 
 pub async fn list_test_users(
     State(pool): State<SqlitePool>,
+    axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path(test_id): axum::extract::Path<i64>,
 ) -> Result<Json<Vec<TestUser>>, StatusCode> {
+    // Test users carry their one-time tokens, so only admins who manage the test may list them
+    ensure_can_manage_test(&pool, &claims, test_id).await?;
+
     let users = sqlx::query_as::<_, TestUser>(
         "SELECT * FROM test_users WHERE test_id = ? ORDER BY id DESC"
     )
@@ -170,6 +203,8 @@ pub async fn close_test(
     axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path(test_id): axum::extract::Path<i64>,
 ) -> Result<StatusCode, StatusCode> {
+    ensure_can_manage_test(&pool, &claims, test_id).await?;
+
     // Get test name before closing
     let test: Option<(String,)> = sqlx::query_as(
         "SELECT name FROM tests WHERE id = ?"
@@ -213,52 +248,41 @@ pub async fn delete_test(
     State(pool): State<SqlitePool>,
     axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path(test_id): axum::extract::Path<i64>,
-) -> Result<StatusCode, StatusCode> {
-    // Fetch the test to check ownership
-    let test: Option<Test> = sqlx::query_as::<_, Test>("SELECT * FROM tests WHERE id = ?")
+) -> Result<StatusCode, AppError> {
+    ensure_can_manage_test(&pool, &claims, test_id).await?;
+
+    let test: Test = sqlx::query_as::<_, Test>("SELECT * FROM tests WHERE id = ?")
         .bind(test_id)
         .fetch_optional(&pool)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?
+        .ok_or_else(|| AppError::NotFound("Test not found".to_string()))?;
 
-    match test {
-        None => Err(StatusCode::NOT_FOUND),
-        Some(test) => {
-            // Check if user is super admin or test creator
-            let is_authorized = claims.is_super_admin
-                || test.created_by.as_ref() == Some(&claims.sub);
+    // Delete the test (cascades to test_categories, test_users, and ratings)
+    let result = sqlx::query("DELETE FROM tests WHERE id = ?")
+        .bind(test_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
 
-            if !is_authorized {
-                return Err(StatusCode::FORBIDDEN);
-            }
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Test not found".to_string()));
+    }
 
-            // Delete the test (cascades to test_categories, test_users, and ratings)
-            let result = sqlx::query("DELETE FROM tests WHERE id = ?")
-                .bind(test_id)
-                .execute(&pool)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Log test deletion
+    log_activity(
+        &pool,
+        Some(&claims.sub),
+        None,
+        "delete_test",
+        Some("test"),
+        Some(test_id),
+        Some(json!({"name": test.name, "created_by": test.created_by})),
+        None,
+        None,
+    ).await.ok();
 
-            if result.rows_affected() == 0 {
-                Err(StatusCode::NOT_FOUND)
-            } else {
-                // Log test deletion
-                log_activity(
-                    &pool,
-                    Some(&claims.sub),
-                    None,
-                    "delete_test",
-                    Some("test"),
-                    Some(test_id),
-                    Some(json!({"name": test.name, "created_by": test.created_by})),
-                    None,
-                    None,
-                ).await.ok();
-
-                Ok(StatusCode::NO_CONTENT)
-            }
-        }
-    }
+    Ok(StatusCode::NO_CONTENT)
 }
 
 pub async fn delete_test_user(
@@ -266,6 +290,8 @@ pub async fn delete_test_user(
     axum::Extension(claims): axum::Extension<Claims>,
     axum::extract::Path((test_id, user_id)): axum::extract::Path<(i64, i64)>,
 ) -> Result<StatusCode, StatusCode> {
+    ensure_can_manage_test(&pool, &claims, test_id).await?;
+
     // Check if test is closed and get user email
     let test: Option<(String,)> = sqlx::query_as(
         "SELECT status FROM tests WHERE id = ?"
@@ -340,37 +366,41 @@ pub async fn get_test_results(
         .map_err(|_| StatusCode::NOT_FOUND)?;
 
     // Get aggregated statistics
-    let aggregated: Vec<MediaFileStats> = sqlx::query_as::<_, (i64, String, String, String, String, String, f64, i64)>(
+    // Media from the linked categories and all of their subcategories, each counted once
+    let aggregated: Vec<MediaFileStats> = sqlx::query_as::<_, (i64, String, String, String, String, String, Option<String>, Option<String>, Option<sqlx::types::Json<MediaMetadata>>, f64, i64)>(&format!(
         r#"
         SELECT
-            mf.id, mf.filename, mf.file_path, mf.media_type, mf.mime_type, mf.uploaded_at,
-            COALESCE(AVG(r.stars), 0) as avg_stars,
+            mf.id, mf.filename, mf.storage_key, mf.media_type, mf.mime_type, mf.uploaded_at, mf.archived_at, mf.content_hash, mf.metadata,
+            COALESCE(AVG(r.stars), 0.0) as avg_stars,
             COUNT(r.id) as total_ratings
         FROM media_files mf
-        INNER JOIN media_file_categories mfc ON mf.id = mfc.media_file_id
-        INNER JOIN test_categories tc ON mfc.category_id = tc.category_id
         LEFT JOIN ratings r ON r.media_file_id = mf.id
         LEFT JOIN test_users tu ON r.test_user_id = tu.id AND tu.test_id = ?
-        WHERE tc.test_id = ?
+        WHERE mf.id IN ({})
         GROUP BY mf.id
         ORDER BY avg_stars DESC
-        "#
-    )
+        "#,
+        TEST_MEDIA_FILE_IDS
+    ))
     .bind(test_id)
     .bind(test_id)
     .fetch_all(&pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .into_iter()
-    .map(|(id, filename, file_path, media_type, mime_type, uploaded_at, avg_stars, total_ratings)| {
+    .map(|(id, filename, storage_key, media_type, mime_type, uploaded_at, archived_at, content_hash, metadata, avg_stars, total_ratings)| {
         MediaFileStats {
             media_file: MediaFile {
                 id,
                 filename,
-                file_path,
+                storage_key,
                 media_type,
                 mime_type,
                 uploaded_at,
+                archived_at,
+                content_hash,
+                metadata,
+                url_signature: Some(media_urls::sign(id)),
             },
             average_stars: avg_stars,
             total_ratings,
@@ -379,10 +409,10 @@ pub async fn get_test_results(
     .collect();
 
     // Get individual ratings
-    let individual: Vec<RatingWithUser> = sqlx::query_as::<_, (i64, i64, i64, f64, Option<String>, String, String, i64, String, String, String, String, String)>(
+    let individual: Vec<RatingWithUser> = sqlx::query_as::<_, (i64, i64, i64, f64, Option<String>, String, String, i64, String, String, String, String, String, Option<String>, Option<String>, Option<sqlx::types::Json<MediaMetadata>>)>(
         r#"
         SELECT r.id, r.test_user_id, r.media_file_id, r.stars, r.comment, r.rated_at, tu.email,
-               mf.id, mf.filename, mf.file_path, mf.media_type, mf.mime_type, mf.uploaded_at
+               mf.id, mf.filename, mf.storage_key, mf.media_type, mf.mime_type, mf.uploaded_at, mf.archived_at, mf.content_hash, mf.metadata
         FROM ratings r
         INNER JOIN test_users tu ON r.test_user_id = tu.id
         INNER JOIN media_files mf ON r.media_file_id = mf.id
@@ -395,7 +425,7 @@ pub async fn get_test_results(
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
     .into_iter()
-    .map(|(id, test_user_id, media_file_id, stars, comment, rated_at, email, mf_id, filename, file_path, media_type, mime_type, uploaded_at)| {
+    .map(|(id, test_user_id, media_file_id, stars, comment, rated_at, email, mf_id, filename, storage_key, media_type, mime_type, uploaded_at, archived_at, content_hash, metadata)| {
         RatingWithUser {
             rating: Rating {
                 id,
@@ -409,10 +439,14 @@ pub async fn get_test_results(
             media_file: MediaFile {
                 id: mf_id,
                 filename,
-                file_path,
+                storage_key,
                 media_type,
                 mime_type,
                 uploaded_at,
+                archived_at,
+                content_hash,
+                metadata,
+                url_signature: Some(media_urls::sign(mf_id)),
             },
         }
     })
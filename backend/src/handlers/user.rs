@@ -1,8 +1,14 @@
 use crate::{
-    models::{MediaFile, Rating, RatingRequest, Test, TestDetailsResponse},
-    utils::activity_logger::log_activity,
+    handlers::media::{serve_object, MediaObject},
+    models::{MediaFile, Rating, RatingRequest, Test, TestDetailsResponse, TestMediaFile},
+    utils::{activity_logger::log_activity, category_tree::TEST_MEDIA_FILE_IDS, rubric::rubrics_for_test},
+};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Json,
 };
-use axum::{extract::State, http::StatusCode, Json};
 use serde_json::json;
 use sqlx::SqlitePool;
 
@@ -61,21 +67,33 @@ pub async fn get_test_by_token(
     }
 
     // Get media files for this test
-    let media_files = sqlx::query_as::<_, MediaFile>(
+    // Media from the linked categories and all of their subcategories
+    let media_files = sqlx::query_as::<_, MediaFile>(&format!(
         r#"
-        SELECT DISTINCT mf.*
+        SELECT mf.*
         FROM media_files mf
-        INNER JOIN media_file_categories mfc ON mf.id = mfc.media_file_id
-        INNER JOIN test_categories tc ON mfc.category_id = tc.category_id
-        WHERE tc.test_id = ?
+        WHERE mf.id IN ({})
         ORDER BY mf.uploaded_at
-        "#
-    )
+        "#,
+        TEST_MEDIA_FILE_IDS
+    ))
     .bind(test_id)
     .fetch_all(&pool)
     .await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // Attach the rating rubric of the category each file comes from
+    let mut rubrics = rubrics_for_test(&pool, test_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let media_files = media_files
+        .into_iter()
+        .map(|media_file| TestMediaFile {
+            rubric: rubrics.remove(&media_file.id),
+            media_file,
+        })
+        .collect();
+
     Ok(Json(TestDetailsResponse { test, media_files }))
 }
 
@@ -234,3 +252,175 @@ pub async fn complete_test(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Serve a media file to a participant of a test. Only files that are part of
+/// the token's test are served, and only while the test is open and the
+/// participant hasn't completed it.
+async fn serve_test_object(
+    pool: &SqlitePool,
+    token: &str,
+    media_file_id: i64,
+    object: MediaObject,
+    headers: &HeaderMap,
+) -> Result<Response, StatusCode> {
+    let test_user: Option<(i64, Option<String>, String)> = sqlx::query_as(
+        r#"
+        SELECT tu.test_id, tu.completed_at, t.status
+        FROM test_users tu
+        INNER JOIN tests t ON t.id = tu.test_id
+        WHERE tu.one_time_token = ?
+        "#
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (test_id, completed_at, status) = test_user.ok_or(StatusCode::NOT_FOUND)?;
+    if completed_at.is_some() {
+        return Err(StatusCode::GONE);
+    }
+    if status == "closed" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Same media as listed by get_test_by_token
+    let in_test: Option<(i64,)> = sqlx::query_as(&format!(
+        "SELECT id FROM media_files WHERE id = ? AND id IN ({})",
+        TEST_MEDIA_FILE_IDS
+    ))
+    .bind(media_file_id)
+    .bind(test_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if in_test.is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    serve_object(pool, media_file_id, object, headers).await
+}
+
+pub async fn serve_test_media(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path((token, media_file_id)): axum::extract::Path<(String, i64)>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_test_object(&pool, &token, media_file_id, MediaObject::File, &headers).await
+}
+
+pub async fn serve_test_thumbnail(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path((token, media_file_id)): axum::extract::Path<(String, i64)>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_test_object(&pool, &token, media_file_id, MediaObject::Thumbnail, &headers).await
+}
+
+pub async fn serve_test_waveform(
+    State(pool): State<SqlitePool>,
+    axum::extract::Path((token, media_file_id)): axum::extract::Path<(String, i64)>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    serve_test_object(&pool, &token, media_file_id, MediaObject::Waveform, &headers).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, storage, utils::uploads::upload_dir};
+    use axum::extract::Path;
+
+    /// Two open tests, each with a category holding one stored file. Returns the
+    /// pool, the token of a participant in the first test, and the media ids of
+    /// the first and second test.
+    async fn setup() -> (SqlitePool, String, i64, i64) {
+        storage::init_for_tests();
+        let pool = test_pool().await;
+        let token = uuid::Uuid::new_v4().to_string();
+
+        let mut media_ids = Vec::new();
+        for test in ["first", "second"] {
+            let test_id: i64 = sqlx::query_scalar("INSERT INTO tests (name) VALUES (?) RETURNING id")
+                .bind(test)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            let category_id: i64 = sqlx::query_scalar("INSERT INTO categories (name, media_type) VALUES (?, 'text') RETURNING id")
+                .bind(test)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+            sqlx::query("INSERT INTO test_categories (test_id, category_id) VALUES (?, ?)")
+                .bind(test_id)
+                .bind(category_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let storage_key = format!("{}.txt", uuid::Uuid::new_v4());
+            std::fs::write(std::path::Path::new(&upload_dir()).join(&storage_key), test).unwrap();
+            let media_id: i64 = sqlx::query_scalar(
+                "INSERT INTO media_files (filename, storage_key, media_type, mime_type) VALUES (?, ?, 'text', 'text/plain') RETURNING id"
+            )
+            .bind(format!("{}.txt", test))
+            .bind(&storage_key)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            sqlx::query("INSERT INTO media_file_categories (media_file_id, category_id) VALUES (?, ?)")
+                .bind(media_id)
+                .bind(category_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+            media_ids.push(media_id);
+
+            if test == "first" {
+                sqlx::query("INSERT INTO test_users (test_id, email, one_time_token) VALUES (?, 'p@example.com', ?)")
+                    .bind(test_id)
+                    .bind(&token)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+        }
+
+        (pool, token, media_ids[0], media_ids[1])
+    }
+
+    async fn serve(pool: &SqlitePool, token: &str, media_file_id: i64) -> StatusCode {
+        match serve_test_media(State(pool.clone()), Path((token.to_string(), media_file_id)), HeaderMap::new()).await {
+            Ok(response) => response.status(),
+            Err(status) => status,
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_media_of_own_test() {
+        let (pool, token, own, _) = setup().await;
+        assert_eq!(serve(&pool, &token, own).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn hides_media_of_other_tests() {
+        let (pool, token, _, other) = setup().await;
+        assert_eq!(serve(&pool, &token, other).await, StatusCode::NOT_FOUND);
+        assert_eq!(serve(&pool, &token, other + 100).await, StatusCode::NOT_FOUND);
+
+        let thumbnail = serve_test_thumbnail(State(pool.clone()), Path((token.clone(), other)), HeaderMap::new()).await;
+        assert_eq!(thumbnail.err(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_completed_and_closed() {
+        let (pool, token, own, _) = setup().await;
+        assert_eq!(serve(&pool, "not-a-token", own).await, StatusCode::NOT_FOUND);
+
+        sqlx::query("UPDATE tests SET status = 'closed'").execute(&pool).await.unwrap();
+        assert_eq!(serve(&pool, &token, own).await, StatusCode::FORBIDDEN);
+
+        sqlx::query("UPDATE test_users SET completed_at = datetime('now')").execute(&pool).await.unwrap();
+        assert_eq!(serve(&pool, &token, own).await, StatusCode::GONE);
+    }
+}
@@ -1,21 +1,27 @@
+mod commands;
 mod db;
 mod error;
 mod handlers;
 mod middleware;
 mod models;
+mod storage;
 mod utils;
 
 use axum::{
-    http::{header, HeaderValue, Method},
+    extract::DefaultBodyLimit,
+    http::{header, HeaderName, HeaderValue, Method},
     middleware as axum_middleware,
-    routing::{delete, get, patch, post},
+    routing::{delete, get, patch, post, put},
     Router,
 };
+use clap::Parser;
+use commands::{Args, Command};
+use std::net::SocketAddr;
+use utils::permissions::Permission;
 use tower_http::{
     cors::CorsLayer,
     limit::RequestBodyLimitLayer,
 };
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -25,6 +31,8 @@ async fn main() {
     // Load environment variables
     dotenvy::dotenv().ok();
 
+    let args = Args::parse();
+
     let database_url = std::env::var("DATABASE_URL")
         .unwrap_or_else(|_| "sqlite:../media_ranking.db".to_string());
     let host = std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
@@ -40,6 +48,53 @@ async fn main() {
         .await
         .expect("Failed to run migrations");
 
+    // Refuse to start without a usable storage backend
+    if let Err(e) = storage::init() {
+        tracing::error!("Invalid storage configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    // Maintenance commands run against the migrated database and exit
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::BackfillPreviews { force } => commands::backfill_previews::run(&pool, force).await,
+            Command::MigrateStorage { from, to, delete_source } => {
+                commands::migrate_storage::run(&pool, &from, &to, delete_source).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!("Command failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Refuse to start without usable token signing keys
+    if let Err(e) = utils::jwt_keys::init() {
+        tracing::error!("Invalid JWT configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    // Refuse to start with SSO half configured
+    if let Err(e) = utils::oidc::OidcConfig::from_env() {
+        tracing::error!("Invalid OIDC configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    // Refuse to start without a strong media URL signing secret
+    if let Err(e) = utils::media_urls::init() {
+        tracing::error!("Invalid media URL configuration: {}", e);
+        std::process::exit(1);
+    }
+
+    // Remove partial uploads from a previous run
+    utils::uploads::clean_temp_dir().await;
+
+    // Record sizes and content hashes of media uploaded before they were stored,
+    // without holding up startup
+    tokio::spawn(utils::uploads::backfill_file_sizes(pool.clone()));
+    tokio::spawn(utils::uploads::backfill_content_hashes(pool.clone()));
+
     // CORS configuration - Use environment variable for frontend URL
     let frontend_url = std::env::var("FRONTEND_URL")
         .unwrap_or_else(|_| "http://localhost:5173".to_string());
@@ -60,105 +115,323 @@ async fn main() {
             header::AUTHORIZATION,
             header::CONTENT_TYPE,
             header::ACCEPT,
+            HeaderName::from_static("x-csrf-token"),
         ])
         .allow_credentials(true);
 
-    // Build router
+    // Open media route keyed by sequential ids, kept only for old clients; the
+    // previews came after it and are only served through signed or test URLs
+    let public_media_routes = if utils::media_urls::public_routes_enabled() {
+        tracing::warn!("PUBLIC_MEDIA_ROUTES is enabled: any media file can be downloaded by id without a token");
+        Router::new()
+            .route("/api/media/:id/serve", get(handlers::media::serve_media))
+    } else {
+        Router::new()
+    };
+
+    // Build router. Every admin route declares the permission it needs; the
+    // permission check runs inside jwt_auth, which resolves the caller's role.
     let app = Router::new()
         // Public routes
         .route("/api/admin/login", post(handlers::auth::login))
+        .route("/api/admin/refresh", post(handlers::auth::refresh_token))
+        .route("/api/admin/login/mfa", post(handlers::mfa::login_mfa))
+        .route("/api/admin/login/mfa/setup", post(handlers::mfa::login_mfa_setup))
+        .route("/api/admin/password-reset/request", post(handlers::password_reset::request_password_reset))
+        .route("/api/admin/password-reset/confirm", post(handlers::password_reset::confirm_password_reset))
+        .route("/api/admin/oidc/config", get(handlers::oidc::oidc_config))
+        .route("/api/admin/oidc/login", get(handlers::oidc::oidc_login))
+        .route("/api/admin/oidc/callback", get(handlers::oidc::oidc_callback))
+        .route("/api/admin/oidc/exchange", post(handlers::oidc::oidc_exchange))
         .route("/api/test/:token", get(handlers::user::get_test_by_token))
         .route("/api/test/:token/ratings", post(handlers::user::submit_rating))
         .route("/api/test/:token/ratings", get(handlers::user::get_user_ratings))
         .route("/api/test/:token/complete", post(handlers::user::complete_test))
-        .route("/api/media/:id/serve", get(handlers::media::serve_media))
-        // Protected admin routes (super admin only)
+        .route("/api/test/:token/media/:id/serve", get(handlers::user::serve_test_media))
+        .route("/api/test/:token/media/:id/thumbnail", get(handlers::user::serve_test_thumbnail))
+        .route("/api/test/:token/media/:id/waveform", get(handlers::user::serve_test_waveform))
+        // Signed, expiring admin media URLs (the signature stands in for the JWT)
+        .route("/api/admin/media/:id/serve", get(handlers::media::serve_signed_media))
+        .route("/api/admin/media/:id/thumbnail", get(handlers::media::serve_signed_thumbnail))
+        .route("/api/admin/media/:id/waveform", get(handlers::media::serve_signed_waveform))
+        // The preview paths from the original thumbnail/waveform API, now signed as well
+        .route("/api/media/:id/thumbnail", get(handlers::media::serve_signed_thumbnail))
+        .route("/api/media/:id/waveform", get(handlers::media::serve_signed_waveform))
+        .merge(public_media_routes)
+        // Session routes (any authenticated admin)
+        .route(
+            "/api/admin/logout",
+            post(handlers::auth::logout)
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth_allow_password_change)),
+        )
+        .route(
+            "/api/admin/change-password",
+            post(handlers::auth::change_password)
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth_allow_password_change)),
+        )
+        .route(
+            "/api/admin/account",
+            get(handlers::auth::get_account)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/account/email",
+            put(handlers::auth::update_account_email)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/account/sessions",
+            get(handlers::sessions::list_own_sessions)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/account/sessions/revoke-others",
+            post(handlers::sessions::terminate_other_sessions)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/sessions/:id",
+            delete(handlers::sessions::terminate_session)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/totp",
+            get(handlers::mfa::totp_status)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/totp/setup",
+            post(handlers::mfa::setup_totp)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/totp/enable",
+            post(handlers::mfa::enable_totp)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/totp/disable",
+            post(handlers::mfa::disable_totp)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/totp/recovery-codes",
+            post(handlers::mfa::regenerate_recovery_codes)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/api-keys",
+            get(handlers::api_keys::list_api_keys)
+                .post(handlers::api_keys::create_api_key)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/api-keys/:id",
+            delete(handlers::api_keys::revoke_api_key)
+                .layer(axum_middleware::from_fn(middleware::auth::require_session))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        // Admin management
         .route(
             "/api/admin/users",
             get(handlers::auth::list_admins)
                 .post(handlers::auth::create_admin)
-                .layer(axum_middleware::from_fn(middleware::auth::super_admin_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/sessions",
+            get(handlers::sessions::list_sessions)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/users/:id",
             delete(handlers::auth::delete_admin)
-                .layer(axum_middleware::from_fn(middleware::auth::super_admin_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
-            "/api/admin/change-password",
-            post(handlers::auth::change_password)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            "/api/admin/users/:id/unlock",
+            post(handlers::auth::unlock_admin)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/reset-password",
+            post(handlers::auth::reset_admin_password)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/suspend",
+            post(handlers::auth::suspend_admin)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/reactivate",
+            post(handlers::auth::reactivate_admin)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
+        .route(
+            "/api/admin/users/:id/role",
+            patch(handlers::auth::update_admin_role)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/email",
+            put(handlers::auth::update_admin_email)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/totp",
+            delete(handlers::mfa::reset_admin_totp)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/users/:id/totp-required",
+            put(handlers::mfa::set_totp_requirement)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageAdmins, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        // Library
         .route(
             "/api/admin/categories",
-            post(handlers::categories::create_category)
-                .get(handlers::categories::list_categories)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            get(handlers::categories::list_categories)
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .merge(
+                    post(handlers::categories::create_category)
+                        .layer(axum_middleware::from_fn_with_state(Permission::ManageCategories, middleware::auth::require_permission)),
+                )
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/categories/:id",
-            delete(handlers::categories::delete_category)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            patch(handlers::categories::update_category)
+                .delete(handlers::categories::delete_category)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageCategories, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/categories/stats",
+            get(handlers::categories::list_category_stats)
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/categories/:id/stats",
+            get(handlers::categories::get_category_stats)
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/categories/:id/merge",
+            post(handlers::categories::merge_category)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageCategories, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/media/upload",
             post(handlers::media::upload_media)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                // Files are streamed to disk and size-checked per file, so axum's 2MB buffering limit doesn't apply
+                .layer(DefaultBodyLimit::disable())
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageMedia, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/media",
             get(handlers::media::list_media)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
+        )
+        .route(
+            "/api/admin/media/duplicates",
+            get(handlers::media::list_duplicate_media)
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/media/:id",
             delete(handlers::media::delete_media)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageMedia, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/media/:id/categories",
-            axum::routing::put(handlers::media::update_media_categories)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            put(handlers::media::update_media_categories)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageMedia, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
+        // Tests (ownership of individual tests is checked in the handlers)
         .route(
             "/api/admin/tests",
-            post(handlers::tests::create_test)
-                .get(handlers::tests::list_tests)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            get(handlers::tests::list_tests)
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewLibrary, middleware::auth::require_permission))
+                .merge(
+                    post(handlers::tests::create_test)
+                        .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission)),
+                )
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/tests/:id",
             delete(handlers::tests::delete_test)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/tests/:id/users",
-            post(handlers::tests::add_test_user)
-                .get(handlers::tests::list_test_users)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+            get(handlers::tests::list_test_users)
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission))
+                .merge(
+                    post(handlers::tests::add_test_user)
+                        .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission)),
+                )
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/tests/:test_id/users/:user_id",
             delete(handlers::tests::delete_test_user)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/tests/:id/close",
             patch(handlers::tests::close_test)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ManageOwnTests, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/tests/:id/results",
             get(handlers::tests::get_test_results)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewResults, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
         .route(
             "/api/admin/activity-logs",
             get(handlers::activity_logs::list_activity_logs)
-                .layer(axum_middleware::from_fn(middleware::auth::jwt_auth)),
+                .layer(axum_middleware::from_fn_with_state(Permission::ViewActivityLogs, middleware::auth::require_permission))
+                .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth)),
         )
-        .layer(RequestBodyLimitLayer::new(250 * 1024 * 1024)) // 250MB limit
+        .layer(RequestBodyLimitLayer::new(utils::uploads::max_request_bytes()))
         .layer(cors)
+        .layer(axum_middleware::from_fn(utils::request_meta::resolve_client_ip))
         .with_state(pool);
 
     let addr = format!("{}:{}", host, port);
@@ -168,7 +441,7 @@ async fn main() {
 
     tracing::info!("Server running on http://{}", addr);
 
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .expect("Failed to start server");
 }
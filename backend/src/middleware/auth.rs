@@ -1,81 +1,222 @@
-use crate::{error::AppError, utils::auth::verify_jwt};
+use crate::{
+    error::AppError,
+    models::Claims,
+    utils::{
+        activity_logger::REQUEST_API_KEY_ID,
+        api_keys::{authenticate_key, KEY_PREFIX},
+        auth::verify_jwt,
+        permissions::{Permission, Role},
+        request_meta::client_ip,
+        session::{active_session, touch_session},
+        session_cookies,
+    },
+};
 use axum::{
-    extract::Request,
+    extract::{Request, State},
+    http::{HeaderMap, Method},
     middleware::Next,
     response::Response,
 };
+use sqlx::SqlitePool;
+
+/// An API key sent as `X-API-Key: <key>` or `Authorization: Bearer <key>`
+fn api_key_from_headers(headers: &HeaderMap) -> Option<&str> {
+    if let Some(key) = headers.get("X-API-Key").and_then(|h| h.to_str().ok()) {
+        return Some(key);
+    }
+
+    headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .filter(|token| token.starts_with(KEY_PREFIX))
+}
+
+/// Build claims for a request made with an API key. The key acts as its owner,
+/// limited to the key's scopes.
+async fn authenticate_api_key(pool: &SqlitePool, headers: &HeaderMap, key: &str) -> Result<Claims, AppError> {
+    let key = authenticate_key(pool, key, client_ip(headers).as_deref())
+        .await
+        .inspect_err(|e| tracing::warn!("API key rejected: {:?}", e))?;
+
+    let role = Role::parse(&key.role)
+        .ok_or_else(|| AppError::InternalServerError(format!("Unknown role '{}'", key.role)))?;
+
+    Ok(Claims {
+        sub: key.username,
+        exp: 0,
+        // Super admin shortcuts only apply to keys that were given admin management
+        is_super_admin: role == Role::SuperAdmin && key.scopes.contains(&Permission::ManageAdmins),
+        role,
+        sid: String::new(),
+        password_must_change: key.password_change_required,
+        api_key_id: Some(key.id),
+        scopes: Some(key.scopes),
+    })
+}
+
+/// Verify the credentials on a request: an API key, or a Bearer token (or, in
+/// cookie mode, a session cookie) whose session is still live. Cookie-authenticated
+/// requests must also pass the double-submit CSRF check.
+///
+/// A token is rejected when its session was revoked (logout, admin deleted) or the
+/// admin is suspended, even if the JWT itself has not expired yet.
+async fn authenticate(pool: &SqlitePool, method: &Method, headers: &HeaderMap) -> Result<Claims, AppError> {
+    if let Some(key) = api_key_from_headers(headers) {
+        return authenticate_api_key(pool, headers, key).await;
+    }
+
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok());
+
+    let token = match auth_header {
+        Some(auth_header) => {
+            let Some(token) = auth_header.strip_prefix("Bearer ") else {
+                tracing::warn!("Authorization header missing 'Bearer ' prefix");
+                return Err(AppError::Unauthorized("Authorization header must be in format: Bearer <token>".to_string()));
+            };
+            token
+        }
+        None => {
+            let Some(token) = session_cookies::get(headers, session_cookies::SESSION_COOKIE) else {
+                tracing::warn!("Missing Authorization header");
+                return Err(AppError::Unauthorized("Missing Authorization header".to_string()));
+            };
+
+            session_cookies::verify_csrf(method, headers).inspect_err(|_| {
+                tracing::warn!("Rejected cookie-authenticated {} without a valid CSRF token", method);
+            })?;
+            token
+        }
+    };
+
+    let mut claims = verify_jwt(token).map_err(|e| {
+        tracing::warn!("JWT verification failed: {}", e);
+        AppError::Unauthorized(format!("Invalid or expired token: {}", e))
+    })?;
+
+    let session = active_session(pool, &claims.sid, &claims.sub)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to check session: {}", e)))?;
+
+    let Some(session) = session else {
+        tracing::warn!("Rejected token for revoked or unknown session {}", claims.sid);
+        return Err(AppError::Unauthorized("Session has been revoked".to_string()));
+    };
+
+    if session.suspended {
+        tracing::warn!("Rejected token for suspended admin {}", claims.sub);
+        return Err(AppError::Unauthorized("Account is suspended".to_string()));
+    }
 
+    if let Err(e) = touch_session(pool, &claims.sid).await {
+        tracing::warn!("Failed to record activity on session {}: {}", claims.sid, e);
+    }
+
+    // The stored role and password state win over the ones baked into the token
+    claims.role = Role::parse(&session.role)
+        .ok_or_else(|| AppError::InternalServerError(format!("Unknown role '{}'", session.role)))?;
+    claims.is_super_admin = claims.role == Role::SuperAdmin;
+    claims.password_must_change = session.password_change_required;
+
+    Ok(claims)
+}
+
+/// Authenticate an admin request with a session token or an API key.
+/// Admins who still have to change their password
+/// (new account, reset, or expired password) are refused here; the few routes they
+/// need use `jwt_auth_allow_password_change` instead.
 pub async fn jwt_auth(
+    State(pool): State<SqlitePool>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok());
+    let claims = authenticate(&pool, req.method(), req.headers()).await?;
 
-    if let Some(auth_header) = auth_header {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            let jwt_secret = std::env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "default-secret".to_string());
-
-            match verify_jwt(token, &jwt_secret) {
-                Ok(claims) => {
-                    req.extensions_mut().insert(claims);
-                    return Ok(next.run(req).await);
-                }
-                Err(e) => {
-                    tracing::warn!("JWT verification failed: {}", e);
-                    return Err(AppError::Unauthorized(format!("Invalid or expired token: {}", e)));
-                }
-            }
-        } else {
-            tracing::warn!("Authorization header missing 'Bearer ' prefix");
-            return Err(AppError::Unauthorized("Authorization header must be in format: Bearer <token>".to_string()));
-        }
+    if claims.password_must_change {
+        tracing::warn!("Admin {} must change their password before using {}", claims.sub, req.uri().path());
+        return Err(AppError::Forbidden("Password change required".to_string()));
     }
 
-    tracing::warn!("Missing Authorization header");
-    Err(AppError::Unauthorized("Missing Authorization header".to_string()))
+    let api_key_id = claims.api_key_id;
+    req.extensions_mut().insert(claims);
+
+    match api_key_id {
+        // Attribute everything logged while handling this request to the key
+        Some(id) => Ok(REQUEST_API_KEY_ID.scope(id, next.run(req)).await),
+        None => Ok(next.run(req).await),
+    }
 }
 
-pub async fn super_admin_auth(
+/// Like `jwt_auth`, but lets through admins whose password must be changed.
+/// Only for change-password and logout, which need an interactive session.
+pub async fn jwt_auth_allow_password_change(
+    State(pool): State<SqlitePool>,
     mut req: Request,
     next: Next,
 ) -> Result<Response, AppError> {
-    // First verify JWT and get claims
-    let auth_header = req
-        .headers()
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok());
+    let claims = authenticate(&pool, req.method(), req.headers()).await?;
+
+    if claims.api_key_id.is_some() {
+        return Err(AppError::Forbidden("API keys cannot be used for this endpoint".to_string()));
+    }
+
+    req.extensions_mut().insert(claims);
+    Ok(next.run(req).await)
+}
+
+/// Layered inside `jwt_auth` on account routes (2FA, API keys) that must be
+/// used from a signed-in session rather than with an API key.
+pub async fn require_session(req: Request, next: Next) -> Result<Response, AppError> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    if claims.api_key_id.is_some() {
+        return Err(AppError::Forbidden("API keys cannot be used for this endpoint".to_string()));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Route-level permission check, layered inside `jwt_auth`:
+///
+/// ```ignore
+/// delete(handlers::media::delete_media)
+///     .layer(axum_middleware::from_fn_with_state(Permission::ManageMedia, middleware::auth::require_permission))
+///     .layer(axum_middleware::from_fn_with_state(pool.clone(), middleware::auth::jwt_auth))
+/// ```
+pub async fn require_permission(
+    State(permission): State<Permission>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
-    if let Some(auth_header) = auth_header {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            let jwt_secret = std::env::var("JWT_SECRET")
-                .unwrap_or_else(|_| "default-secret".to_string());
-
-            match verify_jwt(token, &jwt_secret) {
-                Ok(claims) => {
-                    // Check if user is super admin
-                    if claims.is_super_admin {
-                        req.extensions_mut().insert(claims);
-                        return Ok(next.run(req).await);
-                    } else {
-                        tracing::warn!("Non-super-admin attempted to access super admin route");
-                        return Err(AppError::Forbidden("Super admin access required".to_string()));
-                    }
-                }
-                Err(e) => {
-                    tracing::warn!("JWT verification failed: {}", e);
-                    return Err(AppError::Unauthorized(format!("Invalid or expired token: {}", e)));
-                }
-            }
-        } else {
-            tracing::warn!("Authorization header missing 'Bearer ' prefix");
-            return Err(AppError::Unauthorized("Authorization header must be in format: Bearer <token>".to_string()));
+    if !claims.has_permission(permission) {
+        tracing::warn!(
+            "Admin {} with role {} denied {:?}",
+            claims.sub,
+            claims.role.as_str(),
+            permission
+        );
+        if claims.role.has_permission(permission) {
+            return Err(AppError::Forbidden(format!(
+                "API key does not have the '{}' scope",
+                permission.as_str()
+            )));
         }
+        return Err(AppError::Forbidden(format!(
+            "Role '{}' does not have the {:?} permission",
+            claims.role.as_str(),
+            permission
+        )));
     }
 
-    tracing::warn!("Missing Authorization header");
-    Err(AppError::Unauthorized("Missing Authorization header".to_string()))
+    Ok(next.run(req).await)
 }
@@ -1,3 +1,4 @@
+use crate::utils::permissions::{Permission, Role};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -5,11 +6,27 @@ pub struct Admin {
     pub id: i64,
     pub username: String,
     #[serde(skip_serializing)]
+    #[allow(dead_code)]
     pub password_hash: String,
     pub is_super_admin: bool,
+    pub role: String,
     pub created_at: String,
     pub password_must_change: bool,
     pub last_password_change: Option<String>,
+    pub totp_enabled: bool,
+    pub totp_required: bool,
+    /// When a super admin suspended the account; `None` for active admins
+    #[sqlx(default)]
+    pub suspended_at: Option<String>,
+    /// Where password reset links are sent
+    #[sqlx(default)]
+    pub email: Option<String>,
+    /// Set while the account is locked out after repeated failed logins
+    #[sqlx(default)]
+    pub locked_until: Option<String>,
+    /// Account is linked to (and was usually provisioned by) the SSO provider
+    #[sqlx(default)]
+    pub sso: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -18,16 +35,81 @@ pub struct Category {
     pub name: String,
     pub media_type: String,
     pub created_at: String,
+    pub parent_id: Option<i64>,
+    pub archived_at: Option<String>,
+    /// Guidance shown to participants rating media from this category
+    pub instructions: Option<String>,
+    /// Anchor label per whole star, e.g. {"1": "unusable", "5": "broadcast quality"}
+    pub star_labels: Option<sqlx::types::Json<StarLabels>>,
+}
+
+/// Star value (1-5) to anchor label
+pub type StarLabels = std::collections::BTreeMap<u8, String>;
+
+/// A category with its subcategories, as returned by `list_categories`
+#[derive(Debug, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub children: Vec<CategoryNode>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct MediaFile {
     pub id: i64,
     pub filename: String,
-    pub file_path: String,
+    /// Where the file lives in storage; internal, so never sent to clients
+    /// (participants receive media files with their test)
+    #[serde(skip_serializing)]
+    pub storage_key: String,
     pub media_type: String,
     pub mime_type: String,
     pub uploaded_at: String,
+    pub archived_at: Option<String>,
+    pub content_hash: Option<String>,
+    pub metadata: Option<sqlx::types::Json<MediaMetadata>>,
+    /// Signed access to the admin media routes; only set in admin responses
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_signature: Option<MediaUrlSignature>,
+}
+
+/// Expiry (Unix seconds) and HMAC of a signed admin media URL, sent as the
+/// `expires` and `signature` query parameters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MediaUrlSignature {
+    pub expires: i64,
+    pub signature: String,
+}
+
+/// Technical details read from a media file on upload. Only the fields that
+/// apply to the file's type are set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MediaMetadata {
+    /// Audio and video
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<u32>,
+    /// Images and video, in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// Images: grayscale, grayscale-alpha, rgb, rgba or cmyk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_space: Option<String>,
+    /// Text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub characters: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub words: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,6 +119,30 @@ pub struct MediaFileWithCategories {
     pub categories: Vec<Category>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct UploadMediaResponse {
+    /// New media files created by the upload
+    pub uploaded: Vec<i64>,
+    /// Existing media files the upload was identical to, with categories added
+    /// (or files earlier in the same upload, for repeated copies)
+    pub reused: Vec<i64>,
+    /// Files that were refused; the others are stored regardless
+    pub failed: Vec<UploadFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadFailure {
+    pub filename: String,
+    pub error: String,
+}
+
+/// Media files in the library that have the same content
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    pub content_hash: String,
+    pub media: Vec<MediaFileWithCategories>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UpdateMediaCategoriesRequest {
     pub category_ids: Vec<i64>,
@@ -82,15 +188,214 @@ pub struct LoginRequest {
 
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
-    pub token: String,
+    /// Absent in cookie mode (AUTH_MODE=cookie), where the tokens are set as HttpOnly cookies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// Cookie mode only: send back in the X-CSRF-Token header on state-changing requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csrf_token: Option<String>,
+    pub expires_in: u64,
     pub is_super_admin: bool,
+    pub role: Role,
     pub password_must_change: bool,
+    /// Only present right after TOTP enrollment completed during login
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+/// Returned by login instead of tokens when a second factor is needed
+#[derive(Debug, Serialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    /// The admin must enroll in TOTP (required by a super admin) before finishing login
+    pub enrollment_required: bool,
+    pub mfa_token: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginOutcome {
+    Authenticated(LoginResponse),
+    MfaRequired(MfaChallengeResponse),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MfaTokenRequest {
+    pub mfa_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TotpStatusResponse {
+    pub enabled: bool,
+    pub required: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TotpRequirementRequest {
+    pub required: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OidcConfigResponse {
+    pub enabled: bool,
+    pub password_login_enabled: bool,
+}
+
+/// Query string the identity provider redirects back with
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcTicketRequest {
+    pub ticket: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    /// Omitted in cookie mode, where the refresh token comes from its cookie
+    #[serde(default)]
+    pub refresh_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateAdminRequest {
     pub username: String,
     pub password: String,
+    pub role: Option<Role>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateEmailRequest {
+    /// `None` or an empty string removes the address
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ForgotPasswordResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountResponse {
+    pub username: String,
+    pub email: Option<String>,
+    pub sso: bool,
+}
+
+/// A live admin session, as listed to its owner or to super admins
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminSession {
+    pub id: String,
+    pub admin_id: i64,
+    pub username: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+    /// Last refresh or authenticated request (updated at most once a minute)
+    pub last_used_at: String,
+    pub expires_at: String,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionListQuery {
+    pub admin_id: Option<i64>,
+}
+
+/// API key metadata; the secret itself is only returned once, on creation
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: i64,
+    pub admin_id: i64,
+    pub name: String,
+    /// First characters of the key so it can be recognized in lists
+    pub key_prefix: String,
+    /// Comma-separated permission names
+    #[serde(skip_serializing)]
+    pub scopes: String,
+    pub expires_at: String,
+    pub last_used_at: Option<String>,
+    pub last_used_ip: Option<String>,
+    pub created_at: String,
+    pub revoked_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub scopes: Vec<Permission>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<Permission>,
+    /// Defaults to API_KEY_DEFAULT_LIFETIME_DAYS
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKeyResponse {
+    /// Full key, shown only in this response
+    pub api_key: String,
+    #[serde(flatten)]
+    pub key: ApiKeyResponse,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordResetResponse {
+    /// Shown once; the admin must replace it on their next login
+    pub temporary_password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateAdminRoleRequest {
+    pub role: Role,
 }
 
 #[derive(Debug, Deserialize)]
@@ -103,6 +408,88 @@ pub struct ChangePasswordRequest {
 pub struct CreateCategoryRequest {
     pub name: String,
     pub media_type: String,
+    pub parent_id: Option<i64>,
+    pub instructions: Option<String>,
+    pub star_labels: Option<StarLabels>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub media_type: Option<String>,
+    /// Absent: keep the parent; `null`: make top-level; id: move under that category
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub parent_id: Option<Option<i64>>,
+    /// Absent: keep; `null`: remove
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub instructions: Option<Option<String>>,
+    /// Absent: keep; `null`: remove
+    #[serde(default, deserialize_with = "deserialize_present")]
+    pub star_labels: Option<Option<StarLabels>>,
+}
+
+/// Tells an explicit `null` apart from a missing field: a present field becomes `Some`
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeCategoryRequest {
+    /// Category that receives the media and tests; the merged category is deleted
+    pub target_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeCategoryResponse {
+    pub category: Category,
+    pub media_moved: u64,
+    pub tests_moved: u64,
+}
+
+/// Size and usage of a category, for `GET /api/admin/categories/:id/stats` and
+/// `GET /api/admin/categories/stats`
+#[derive(Debug, Serialize)]
+pub struct CategoryStats {
+    pub category: Category,
+    /// Media in the category and its subcategories, each file counted once;
+    /// archived media and archived subcategories are left out
+    pub media_count: i64,
+    pub media_by_mime_type: Vec<MimeTypeCount>,
+    /// Sum of the recorded sizes of those files
+    pub total_bytes: u64,
+    /// Media records without a recorded size (uploaded before sizes were
+    /// stored and not found in storage since)
+    pub missing_files: i64,
+    /// Tests linked to this category itself
+    pub tests: Vec<CategoryTestUsage>,
+    pub total_ratings: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MimeTypeCount {
+    pub mime_type: String,
+    pub count: i64,
+}
+
+/// A test linked to a category and the ratings it has collected
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CategoryTestUsage {
+    pub id: i64,
+    pub name: String,
+    pub status: String,
+    pub ratings: i64,
+}
+
+/// `?force=true` on a category or media delete archives the item when tests
+/// still depend on it, instead of refusing
+#[derive(Debug, Deserialize)]
+pub struct DeleteQuery {
+    #[serde(default)]
+    pub force: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -134,7 +521,23 @@ pub struct RatingRequest {
 #[derive(Debug, Serialize)]
 pub struct TestDetailsResponse {
     pub test: Test,
-    pub media_files: Vec<MediaFile>,
+    pub media_files: Vec<TestMediaFile>,
+}
+
+/// A media file of a test with the rating rubric of the category it comes from
+#[derive(Debug, Serialize)]
+pub struct TestMediaFile {
+    #[serde(flatten)]
+    pub media_file: MediaFile,
+    pub rubric: Option<Rubric>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rubric {
+    pub category_id: i64,
+    pub category_name: String,
+    pub instructions: Option<String>,
+    pub star_labels: StarLabels,
 }
 
 #[derive(Debug, Serialize)]
@@ -163,6 +566,35 @@ pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub is_super_admin: bool,
+    pub role: Role,
+    /// Server-side session (admin_sessions.id) this access token belongs to
+    pub sid: String,
+    /// Only the change-password route is reachable while this is set
+    #[serde(default)]
+    pub password_must_change: bool,
+    /// Set by `jwt_auth` when the request used an API key instead of a session token
+    #[serde(skip)]
+    pub api_key_id: Option<i64>,
+    /// Scopes of that API key; the key can only use permissions both it and the role have
+    #[serde(skip)]
+    pub scopes: Option<Vec<Permission>>,
+}
+
+impl Claims {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.role.has_permission(permission)
+            && self.scopes.as_ref().is_none_or(|scopes| scopes.contains(&permission))
+    }
+}
+
+/// Short-lived pre-auth token issued after the password step of a two-factor login
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaClaims {
+    pub sub: String,
+    pub exp: usize,
+    pub admin_id: i64,
+    /// Always "mfa"; keeps these tokens distinct from access tokens
+    pub purpose: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -183,6 +615,11 @@ pub struct ActivityLog {
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
     pub timestamp: String,
+    /// API key the admin used for this action, if any
+    #[sqlx(default)]
+    pub api_key_id: Option<i64>,
+    #[sqlx(default)]
+    pub api_key_name: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
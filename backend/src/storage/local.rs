@@ -0,0 +1,76 @@
+use super::{check_key, Storage, StorageReader};
+use crate::utils::{file_response::serve_file, uploads::upload_dir};
+use async_trait::async_trait;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Files in a directory on the local disk (UPLOAD_DIR)
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn from_env() -> Self {
+        Self {
+            root: PathBuf::from(upload_dir()),
+        }
+    }
+
+    fn path(&self, key: &str) -> io::Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put_file(&self, key: &str, source: &Path, _content_type: &str) -> io::Result<()> {
+        let destination = self.path(key)?;
+        tokio::fs::create_dir_all(&self.root).await?;
+
+        // Uploads are staged inside UPLOAD_DIR, so this is normally an atomic
+        // rename; a source on another filesystem is copied instead
+        if tokio::fs::rename(source, &destination).await.is_err() {
+            let partial = destination.with_extension("partial");
+            tokio::fs::copy(source, &partial).await?;
+            tokio::fs::rename(&partial, &destination).await?;
+            let _ = tokio::fs::remove_file(source).await;
+        }
+        Ok(())
+    }
+
+    async fn open(&self, key: &str) -> io::Result<StorageReader> {
+        let file = tokio::fs::File::open(self.path(key)?).await?;
+        Ok(Box::pin(file))
+    }
+
+    async fn size(&self, key: &str) -> io::Result<Option<u64>> {
+        match tokio::fs::metadata(self.path(key)?).await {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> io::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    async fn serve(&self, key: &str, content_type: &str, request_headers: &HeaderMap) -> Result<Response, StatusCode> {
+        let path = self.path(key).map_err(|_| StatusCode::NOT_FOUND)?;
+        serve_file(&path, content_type, request_headers).await
+    }
+}
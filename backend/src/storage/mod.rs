@@ -0,0 +1,94 @@
+//! Where media files and their previews are kept. Files are addressed by a
+//! storage key (e.g. `<uuid>.wav`), which is the same in every backend, so
+//! files can be moved between backends without touching the database.
+
+mod local;
+mod s3;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+use async_trait::async_trait;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::Response,
+};
+use std::{io, path::Path, pin::Pin, sync::OnceLock};
+use tokio::io::AsyncRead;
+
+pub type StorageReader = Pin<Box<dyn AsyncRead + Send>>;
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Backend name as used in STORAGE_BACKEND
+    fn name(&self) -> &'static str;
+
+    /// Store a local file under `key`, replacing any existing object. The
+    /// source may be moved, so callers must not use it afterwards.
+    async fn put_file(&self, key: &str, source: &Path, content_type: &str) -> io::Result<()>;
+
+    /// Read an object; `NotFound` if it doesn't exist
+    async fn open(&self, key: &str) -> io::Result<StorageReader>;
+
+    /// Size of an object in bytes, or `None` if it doesn't exist
+    async fn size(&self, key: &str) -> io::Result<Option<u64>>;
+
+    /// Delete an object; deleting one that doesn't exist is not an error
+    async fn delete(&self, key: &str) -> io::Result<()>;
+
+    /// Answer a GET for an object, honoring `Range` and conditional headers
+    async fn serve(&self, key: &str, content_type: &str, request_headers: &HeaderMap) -> Result<Response, StatusCode>;
+}
+
+static STORAGE: OnceLock<Box<dyn Storage>> = OnceLock::new();
+
+/// Set up the backend chosen by STORAGE_BACKEND (`local`, the default, or
+/// `s3`). Called once at startup; an error means the server must not start.
+pub fn init() -> Result<(), String> {
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+    let storage = from_name(&backend)?;
+
+    tracing::info!("Storing media in the {} backend", storage.name());
+
+    STORAGE
+        .set(storage)
+        .map_err(|_| "Storage is already initialized".to_string())
+}
+
+/// Local storage in a temp directory of its own, shared by every test that
+/// stores files
+#[cfg(test)]
+pub fn init_for_tests() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let dir = std::env::temp_dir().join(format!("media-ranking-tests-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("Failed to create the test upload directory");
+        std::env::set_var("UPLOAD_DIR", &dir);
+        STORAGE.set(Box::new(LocalStorage::from_env())).ok();
+    });
+}
+
+pub fn get() -> &'static dyn Storage {
+    STORAGE.get().expect("Storage is not initialized").as_ref()
+}
+
+/// Build a backend by name from its environment settings
+pub fn from_name(name: &str) -> Result<Box<dyn Storage>, String> {
+    match name.trim() {
+        "local" => Ok(Box::new(LocalStorage::from_env())),
+        "s3" => Ok(Box::new(S3Storage::from_env()?)),
+        other => Err(format!("Unknown storage backend '{}'. Must be one of: local, s3", other)),
+    }
+}
+
+/// Storage keys are flat file names; anything that could address another
+/// location is refused
+fn check_key(key: &str) -> io::Result<()> {
+    if key.is_empty() || key.starts_with('.') || key.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid storage key '{}'", key),
+        ));
+    }
+    Ok(())
+}
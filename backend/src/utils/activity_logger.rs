@@ -1,5 +1,11 @@
 use sqlx::SqlitePool;
 
+tokio::task_local! {
+    /// API key behind the request being handled. `jwt_auth` sets it for
+    /// key-authenticated requests so every entry they log is attributed to the key.
+    pub static REQUEST_API_KEY_ID: i64;
+}
+
 /// Log an activity to the activity_logs table
 ///
 /// # Arguments
@@ -13,6 +19,8 @@ use sqlx::SqlitePool;
 /// * `ip_address` - IP address of the requester
 /// * `user_agent` - User agent string from the request
 ///
+/// Inside a request authenticated with an API key, the key's id is recorded too.
+///
 /// # Returns
 /// * `Result<(), sqlx::Error>` - Ok on success, error on database failure
 ///
@@ -30,6 +38,7 @@ use sqlx::SqlitePool;
 ///     None,
 /// ).await.ok(); // Don't fail request if logging fails
 /// ```
+#[allow(clippy::too_many_arguments)]
 pub async fn log_activity(
     pool: &SqlitePool,
     admin_username: Option<&str>,
@@ -41,10 +50,12 @@ pub async fn log_activity(
     ip_address: Option<&str>,
     user_agent: Option<&str>,
 ) -> Result<(), sqlx::Error> {
+    let api_key_id = REQUEST_API_KEY_ID.try_with(|id| *id).ok();
+
     sqlx::query(
         "INSERT INTO activity_logs
-         (admin_username, user_email, action, entity_type, entity_id, details, ip_address, user_agent)
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+         (admin_username, user_email, action, entity_type, entity_id, details, ip_address, user_agent, api_key_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(admin_username)
     .bind(user_email)
@@ -54,6 +65,7 @@ pub async fn log_activity(
     .bind(details.map(|d| d.to_string()))
     .bind(ip_address)
     .bind(user_agent)
+    .bind(api_key_id)
     .execute(pool)
     .await?;
 
@@ -0,0 +1,128 @@
+use crate::{
+    error::AppError,
+    utils::{auth::{generate_secret_token, hash_token}, password_policy, permissions::Permission},
+};
+use sqlx::SqlitePool;
+
+/// Every API key starts with this, so `jwt_auth` can tell keys from JWTs
+pub const KEY_PREFIX: &str = "mrk_";
+
+/// Lifetime used when a key is created without `expires_in_days` (API_KEY_DEFAULT_LIFETIME_DAYS)
+pub fn default_lifetime_days() -> i64 {
+    env_i64("API_KEY_DEFAULT_LIFETIME_DAYS", 90)
+}
+
+/// Longest lifetime a key may be created with (API_KEY_MAX_LIFETIME_DAYS)
+pub fn max_lifetime_days() -> i64 {
+    env_i64("API_KEY_MAX_LIFETIME_DAYS", 365)
+}
+
+fn env_i64(name: &str, default: i64) -> i64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(default)
+}
+
+/// A new key: the plaintext for the caller, the prefix for display and the hash for storage
+pub struct GeneratedKey {
+    pub key: String,
+    pub prefix: String,
+    pub hash: String,
+}
+
+pub fn generate_key() -> GeneratedKey {
+    let key = format!("{}{}", KEY_PREFIX, generate_secret_token());
+    GeneratedKey {
+        prefix: key[..KEY_PREFIX.len() + 8].to_string(),
+        hash: hash_token(&key),
+        key,
+    }
+}
+
+pub fn format_scopes(scopes: &[Permission]) -> String {
+    scopes
+        .iter()
+        .map(|scope| scope.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parse stored scopes, skipping names that no longer exist
+pub fn parse_scopes(scopes: &str) -> Vec<Permission> {
+    scopes.split(',').filter_map(Permission::parse).collect()
+}
+
+/// Owner and scopes of a valid API key
+pub struct AuthenticatedKey {
+    pub id: i64,
+    pub username: String,
+    pub role: String,
+    pub scopes: Vec<Permission>,
+    /// The owner has a temporary or expired password; keys are refused like
+    /// the owner's sessions until it is changed
+    pub password_change_required: bool,
+}
+
+/// Resolve an API key to its owner, rejecting unknown, revoked and expired keys
+/// and keys of suspended admins. Records the use on the key.
+pub async fn authenticate_key(
+    pool: &SqlitePool,
+    key: &str,
+    ip_address: Option<&str>,
+) -> Result<AuthenticatedKey, AppError> {
+    #[allow(clippy::type_complexity)]
+    let row: Option<(i64, String, bool, bool, String, String, bool, bool, Option<String>, String, bool)> = sqlx::query_as(
+        "SELECT k.id, k.scopes, k.revoked_at IS NOT NULL, k.expires_at <= datetime('now'),
+                a.username, a.role, a.suspended_at IS NOT NULL,
+                a.password_must_change, a.last_password_change, a.created_at, a.oidc_subject IS NOT NULL
+         FROM admin_api_keys k
+         INNER JOIN admins a ON a.id = k.admin_id
+         WHERE k.key_hash = ?"
+    )
+    .bind(hash_token(key))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to check API key: {}", e)))?;
+
+    let Some((id, scopes, revoked, expired, username, role, suspended, must_change, last_change, created_at, sso)) = row else {
+        return Err(AppError::Unauthorized("Invalid API key".to_string()));
+    };
+
+    if revoked || expired {
+        return Err(AppError::Unauthorized("API key has expired or been revoked".to_string()));
+    }
+
+    if suspended {
+        return Err(AppError::Unauthorized("Account is suspended".to_string()));
+    }
+
+    sqlx::query("UPDATE admin_api_keys SET last_used_at = datetime('now'), last_used_ip = ? WHERE id = ?")
+        .bind(ip_address)
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to record API key use: {}", e)))?;
+
+    Ok(AuthenticatedKey {
+        id,
+        username,
+        role,
+        scopes: parse_scopes(&scopes),
+        password_change_required: must_change
+            || (!sso && password_policy::is_expired(last_change.as_deref(), &created_at)),
+    })
+}
+
+/// Revoke every live key of an admin. Called whenever their password is reset
+/// or changed, so a key minted with a compromised password doesn't outlive it.
+pub async fn revoke_admin_api_keys(pool: &SqlitePool, admin_id: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE admin_api_keys SET revoked_at = datetime('now') WHERE admin_id = ? AND revoked_at IS NULL"
+    )
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,5 +1,9 @@
-use crate::models::Claims;
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use crate::{
+    models::{Claims, MfaClaims},
+    utils::{jwt_keys, permissions::Role},
+};
+use jsonwebtoken::encode;
+use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 pub fn hash_password(password: &str) -> Result<String, bcrypt::BcryptError> {
@@ -10,35 +14,117 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool, bcrypt::Bcryp
     bcrypt::verify(password, hash)
 }
 
-pub fn create_jwt(username: &str, is_super_admin: bool, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
+/// bcrypt hash, at the default cost, of a random password nobody knows
+const DUMMY_PASSWORD_HASH: &str = "$2b$12$8AqK6yTaCPoQd/O6K8AkT.WkwT0x7Nzr6sAtk2Ctbm5qGzbw9eOOi";
+
+/// Spend as long as a real password check without an account to check against,
+/// so unknown usernames can't be told apart by how fast they are refused
+pub fn verify_dummy_password(password: &str) {
+    let _ = bcrypt::verify(password, DUMMY_PASSWORD_HASH);
+}
+
+/// Lifetime of an access token in seconds (ACCESS_TOKEN_TTL_MINUTES, default 15 minutes)
+pub fn access_token_ttl_secs() -> u64 {
+    std::env::var("ACCESS_TOKEN_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(15)
+        * 60
+}
+
+pub fn create_jwt(
+    username: &str,
+    role: Role,
+    session_id: &str,
+    password_must_change: bool,
+) -> Result<String, jsonwebtoken::errors::Error> {
     let expiration = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
-        .as_secs() + 86400; // 24 hours
+        .as_secs() + access_token_ttl_secs();
 
     let claims = Claims {
         sub: username.to_string(),
         exp: expiration as usize,
-        is_super_admin,
+        is_super_admin: role == Role::SuperAdmin,
+        role,
+        sid: session_id.to_string(),
+        password_must_change,
+        api_key_id: None,
+        scopes: None,
+    };
+
+    let keys = jwt_keys::keys();
+    encode(&keys.header(), &claims, keys.encoding_key())
+}
+
+pub fn verify_jwt(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
+    jwt_keys::keys().decode::<Claims>(token)
+}
+
+/// Lifetime of the pre-auth token used between the password and TOTP steps
+const MFA_TOKEN_TTL_SECS: u64 = 5 * 60;
+
+pub fn create_mfa_token(username: &str, admin_id: i64) -> Result<String, jsonwebtoken::errors::Error> {
+    let expiration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() + MFA_TOKEN_TTL_SECS;
+
+    let claims = MfaClaims {
+        sub: username.to_string(),
+        exp: expiration as usize,
+        admin_id,
+        purpose: "mfa".to_string(),
     };
 
-    encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
+    let keys = jwt_keys::keys();
+    encode(&keys.header(), &claims, keys.encoding_key())
 }
 
-pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let validation = Validation::default();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &validation,
-    )?;
-    Ok(token_data.claims)
+pub fn verify_mfa_token(token: &str) -> Result<MfaClaims, jsonwebtoken::errors::Error> {
+    let claims = jwt_keys::keys().decode::<MfaClaims>(token)?;
+
+    if claims.purpose != "mfa" {
+        return Err(jsonwebtoken::errors::ErrorKind::InvalidToken.into());
+    }
+
+    Ok(claims)
 }
 
 pub fn generate_one_time_token() -> String {
     uuid::Uuid::new_v4().to_string()
 }
+
+/// Random alphanumeric password handed out by a super admin reset
+pub fn generate_temporary_password(length: usize) -> String {
+    use rand::{distributions::Alphanumeric, Rng};
+
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(length)
+        .map(char::from)
+        .collect()
+}
+
+/// Generate a high-entropy opaque secret (two v4 UUIDs, 244 random bits)
+pub fn generate_secret_token() -> String {
+    format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+/// SHA-256 hex digest used to store opaque tokens without keeping the plaintext
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dummy_hash_costs_as_much_as_a_real_one() {
+        let parts: bcrypt::HashParts = DUMMY_PASSWORD_HASH.parse().unwrap();
+        assert_eq!(parts.get_cost(), bcrypt::DEFAULT_COST);
+        assert!(!verify_password("admin123", DUMMY_PASSWORD_HASH).unwrap());
+    }
+}
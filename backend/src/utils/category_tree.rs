@@ -0,0 +1,125 @@
+use crate::{
+    error::AppError,
+    models::{Category, CategoryNode},
+};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Subquery selecting the media of a test: everything in the categories linked
+/// to the test (bound as the single `?`) and in all of their descendants.
+/// `UNION` (not `UNION ALL`) stops the recursion even if the data has a cycle.
+pub const TEST_MEDIA_FILE_IDS: &str = "
+    WITH RECURSIVE test_category_tree(id) AS (
+        SELECT category_id FROM test_categories WHERE test_id = ?
+        UNION
+        SELECT c.id FROM categories c INNER JOIN test_category_tree t ON c.parent_id = t.id
+    )
+    SELECT mfc.media_file_id
+    FROM media_file_categories mfc
+    INNER JOIN test_category_tree t ON t.id = mfc.category_id";
+
+/// Subquery selecting the categories of a test: the ones linked to the test
+/// (bound as the single `?`) and all of their descendants
+pub const TEST_CATEGORY_IDS: &str = "
+    WITH RECURSIVE test_category_tree(id) AS (
+        SELECT category_id FROM test_categories WHERE test_id = ?
+        UNION
+        SELECT c.id FROM categories c INNER JOIN test_category_tree t ON c.parent_id = t.id
+    )
+    SELECT id FROM test_category_tree";
+
+/// Recursive CTE (for use after `WITH RECURSIVE`) pairing each category
+/// (`root_id`) with itself and its descendants (`id`), skipping archived
+/// subcategories. Binding `?1` to a category id limits it to that category;
+/// binding NULL covers every category.
+pub const CATEGORY_SUBTREES: &str = "
+    category_subtree(root_id, id) AS (
+        SELECT id, id FROM categories WHERE ?1 IS NULL OR id = ?1
+        UNION
+        SELECT t.root_id, c.id FROM categories c
+        INNER JOIN category_subtree t ON c.parent_id = t.id
+        WHERE c.archived_at IS NULL
+    )";
+
+/// Arrange a flat list of categories into trees. Categories whose parent is
+/// missing from the list are treated as top-level.
+pub fn build_tree(categories: Vec<Category>) -> Vec<CategoryNode> {
+    let known: Vec<i64> = categories.iter().map(|c| c.id).collect();
+    let mut children: HashMap<Option<i64>, Vec<Category>> = HashMap::new();
+
+    for category in categories {
+        let parent = category.parent_id.filter(|id| known.contains(id));
+        children.entry(parent).or_default().push(category);
+    }
+
+    fn attach(parent: Option<i64>, children: &mut HashMap<Option<i64>, Vec<Category>>) -> Vec<CategoryNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|category| {
+                let id = category.id;
+                CategoryNode {
+                    category,
+                    children: attach(Some(id), children),
+                }
+            })
+            .collect()
+    }
+
+    attach(None, &mut children)
+}
+
+/// Ids of a category's descendants (not including the category itself)
+pub async fn descendant_ids(pool: &SqlitePool, category_id: i64) -> Result<Vec<i64>, AppError> {
+    sqlx::query_scalar(
+        "WITH RECURSIVE descendants(id) AS (
+             SELECT id FROM categories WHERE parent_id = ?
+             UNION
+             SELECT c.id FROM categories c INNER JOIN descendants d ON c.parent_id = d.id
+         )
+         SELECT id FROM descendants"
+    )
+    .bind(category_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))
+}
+
+/// Check that `parent_id` can be the parent of a category with `media_type`.
+/// For an existing category (`category_id`), also reject moving it under itself
+/// or one of its own descendants, which would create a cycle.
+pub async fn validate_parent(
+    pool: &SqlitePool,
+    category_id: Option<i64>,
+    parent_id: i64,
+    media_type: &str,
+) -> Result<(), AppError> {
+    let parent: Option<(String, String)> = sqlx::query_as("SELECT name, media_type FROM categories WHERE id = ? AND archived_at IS NULL")
+        .bind(parent_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (parent_name, parent_media_type) = parent
+        .ok_or_else(|| AppError::BadRequest(format!("Parent category with id {} does not exist", parent_id)))?;
+
+    // A test on the parent includes the child's media, so both must hold the same type
+    if parent_media_type != media_type {
+        return Err(AppError::BadRequest(format!(
+            "A {} category cannot be placed under {} category '{}'",
+            media_type, parent_media_type, parent_name
+        )));
+    }
+
+    if let Some(category_id) = category_id {
+        if parent_id == category_id || descendant_ids(pool, category_id).await?.contains(&parent_id) {
+            return Err(AppError::BadRequest(format!(
+                "Cannot move the category under '{}': it is the category itself or one of its subcategories",
+                parent_name
+            )));
+        }
+    }
+
+    Ok(())
+}
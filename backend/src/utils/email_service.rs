@@ -12,17 +12,6 @@ pub async fn send_test_invitation_email(
     test_description: Option<&str>,
     test_link: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Read SMTP configuration from environment
-    let smtp_host = env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string());
-    let smtp_port = env::var("SMTP_PORT")
-        .unwrap_or_else(|_| "587".to_string())
-        .parse::<u16>()
-        .unwrap_or(587);
-    let smtp_username = env::var("SMTP_USERNAME")?;
-    let smtp_password = env::var("SMTP_PASSWORD")?;
-    let from_email = env::var("SMTP_FROM_EMAIL").unwrap_or_else(|_| smtp_username.clone());
-    let from_name = env::var("SMTP_FROM_NAME").unwrap_or_else(|_| "Nocturnal Surveys".to_string());
-
     // Build HTML email content
     let description_html = if let Some(desc) = test_description {
         format!(
@@ -128,6 +117,103 @@ If you have any questions or encounter issues, please contact your administrator
         test_link
     );
 
+    send_email(
+        recipient_email,
+        &format!("Invitation: {} - Nocturnal Survey", test_name),
+        text_body,
+        html_body,
+    )
+}
+
+pub async fn send_password_reset_email(
+    recipient_email: &str,
+    username: &str,
+    reset_link: &str,
+    valid_minutes: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let html_body = format!(
+        r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+</head>
+<body style="font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif; margin: 0; padding: 0; background-color: #f3f4f6;">
+    <div style="max-width: 600px; margin: 0 auto; background-color: #ffffff; padding: 40px 30px;">
+        <!-- Header -->
+        <div style="text-align: center; margin-bottom: 30px;">
+            <h1 style="color: #1f2937; font-size: 28px; margin: 0 0 10px 0;">Reset Your Password</h1>
+            <p style="color: #6b7280; font-size: 16px; margin: 0;">Admin account: {}</p>
+        </div>
+
+        <p style="color: #374151; line-height: 1.6;">
+            Someone asked to reset the password for this admin account. If it was you, choose a new password below.
+        </p>
+
+        <!-- CTA Button -->
+        <div style="text-align: center; margin: 40px 0;">
+            <a href="{}" style="display: inline-block; background-color: #3b82f6; color: #ffffff; text-decoration: none; padding: 14px 32px; border-radius: 6px; font-size: 16px; font-weight: 600; box-shadow: 0 2px 4px rgba(0, 0, 0, 0.1);">Choose a New Password</a>
+        </div>
+
+        <!-- Important Notes -->
+        <div style="background-color: #fef3c7; border: 1px solid #fbbf24; border-radius: 6px; padding: 16px; margin: 30px 0;">
+            <p style="color: #78350f; font-size: 14px; margin: 0; font-weight: 500;">
+                ⚠️ <strong>Important:</strong> This link works once and expires in {} minutes. If you didn't ask for a reset, you can ignore this email.
+            </p>
+        </div>
+
+        <!-- Footer -->
+        <div style="text-align: center; margin-top: 40px; padding-top: 30px; border-top: 1px solid #e5e7eb;">
+            <p style="color: #9ca3af; font-size: 12px; margin: 10px 0 0 0;">
+                &copy; {} Nocturnal Surveys. All rights reserved.
+            </p>
+        </div>
+    </div>
+</body>
+</html>
+        "#,
+        html_escape(username),
+        reset_link,
+        valid_minutes,
+        chrono::Utc::now().year()
+    );
+
+    // Build plain text version as fallback
+    let text_body = format!(
+        r#"Reset Your Password
+
+Someone asked to reset the password for the admin account "{}". If it was you, choose a new password here:
+{}
+
+This link works once and expires in {} minutes. If you didn't ask for a reset, you can ignore this email.
+        "#,
+        username,
+        reset_link,
+        valid_minutes
+    );
+
+    send_email(recipient_email, "Password reset - Nocturnal Survey", text_body, html_body)
+}
+
+/// Send a multipart (plain text + HTML) email using the SMTP settings from the environment
+fn send_email(
+    recipient_email: &str,
+    subject: &str,
+    text_body: String,
+    html_body: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Read SMTP configuration from environment
+    let smtp_host = env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string());
+    let smtp_port = env::var("SMTP_PORT")
+        .unwrap_or_else(|_| "587".to_string())
+        .parse::<u16>()
+        .unwrap_or(587);
+    let smtp_username = env::var("SMTP_USERNAME")?;
+    let smtp_password = env::var("SMTP_PASSWORD")?;
+    let from_email = env::var("SMTP_FROM_EMAIL").unwrap_or_else(|_| smtp_username.clone());
+    let from_name = env::var("SMTP_FROM_NAME").unwrap_or_else(|_| "Nocturnal Surveys".to_string());
+
     // Parse email addresses
     let from_mailbox: Mailbox = format!("{} <{}>", from_name, from_email)
         .parse()
@@ -141,7 +227,7 @@ If you have any questions or encounter issues, please contact your administrator
     let email = Message::builder()
         .from(from_mailbox)
         .to(to_mailbox)
-        .subject(format!("Invitation: {} - Nocturnal Survey", test_name))
+        .subject(subject)
         .multipart(
             MultiPart::alternative()
                 .singlepart(
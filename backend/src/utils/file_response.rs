@@ -0,0 +1,237 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use std::{io::SeekFrom, path::Path, time::SystemTime};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt},
+};
+use tokio_util::io::ReaderStream;
+
+/// Byte range to send, inclusive at both ends
+#[derive(Debug, PartialEq)]
+enum RangeRequest {
+    Full,
+    Partial { start: u64, end: u64 },
+    Unsatisfiable,
+}
+
+/// Serve a file from disk with validators and single-range support:
+/// `ETag`/`Last-Modified` with `If-None-Match`/`If-Modified-Since` (304),
+/// `Range` (206, or 416 when it lies outside the file) honoring `If-Range`.
+/// Malformed and multi-range requests get 416 rather than a body the client
+/// didn't ask for.
+pub async fn serve_file(path: &Path, mime_type: &str, request_headers: &HeaderMap) -> Result<Response, StatusCode> {
+    let mut file = File::open(path).await.map_err(|_| StatusCode::NOT_FOUND)?;
+    let metadata = file.metadata().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+
+    let etag = entity_tag(len, modified);
+    let last_modified = modified.map(http_date);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    // Browsers must use the stored type and not guess one from the content
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    // Browsers may keep the file but must revalidate it, which the validators make cheap
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(value) = last_modified.as_deref().and_then(|date| HeaderValue::from_str(date).ok()) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+
+    if not_modified(request_headers, &etag, modified) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+
+    if let Ok(value) = HeaderValue::from_str(mime_type) {
+        headers.insert(header::CONTENT_TYPE, value);
+    }
+
+    let ranges: Vec<&HeaderValue> = request_headers.get_all(header::RANGE).iter().collect();
+    let range = if ranges.is_empty() || !if_range_matches(request_headers, &etag, last_modified.as_deref()) {
+        RangeRequest::Full
+    } else {
+        match ranges.as_slice() {
+            [value] => value.to_str().map_or(RangeRequest::Unsatisfiable, |value| parse_range(value, len)),
+            // Several Range headers are as unsupported as several ranges in one
+            _ => RangeRequest::Unsatisfiable,
+        }
+    };
+
+    match range {
+        RangeRequest::Full => {
+            headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+            Ok((StatusCode::OK, headers, Body::from_stream(ReaderStream::new(file))).into_response())
+        }
+        RangeRequest::Partial { start, end } => {
+            file.seek(SeekFrom::Start(start))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let length = end - start + 1;
+
+            headers.insert(header::CONTENT_LENGTH, HeaderValue::from(length));
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, len)) {
+                headers.insert(header::CONTENT_RANGE, value);
+            }
+            let body = Body::from_stream(ReaderStream::new(file.take(length)));
+            Ok((StatusCode::PARTIAL_CONTENT, headers, body).into_response())
+        }
+        RangeRequest::Unsatisfiable => {
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", len)) {
+                headers.insert(header::CONTENT_RANGE, value);
+            }
+            Ok((StatusCode::RANGE_NOT_SATISFIABLE, headers).into_response())
+        }
+    }
+}
+
+/// Strong validator from size and modification time; files are replaced
+/// rather than edited in place, so this changes whenever the content does
+fn entity_tag(len: u64, modified: Option<SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+    format!("\"{:x}-{:x}\"", len, nanos)
+}
+
+fn http_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value).ok().map(|date| date.with_timezone(&Utc))
+}
+
+/// Conditional GET: `If-None-Match` wins over `If-Modified-Since` when both are sent
+fn not_modified(request_headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = request_headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        // Weak comparison: W/ prefixes are ignored
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+
+    let since = request_headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_http_date);
+    match (since, modified) {
+        // HTTP dates have whole seconds
+        (Some(since), Some(modified)) => DateTime::<Utc>::from(modified).timestamp() <= since.timestamp(),
+        _ => false,
+    }
+}
+
+/// A `Range` only applies if `If-Range` is absent or still names the current
+/// file: an exact date, or the same strong ETag
+fn if_range_matches(request_headers: &HeaderMap, etag: &str, last_modified: Option<&str>) -> bool {
+    match request_headers.get(header::IF_RANGE).and_then(|v| v.to_str().ok()) {
+        None => true,
+        Some(value) if value.starts_with('"') => value == etag,
+        Some(value) => last_modified == Some(value),
+    }
+}
+
+/// Parse a `Range` header for a file of `len` bytes. Units other than bytes are
+/// ignored; a byte range that is malformed, or more than one, can't be served.
+fn parse_range(value: &str, len: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Unsatisfiable;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return RangeRequest::Unsatisfiable;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // Suffix range: the last N bytes
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return RangeRequest::Unsatisfiable,
+            Ok(suffix) if len > 0 => (len.saturating_sub(suffix), len - 1),
+            _ => return RangeRequest::Unsatisfiable,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return RangeRequest::Unsatisfiable,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return RangeRequest::Unsatisfiable,
+        },
+    };
+
+    if start >= len {
+        return RangeRequest::Unsatisfiable;
+    }
+
+    RangeRequest::Partial { start, end }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), RangeRequest::Partial { start: 0, end: 99 });
+        assert_eq!(parse_range("bytes=900-", 1000), RangeRequest::Partial { start: 900, end: 999 });
+        assert_eq!(parse_range("bytes=-100", 1000), RangeRequest::Partial { start: 900, end: 999 });
+        // An end past the file is clamped
+        assert_eq!(parse_range("bytes=500-5000", 1000), RangeRequest::Partial { start: 500, end: 999 });
+    }
+
+    #[test]
+    fn refuses_malformed_and_multiple_ranges() {
+        for value in [
+            "bytes=0-99,200-299",
+            "bytes=abc-def",
+            "bytes=99-0",
+            "bytes=100",
+            "bytes=-",
+            "bytes=-0",
+            "bytes=--5",
+            "bytes=1000-",
+        ] {
+            assert_eq!(parse_range(value, 1000), RangeRequest::Unsatisfiable, "Range: {}", value);
+        }
+    }
+
+    #[test]
+    fn ignores_other_range_units() {
+        assert_eq!(parse_range("items=0-5", 1000), RangeRequest::Full);
+    }
+
+    async fn status_for(ranges: &[&str]) -> StatusCode {
+        let path = std::env::temp_dir().join(format!("file_response_test_{}", uuid::Uuid::new_v4()));
+        tokio::fs::write(&path, vec![0u8; 1000]).await.unwrap();
+
+        let mut headers = HeaderMap::new();
+        for range in ranges {
+            headers.append(header::RANGE, HeaderValue::from_str(range).unwrap());
+        }
+        let response = serve_file(&path, "audio/wav", &headers).await.unwrap();
+
+        tokio::fs::remove_file(&path).await.unwrap();
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn answers_bad_range_headers_with_416() {
+        assert_eq!(status_for(&[]).await, StatusCode::OK);
+        assert_eq!(status_for(&["bytes=0-99"]).await, StatusCode::PARTIAL_CONTENT);
+        assert_eq!(status_for(&["bytes=0-99,200-299"]).await, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(status_for(&["bytes=x-y"]).await, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(status_for(&["bytes=0-99", "bytes=200-299"]).await, StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(status_for(&["bytes=5000-"]).await, StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::error::AppError;
+
+/// How many bytes from the start of a file are used to detect its type
+pub const SNIFF_BYTES: usize = 8192;
+
+/// Accepted when ALLOWED_UPLOAD_MIME_TYPES is not set. SVG and HTML are left
+/// out on purpose: both can carry scripts.
+const DEFAULT_ALLOWED_MIME_TYPES: &[&str] = &[
+    "audio/mpeg",
+    "audio/wav",
+    "audio/ogg",
+    "audio/opus",
+    "audio/flac",
+    "audio/mp4",
+    "audio/aac",
+    "audio/webm",
+    "audio/x-aiff",
+    "video/mp4",
+    "video/webm",
+    "video/quicktime",
+    "video/ogg",
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+    "image/webp",
+    "text/plain",
+    "text/markdown",
+    "text/csv",
+];
+
+/// Lowercased MIME type without parameters, with common aliases mapped to one
+/// name (e.g. `audio/x-wav` and `audio/wave` to `audio/wav`)
+pub fn canonical_mime(mime_type: &str) -> String {
+    let essence = mime_type.split(';').next().unwrap_or("").trim().to_lowercase();
+    let canonical = match essence.as_str() {
+        "audio/x-wav" | "audio/wave" | "audio/vnd.wave" => "audio/wav",
+        "audio/mp3" | "audio/x-mp3" | "audio/mpeg3" | "audio/x-mpeg" => "audio/mpeg",
+        "audio/x-flac" => "audio/flac",
+        "audio/m4a" | "audio/x-m4a" => "audio/mp4",
+        "audio/x-aac" | "audio/aacp" => "audio/aac",
+        "audio/aiff" => "audio/x-aiff",
+        "image/jpg" | "image/pjpeg" => "image/jpeg",
+        "image/x-png" => "image/png",
+        "video/x-m4v" => "video/mp4",
+        "text/x-markdown" => "text/markdown",
+        other => other,
+    };
+    canonical.to_string()
+}
+
+/// Upload allowlist (ALLOWED_UPLOAD_MIME_TYPES, comma-separated; `audio/*`
+/// style wildcards allowed)
+fn allowed_mime_types() -> Vec<String> {
+    match std::env::var("ALLOWED_UPLOAD_MIME_TYPES") {
+        Ok(value) if !value.trim().is_empty() => value
+            .split(',')
+            .map(canonical_mime)
+            .filter(|mime| !mime.is_empty())
+            .collect(),
+        _ => DEFAULT_ALLOWED_MIME_TYPES.iter().map(|mime| mime.to_string()).collect(),
+    }
+}
+
+/// Check a (canonical) MIME type against the upload allowlist
+pub fn ensure_allowed(mime_type: &str, filename: &str) -> Result<(), AppError> {
+    let allowed = allowed_mime_types().iter().any(|entry| match entry.strip_suffix("/*") {
+        Some(top_level) => mime_type.split('/').next() == Some(top_level),
+        None => entry == mime_type,
+    });
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "File '{}' has type '{}', which is not accepted for upload",
+            filename, mime_type
+        )))
+    }
+}
+
+/// Containers that hold either audio or video; the magic bytes can't tell
+/// which, so any type of the same family is taken as a match
+const CONTAINER_FAMILIES: &[&[&str]] = &[
+    &["audio/mp4", "video/mp4", "video/quicktime"],
+    &["audio/webm", "video/webm", "video/x-matroska"],
+    &["audio/ogg", "audio/opus", "video/ogg"],
+];
+
+fn same_type(declared: &str, detected: &str) -> bool {
+    declared == detected
+        || CONTAINER_FAMILIES
+            .iter()
+            .any(|family| family.contains(&declared) && family.contains(&detected))
+}
+
+/// Text is accepted if the start of the file is UTF-8 (a character cut off at
+/// the end of the sample is fine) without NUL bytes
+fn looks_like_text(head: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && head.len() == SNIFF_BYTES,
+    };
+    valid && !head.contains(&0)
+}
+
+/// Verify the declared (canonical) type of an upload against the first
+/// `SNIFF_BYTES` of its content. Binary formats must be recognized from their
+/// magic bytes and agree with the declared type; text must look like text and
+/// not be detected as markup or a script.
+pub fn verify_content(declared: &str, head: &[u8], filename: &str) -> Result<(), AppError> {
+    let detected = infer::get(head).map(|kind| canonical_mime(kind.mime_type()));
+
+    let matches = match (declared.starts_with("text/"), detected.as_deref()) {
+        (true, None) => looks_like_text(head),
+        (true, Some(_)) => false,
+        (false, Some(detected)) => same_type(declared, detected),
+        (false, None) => false,
+    };
+
+    if matches {
+        return Ok(());
+    }
+
+    Err(AppError::BadRequest(match detected {
+        Some(detected) => format!(
+            "File '{}' was uploaded as '{}' but its content is '{}'",
+            filename, declared, detected
+        ),
+        None => format!(
+            "File '{}' was uploaded as '{}' but its content is not a recognized file of that type",
+            filename, declared
+        ),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_HEAD: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01\0\0\0\x01\x08\x06\0\0\0";
+
+    #[test]
+    fn accepts_content_matching_the_declared_type() {
+        assert!(verify_content("image/png", PNG_HEAD, "a.png").is_ok());
+        assert!(verify_content("text/plain", b"just some notes\n", "a.txt").is_ok());
+    }
+
+    #[test]
+    fn rejects_markup_declared_as_png() {
+        let payloads: [&[u8]; 4] = [
+            b"<!DOCTYPE html><html><script>alert(1)</script></html>",
+            b"<html><body onload=alert(1)></body></html>",
+            b"<svg xmlns=\"http://www.w3.org/2000/svg\" onload=\"alert(1)\"/>",
+            b"<?xml version=\"1.0\"?><svg xmlns=\"http://www.w3.org/2000/svg\"><script>alert(1)</script></svg>",
+        ];
+
+        for payload in payloads {
+            assert!(
+                verify_content("image/png", payload, "evil.png").is_err(),
+                "{}",
+                String::from_utf8_lossy(payload)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_binary_declared_as_text() {
+        assert!(verify_content("text/plain", PNG_HEAD, "a.txt").is_err());
+        assert!(verify_content("text/plain", b"text\0with NUL", "a.txt").is_err());
+    }
+
+    #[test]
+    fn refuses_scriptable_types_by_default() {
+        assert!(ensure_allowed("image/svg+xml", "a.svg").is_err());
+        assert!(ensure_allowed("text/html", "a.html").is_err());
+        assert!(ensure_allowed("image/png", "a.png").is_ok());
+    }
+
+    #[test]
+    fn maps_aliases_to_canonical_types() {
+        assert_eq!(canonical_mime("Audio/X-WAV; codecs=1"), "audio/wav");
+        assert_eq!(canonical_mime("image/jpg"), "image/jpeg");
+        assert_eq!(canonical_mime("video/mp4"), "video/mp4");
+    }
+}
@@ -0,0 +1,297 @@
+use jsonwebtoken::{
+    decode, decode_header, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header,
+    Validation,
+};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, str::FromStr, sync::OnceLock};
+
+/// Shortest HMAC secret accepted, in bytes (256 bits for HS256)
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// Fragments of the placeholder secrets shipped in the example configs and docs
+const PLACEHOLDER_MARKERS: [&str; 6] = ["change", "default", "example", "placeholder", "your-", "secret-here"];
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+/// Keys for admin and MFA tokens. New tokens are signed with the current key and
+/// carry its `kid`; tokens signed with a previous key keep validating until they
+/// expire, so keys can be rotated without logging everyone out.
+pub struct SigningKeys {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    verification_keys: HashMap<String, VerificationKey>,
+}
+
+static KEYS: OnceLock<SigningKeys> = OnceLock::new();
+
+/// Load the keys from the environment. Called once at startup; an error means
+/// the server must not start.
+pub fn init() -> Result<(), String> {
+    let keys = SigningKeys::from_env()?;
+
+    tracing::info!(
+        "Signing tokens with {:?} key '{}' ({} key(s) accepted)",
+        keys.algorithm,
+        keys.kid,
+        keys.verification_keys.len()
+    );
+
+    KEYS.set(keys)
+        .map_err(|_| "JWT signing keys are already initialized".to_string())
+}
+
+/// Fixed HS256 keys for tests that issue tokens
+#[cfg(test)]
+pub fn init_for_tests() {
+    KEYS.get_or_init(|| SigningKeys::hmac("k8Jq2vN5xR7tW1zB4mC6pL9sD3fG0hYa", Algorithm::HS256));
+}
+
+pub fn keys() -> &'static SigningKeys {
+    KEYS.get().expect("JWT signing keys are not initialized")
+}
+
+/// Short identifier derived from key material, so the same key always gets the same `kid`
+fn key_id(material: &[u8]) -> String {
+    hex::encode(&Sha256::digest(material)[..8])
+}
+
+pub fn check_secret_strength(name: &str, secret: &str) -> Result<(), String> {
+    if secret.len() < MIN_SECRET_LENGTH {
+        return Err(format!(
+            "{} must be at least {} bytes long (generate one with `openssl rand -base64 48`)",
+            name, MIN_SECRET_LENGTH
+        ));
+    }
+
+    let lowercase = secret.to_lowercase();
+    if PLACEHOLDER_MARKERS.iter().any(|marker| lowercase.contains(marker)) {
+        return Err(format!("{} looks like a placeholder value; set a random secret", name));
+    }
+
+    let mut distinct: Vec<char> = secret.chars().collect();
+    distinct.sort_unstable();
+    distinct.dedup();
+    if distinct.len() < 10 {
+        return Err(format!("{} is too repetitive to be a random secret", name));
+    }
+
+    Ok(())
+}
+
+fn read_key_file(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("Failed to read key file {}: {}", path, e))
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+fn is_hmac(algorithm: Algorithm) -> bool {
+    matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+fn encoding_key_from_pem(algorithm: Algorithm, pem: &[u8]) -> Result<EncodingKey, jsonwebtoken::errors::Error> {
+    match algorithm {
+        Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(pem),
+        Algorithm::EdDSA => EncodingKey::from_ed_pem(pem),
+        _ => EncodingKey::from_rsa_pem(pem),
+    }
+}
+
+fn decoding_key_from_pem(algorithm: Algorithm, pem: &[u8]) -> Result<DecodingKey, jsonwebtoken::errors::Error> {
+    match algorithm {
+        Algorithm::ES256 | Algorithm::ES384 => DecodingKey::from_ec_pem(pem),
+        Algorithm::EdDSA => DecodingKey::from_ed_pem(pem),
+        _ => DecodingKey::from_rsa_pem(pem),
+    }
+}
+
+impl SigningKeys {
+    /// A single HMAC key, without reading the environment
+    #[cfg(test)]
+    fn hmac(secret: &str, algorithm: Algorithm) -> SigningKeys {
+        let kid = key_id(secret.as_bytes());
+        let mut verification_keys = HashMap::new();
+        verification_keys.insert(kid.clone(), VerificationKey {
+            algorithm,
+            key: DecodingKey::from_secret(secret.as_bytes()),
+        });
+        SigningKeys {
+            kid,
+            algorithm,
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            verification_keys,
+        }
+    }
+
+    /// JWT_ALGORITHM selects the algorithm (default HS256).
+    ///
+    /// - HMAC: the current secret is JWT_SECRET; JWT_PREVIOUS_SECRETS lists retired
+    ///   secrets that are still accepted.
+    /// - RSA, ECDSA, EdDSA: PEM keys from JWT_PRIVATE_KEY_FILE and JWT_PUBLIC_KEY_FILE;
+    ///   JWT_PREVIOUS_PUBLIC_KEY_FILES lists retired public keys. JWT_PREVIOUS_SECRETS
+    ///   (as HS256) is still honored to allow moving away from a shared secret.
+    fn from_env() -> Result<SigningKeys, String> {
+        let algorithm_name = std::env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string());
+        let algorithm = Algorithm::from_str(&algorithm_name)
+            .map_err(|_| format!("Unsupported JWT_ALGORITHM '{}'", algorithm_name))?;
+
+        let mut verification_keys = HashMap::new();
+
+        let previous_secret_algorithm = if is_hmac(algorithm) { algorithm } else { Algorithm::HS256 };
+        for secret in env_list("JWT_PREVIOUS_SECRETS") {
+            check_secret_strength("Each JWT_PREVIOUS_SECRETS entry", &secret)?;
+            verification_keys.insert(key_id(secret.as_bytes()), VerificationKey {
+                algorithm: previous_secret_algorithm,
+                key: DecodingKey::from_secret(secret.as_bytes()),
+            });
+        }
+
+        let (kid, encoding_key) = if is_hmac(algorithm) {
+            let secret = std::env::var("JWT_SECRET")
+                .map_err(|_| "JWT_SECRET must be set".to_string())?;
+            check_secret_strength("JWT_SECRET", &secret)?;
+
+            let kid = key_id(secret.as_bytes());
+            verification_keys.insert(kid.clone(), VerificationKey {
+                algorithm,
+                key: DecodingKey::from_secret(secret.as_bytes()),
+            });
+
+            (kid, EncodingKey::from_secret(secret.as_bytes()))
+        } else {
+            let private_path = std::env::var("JWT_PRIVATE_KEY_FILE")
+                .map_err(|_| format!("JWT_PRIVATE_KEY_FILE must be set for {:?}", algorithm))?;
+            let public_path = std::env::var("JWT_PUBLIC_KEY_FILE")
+                .map_err(|_| format!("JWT_PUBLIC_KEY_FILE must be set for {:?}", algorithm))?;
+
+            let encoding_key = encoding_key_from_pem(algorithm, &read_key_file(&private_path)?)
+                .map_err(|e| format!("Invalid private key in {}: {}", private_path, e))?;
+
+            let mut public_paths = vec![public_path];
+            public_paths.extend(env_list("JWT_PREVIOUS_PUBLIC_KEY_FILES"));
+
+            let mut current_kid = None;
+            for path in public_paths {
+                let pem = read_key_file(&path)?;
+                let key = decoding_key_from_pem(algorithm, &pem)
+                    .map_err(|e| format!("Invalid public key in {}: {}", path, e))?;
+                let kid = key_id(&pem);
+                current_kid.get_or_insert_with(|| kid.clone());
+                verification_keys.insert(kid, VerificationKey { algorithm, key });
+            }
+
+            (current_kid.unwrap_or_default(), encoding_key)
+        };
+
+        Ok(SigningKeys { kid, algorithm, encoding_key, verification_keys })
+    }
+
+    /// Header for a new token, naming the current key
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = Some(self.kid.clone());
+        header
+    }
+
+    pub fn encoding_key(&self) -> &EncodingKey {
+        &self.encoding_key
+    }
+
+    /// Verify a token against the key its `kid` names. Tokens without a `kid`
+    /// (issued before key IDs were added) are checked against the current key.
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, jsonwebtoken::errors::Error> {
+        let header = decode_header(token)?;
+        let kid = header.kid.as_deref().unwrap_or(&self.kid);
+
+        let Some(key) = self.verification_keys.get(kid) else {
+            return Err(ErrorKind::InvalidSignature.into());
+        };
+
+        // The key fixes the algorithm, so a token can't pick a weaker one
+        let validation = Validation::new(key.algorithm);
+        Ok(decode::<T>(token, &key.key, &validation)?.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_encoding::BASE64URL_NOPAD;
+    use jsonwebtoken::encode;
+    use serde::{Deserialize, Serialize};
+
+    const SECRET: &str = "k8Jq2vN5xR7tW1zB4mC6pL9sD3fG0hYa";
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "admin".to_string(),
+            exp: (chrono::Utc::now().timestamp() + 600) as usize,
+        }
+    }
+
+    #[test]
+    fn accepts_token_signed_with_current_key() {
+        let keys = SigningKeys::hmac(SECRET, Algorithm::HS256);
+        let token = encode(&keys.header(), &claims(), keys.encoding_key()).unwrap();
+
+        let decoded: TestClaims = keys.decode(&token).unwrap();
+        assert_eq!(decoded.sub, "admin");
+    }
+
+    #[test]
+    fn rejects_algorithm_other_than_the_keys() {
+        let keys = SigningKeys::hmac(SECRET, Algorithm::HS256);
+
+        // Same secret and kid, but the token claims HS512
+        let mut header = keys.header();
+        header.alg = Algorithm::HS512;
+        let token = encode(&header, &claims(), keys.encoding_key()).unwrap();
+
+        let err = keys.decode::<TestClaims>(&token).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidAlgorithm);
+    }
+
+    #[test]
+    fn rejects_unsigned_token() {
+        let keys = SigningKeys::hmac(SECRET, Algorithm::HS256);
+        let header = BASE64URL_NOPAD.encode(format!(r#"{{"alg":"none","kid":"{}"}}"#, keys.kid).as_bytes());
+        let payload = BASE64URL_NOPAD.encode(&serde_json::to_vec(&claims()).unwrap());
+
+        assert!(keys.decode::<TestClaims>(&format!("{}.{}.", header, payload)).is_err());
+    }
+
+    #[test]
+    fn rejects_token_from_unknown_key() {
+        let keys = SigningKeys::hmac(SECRET, Algorithm::HS256);
+        let other = SigningKeys::hmac("Vb7Nq1Zr4Xt8Kp2Lm6Wc9Hd3Js5Fg0Ya", Algorithm::HS256);
+        let token = encode(&other.header(), &claims(), other.encoding_key()).unwrap();
+
+        let err = keys.decode::<TestClaims>(&token).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::InvalidSignature);
+    }
+
+    #[test]
+    fn refuses_weak_secrets() {
+        assert!(check_secret_strength("JWT_SECRET", "too-short").is_err());
+        assert!(check_secret_strength("JWT_SECRET", "change-this-secret-in-production-please").is_err());
+        assert!(check_secret_strength("JWT_SECRET", &"ab".repeat(20)).is_err());
+        assert!(check_secret_strength("JWT_SECRET", SECRET).is_ok());
+    }
+}
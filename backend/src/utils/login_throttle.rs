@@ -0,0 +1,321 @@
+use crate::error::AppError;
+use sqlx::SqlitePool;
+
+/// Failed attempts allowed per username before the account is locked (LOGIN_MAX_ATTEMPTS_PER_USER)
+fn max_attempts_per_user() -> i64 {
+    env_i64("LOGIN_MAX_ATTEMPTS_PER_USER", 5)
+}
+
+/// Failed attempts allowed per client IP before the IP is locked (LOGIN_MAX_ATTEMPTS_PER_IP)
+fn max_attempts_per_ip() -> i64 {
+    env_i64("LOGIN_MAX_ATTEMPTS_PER_IP", 20)
+}
+
+/// How long a lockout lasts, and how long failures are remembered (LOGIN_LOCKOUT_MINUTES)
+fn lockout_minutes() -> i64 {
+    env_i64("LOGIN_LOCKOUT_MINUTES", 15)
+}
+
+fn env_i64(name: &str, default: i64) -> i64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(default)
+}
+
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username)
+}
+
+pub fn ip_key(ip_address: &str) -> String {
+    format!("ip:{}", ip_address)
+}
+
+/// Seconds a client must wait after its latest failure before trying again.
+///
+/// The first two failures are free; after that the wait doubles (2s, 4s, 8s, ...)
+/// up to 60 seconds.
+fn progressive_delay_secs(failed_count: i64) -> i64 {
+    if failed_count < 3 {
+        0
+    } else {
+        (1i64 << (failed_count - 2).min(6)).min(60)
+    }
+}
+
+/// A login attempt already counted against the throttle.
+///
+/// Attempts are counted before the credentials are checked so parallel requests
+/// can't all slip past the limit while bcrypt runs. A failed attempt simply keeps
+/// its reservation; a successful one must `release` it.
+pub struct Reservation {
+    keys: Vec<(String, i64)>,
+    /// The attempt used up the last try for the username, so it is locked if it fails
+    pub locked: bool,
+}
+
+impl Reservation {
+    /// Take the attempt back after the credentials turned out to be correct
+    pub async fn release(self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        release_keys(pool, &self.keys).await
+    }
+}
+
+/// Count an attempt against the username and (when known) the client IP, or
+/// reject it with `TooManyRequests` if either is locked or still inside its
+/// progressive delay window.
+pub async fn reserve(
+    pool: &SqlitePool,
+    username: &str,
+    ip_address: Option<&str>,
+) -> Result<Reservation, AppError> {
+    let mut keys = vec![(user_key(username), max_attempts_per_user())];
+    if let Some(ip) = ip_address {
+        keys.push((ip_key(ip), max_attempts_per_ip()));
+    }
+
+    let user = user_key(username);
+    let mut reserved: Vec<(String, i64)> = Vec::new();
+    let mut locked = false;
+    for (key, max_attempts) in keys {
+        let now_locked = match reserve_key(pool, &key, max_attempts).await {
+            Ok(Some(now_locked)) => now_locked,
+            refused => {
+                // Don't hold on to the keys counted before this one refused
+                release_keys(pool, &reserved).await.ok();
+                return Err(match refused {
+                    Err(e) => AppError::InternalServerError(format!("Failed to record login attempt: {}", e)),
+                    _ => retry_error(pool, &key).await,
+                });
+            }
+        };
+
+        if now_locked {
+            tracing::warn!("Login locked for {} after {} attempts", key, max_attempts);
+            locked |= key == user;
+        }
+        reserved.push((key, max_attempts));
+    }
+
+    Ok(Reservation { keys: reserved, locked })
+}
+
+/// Count one attempt against a key in a single statement, so concurrent attempts
+/// each see the count left by the previous one.
+///
+/// Returns `None` without counting when the key is locked or inside its delay
+/// window, otherwise whether this attempt reached `max_attempts` and locked it.
+async fn reserve_key(
+    pool: &SqlitePool,
+    key: &str,
+    max_attempts: i64,
+) -> Result<Option<bool>, sqlx::Error> {
+    // Failures older than the lockout window, or from before an expired lock, start over.
+    // The lock is set up front; a successful attempt lifts it again on release.
+    // The delay mirrors progressive_delay_secs.
+    let reserved: Option<bool> = sqlx::query_scalar(
+        "INSERT INTO login_attempts (key, failed_count, last_failed_at, locked_until)
+         VALUES (?1, 1, datetime('now'), CASE WHEN 1 >= ?3 THEN datetime('now', ?4) END)
+         ON CONFLICT(key) DO UPDATE SET
+             failed_count = CASE
+                 WHEN last_failed_at < datetime('now', ?2) OR locked_until IS NOT NULL THEN 1
+                 ELSE failed_count + 1
+             END,
+             locked_until = CASE
+                 WHEN (CASE
+                     WHEN last_failed_at < datetime('now', ?2) OR locked_until IS NOT NULL THEN 1
+                     ELSE failed_count + 1
+                 END) >= ?3 THEN datetime('now', ?4)
+             END,
+             last_failed_at = datetime('now')
+         WHERE NOT (locked_until IS NOT NULL AND locked_until > datetime('now'))
+           AND NOT (
+               locked_until IS NULL
+               AND last_failed_at >= datetime('now', ?2)
+               AND failed_count >= 3
+               AND CAST(strftime('%s', 'now') AS INTEGER) - CAST(strftime('%s', last_failed_at) AS INTEGER)
+                   < MIN(60, 1 << MIN(failed_count - 2, 6))
+           )
+         RETURNING locked_until IS NOT NULL"
+    )
+    .bind(key)
+    .bind(format!("-{} minutes", lockout_minutes()))
+    .bind(max_attempts)
+    .bind(format!("+{} minutes", lockout_minutes()))
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(reserved)
+}
+
+/// Undo reservations: drop one attempt from each key and lift a lock it caused
+async fn release_keys(pool: &SqlitePool, keys: &[(String, i64)]) -> Result<(), sqlx::Error> {
+    for (key, max_attempts) in keys {
+        sqlx::query(
+            "UPDATE login_attempts SET
+                 failed_count = MAX(failed_count - 1, 0),
+                 locked_until = CASE WHEN failed_count - 1 >= ? THEN locked_until END
+             WHERE key = ?"
+        )
+        .bind(max_attempts)
+        .bind(key)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Explain why a key refused an attempt
+async fn retry_error(pool: &SqlitePool, key: &str) -> AppError {
+    let row: Option<(i64, Option<i64>, i64)> = sqlx::query_as(
+        "SELECT failed_count,
+                CAST(strftime('%s', locked_until) AS INTEGER) - CAST(strftime('%s', 'now') AS INTEGER),
+                CAST(strftime('%s', 'now') AS INTEGER) - CAST(strftime('%s', last_failed_at) AS INTEGER)
+         FROM login_attempts WHERE key = ?"
+    )
+    .bind(key)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten();
+
+    if let Some((failed_count, lock_remaining, since_last_failure)) = row {
+        if let Some(remaining) = lock_remaining.filter(|r| *r > 0) {
+            return AppError::TooManyRequests(format!(
+                "Too many failed login attempts. Try again in {} minute(s)",
+                (remaining + 59) / 60
+            ));
+        }
+
+        let wait = progressive_delay_secs(failed_count) - since_last_failure;
+        if wait > 0 {
+            return AppError::TooManyRequests(format!(
+                "Too many failed login attempts. Try again in {} second(s)",
+                wait
+            ));
+        }
+    }
+
+    AppError::TooManyRequests("Too many failed login attempts. Try again later".to_string())
+}
+
+/// Clear the failure history of a username (successful login or super admin unlock).
+///
+/// The IP counter is deliberately left alone so one valid account can't be used to
+/// reset an IP that is guessing other accounts.
+pub async fn clear_user(pool: &SqlitePool, username: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM login_attempts WHERE key = ?")
+        .bind(user_key(username))
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    /// Move the latest failures out of the progressive delay window (but not the lockout window)
+    async fn skip_delay(pool: &SqlitePool) {
+        sqlx::query("UPDATE login_attempts SET last_failed_at = datetime('now', '-2 minutes')")
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn failed_count(pool: &SqlitePool, key: &str) -> Option<i64> {
+        sqlx::query_scalar("SELECT failed_count FROM login_attempts WHERE key = ?")
+            .bind(key)
+            .fetch_optional(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn delay_grows_after_two_failures() {
+        let delays: Vec<i64> = (1..=10).map(progressive_delay_secs).collect();
+        assert_eq!(delays, vec![0, 0, 2, 4, 8, 16, 32, 60, 60, 60]);
+    }
+
+    #[tokio::test]
+    async fn locks_username_after_max_attempts() {
+        let pool = test_pool().await;
+
+        for attempt in 1..=max_attempts_per_user() {
+            let reservation = reserve(&pool, "alice", Some("10.0.0.1")).await.unwrap();
+            assert_eq!(reservation.locked, attempt == max_attempts_per_user());
+            skip_delay(&pool).await;
+        }
+
+        // Locked for every client, not just the one that failed
+        let result = reserve(&pool, "alice", Some("10.0.0.2")).await;
+        assert!(matches!(result, Err(AppError::TooManyRequests(msg)) if msg.contains("minute")));
+        assert!(reserve(&pool, "bob", Some("10.0.0.1")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn locks_ip_after_max_attempts() {
+        let pool = test_pool().await;
+
+        for attempt in 1..=max_attempts_per_ip() {
+            reserve(&pool, &format!("user{}", attempt), Some("10.0.0.1")).await.unwrap();
+            skip_delay(&pool).await;
+        }
+
+        let result = reserve(&pool, "someone-else", Some("10.0.0.1")).await;
+        assert!(matches!(result, Err(AppError::TooManyRequests(_))));
+        // The refused attempt isn't counted against the username
+        assert_eq!(failed_count(&pool, &user_key("someone-else")).await.unwrap_or(0), 0);
+        assert!(reserve(&pool, "someone-else", Some("10.0.0.2")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn delays_quick_retries() {
+        let pool = test_pool().await;
+
+        for _ in 0..3 {
+            reserve(&pool, "alice", None).await.unwrap();
+        }
+
+        let result = reserve(&pool, "alice", None).await;
+        assert!(matches!(result, Err(AppError::TooManyRequests(msg)) if msg.contains("second")));
+    }
+
+    #[tokio::test]
+    async fn release_takes_back_a_successful_attempt() {
+        let pool = test_pool().await;
+
+        for _ in 1..max_attempts_per_user() {
+            reserve(&pool, "alice", Some("10.0.0.1")).await.unwrap();
+            skip_delay(&pool).await;
+        }
+
+        // The last try succeeds, so it must not lock the account
+        let reservation = reserve(&pool, "alice", Some("10.0.0.1")).await.unwrap();
+        assert!(reservation.locked);
+        reservation.release(&pool).await.unwrap();
+
+        assert_eq!(failed_count(&pool, &user_key("alice")).await, Some(max_attempts_per_user() - 1));
+        assert_eq!(failed_count(&pool, &ip_key("10.0.0.1")).await, Some(max_attempts_per_user() - 1));
+        skip_delay(&pool).await;
+        assert!(reserve(&pool, "alice", Some("10.0.0.1")).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn clear_user_keeps_the_ip_counter() {
+        let pool = test_pool().await;
+
+        for _ in 0..3 {
+            reserve(&pool, "alice", Some("10.0.0.1")).await.unwrap();
+            skip_delay(&pool).await;
+        }
+
+        assert!(clear_user(&pool, "alice").await.unwrap());
+        assert!(!clear_user(&pool, "alice").await.unwrap());
+        assert_eq!(failed_count(&pool, &user_key("alice")).await, None);
+        assert_eq!(failed_count(&pool, &ip_key("10.0.0.1")).await, Some(3));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::models::MediaMetadata;
+use image::{ExtendedColorType, ImageDecoder, ImageReader};
+use std::{fs::File, io::BufReader, path::Path};
+use symphonia::core::{
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Extract technical metadata from a local file. Parsing runs on the blocking
+/// pool; files that can't be parsed are logged and get no metadata rather than
+/// failing the upload.
+pub async fn extract(path: &Path, media_type: &str, mime_type: &str) -> Option<MediaMetadata> {
+    let path = path.to_path_buf();
+    let media_type = media_type.to_string();
+    let mime_type = mime_type.to_string();
+
+    let result = tokio::task::spawn_blocking(move || {
+        let metadata = match media_type.as_str() {
+            "audio" => audio_metadata(&path, &mime_type),
+            "image" => image_metadata(&path),
+            "video" => video_metadata(&path, &mime_type),
+            "text" => text_metadata(&path),
+            _ => Ok(MediaMetadata::default()),
+        };
+        (path, metadata)
+    })
+    .await;
+
+    match result {
+        Ok((_, Ok(metadata))) => Some(metadata),
+        Ok((path, Err(e))) => {
+            tracing::warn!("Failed to read metadata from {}: {}", path.display(), e);
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Metadata extraction task failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Duration, sample rate, channels and bit depth of the first audio track
+fn audio_metadata(path: &Path, mime_type: &str) -> Result<MediaMetadata, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    hint.mime_type(mime_type);
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| e.to_string())?;
+    let track = probed
+        .format
+        .default_track()
+        .ok_or_else(|| "no audio track".to_string())?;
+    let params = &track.codec_params;
+
+    let duration_seconds = match (params.n_frames, params.time_base, params.sample_rate) {
+        (Some(frames), Some(time_base), _) => {
+            let time = time_base.calc_time(frames);
+            Some(time.seconds as f64 + time.frac)
+        }
+        (Some(frames), None, Some(rate)) if rate > 0 => Some(frames as f64 / rate as f64),
+        _ => None,
+    };
+
+    Ok(MediaMetadata {
+        duration_seconds,
+        sample_rate: params.sample_rate,
+        channels: params.channels.map(|channels| channels.count() as u32),
+        bit_depth: params.bits_per_sample.or(params.bits_per_coded_sample),
+        ..Default::default()
+    })
+}
+
+/// Dimensions and color space, read from the image header without decoding it
+fn image_metadata(path: &Path) -> Result<MediaMetadata, String> {
+    let decoder = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .into_decoder()
+        .map_err(|e| e.to_string())?;
+    let (width, height) = decoder.dimensions();
+
+    Ok(MediaMetadata {
+        width: Some(width),
+        height: Some(height),
+        color_space: color_space(decoder.original_color_type()).map(str::to_string),
+        ..Default::default()
+    })
+}
+
+fn color_space(color_type: ExtendedColorType) -> Option<&'static str> {
+    use ExtendedColorType::*;
+    match color_type {
+        L1 | L2 | L4 | L8 | L16 => Some("grayscale"),
+        La1 | La2 | La4 | La8 | La16 => Some("grayscale-alpha"),
+        Rgb1 | Rgb2 | Rgb4 | Rgb8 | Rgb16 | Rgb32F | Bgr8 => Some("rgb"),
+        Rgba1 | Rgba2 | Rgba4 | Rgba8 | Rgba16 | Rgba32F | Bgra8 => Some("rgba"),
+        Cmyk8 => Some("cmyk"),
+        _ => None,
+    }
+}
+
+/// Duration and resolution from the container: MP4/QuickTime or Matroska/WebM.
+/// Other containers (e.g. Ogg) get no metadata.
+fn video_metadata(path: &Path, mime_type: &str) -> Result<MediaMetadata, String> {
+    match mime_type {
+        "video/mp4" | "video/quicktime" => {
+            let file = File::open(path).map_err(|e| e.to_string())?;
+            let size = file.metadata().map_err(|e| e.to_string())?.len();
+            let mp4 = mp4::Mp4Reader::read_header(BufReader::new(file), size).map_err(|e| e.to_string())?;
+            let video = mp4
+                .tracks()
+                .values()
+                .find(|track| matches!(track.track_type(), Ok(mp4::TrackType::Video)));
+
+            Ok(MediaMetadata {
+                duration_seconds: Some(mp4.duration().as_secs_f64()),
+                width: video.map(|track| track.width() as u32),
+                height: video.map(|track| track.height() as u32),
+                ..Default::default()
+            })
+        }
+        "video/webm" | "video/x-matroska" => {
+            let mkv = matroska::open(path).map_err(|e| e.to_string())?;
+            let video = mkv.tracks.iter().find_map(|track| match &track.settings {
+                matroska::Settings::Video(video) => Some(video),
+                _ => None,
+            });
+
+            Ok(MediaMetadata {
+                duration_seconds: mkv.info.duration.map(|duration| duration.as_secs_f64()),
+                width: video.map(|video| video.pixel_width as u32),
+                height: video.map(|video| video.pixel_height as u32),
+                ..Default::default()
+            })
+        }
+        _ => Ok(MediaMetadata::default()),
+    }
+}
+
+/// Character, word and line counts. A last line without a trailing newline
+/// still counts as a line.
+fn text_metadata(path: &Path) -> Result<MediaMetadata, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let text = String::from_utf8_lossy(&bytes);
+
+    let newlines = text.matches('\n').count() as u64;
+    let lines = if text.is_empty() || text.ends_with('\n') { newlines } else { newlines + 1 };
+
+    Ok(MediaMetadata {
+        characters: Some(text.chars().count() as u64),
+        words: Some(text.split_whitespace().count() as u64),
+        lines: Some(lines),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,122 @@
+use crate::{models::MediaUrlSignature, utils::jwt_keys::check_secret_strength};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// How long a signed media URL stays valid, unless MEDIA_URL_TTL_MINUTES says otherwise
+const DEFAULT_TTL_MINUTES: i64 = 60;
+
+static KEY: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// Load the key for signed admin media URLs from MEDIA_URL_SECRET. It is required
+/// like JWT_SECRET: a per-process random key would silently break every signed URL
+/// on restart and between instances. Called once at startup; an error means the
+/// server must not start.
+pub fn init() -> Result<(), String> {
+    let secret = std::env::var("MEDIA_URL_SECRET")
+        .ok()
+        .filter(|secret| !secret.trim().is_empty())
+        .ok_or_else(|| "MEDIA_URL_SECRET must be set".to_string())?;
+    check_secret_strength("MEDIA_URL_SECRET", &secret)?;
+
+    KEY.set(secret.into_bytes())
+        .map_err(|_| "Media URL key is already initialized".to_string())
+}
+
+/// Fixed key for tests that sign or check media URLs
+#[cfg(test)]
+pub fn init_for_tests() {
+    KEY.get_or_init(|| b"Zq8rT1vX4mN7pL2kW9sB6dF3hJ0cY5gA".to_vec());
+}
+
+/// Whether the unauthenticated `/api/media/:id/...` routes are mounted
+/// (PUBLIC_MEDIA_ROUTES, default false). They serve any file by its sequential
+/// id, so the whole library can be downloaded by counting.
+pub fn public_routes_enabled() -> bool {
+    std::env::var("PUBLIC_MEDIA_ROUTES")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+fn ttl_minutes() -> i64 {
+    std::env::var("MEDIA_URL_TTL_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .unwrap_or(DEFAULT_TTL_MINUTES)
+}
+
+fn mac(media_id: i64, expires: i64) -> HmacSha256 {
+    let key = KEY.get().expect("Media URL key is not initialized");
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(format!("media:{}:{}", media_id, expires).as_bytes());
+    mac
+}
+
+/// Signature letting the bearer fetch a media file and its previews through
+/// the admin media routes until it expires
+pub fn sign(media_id: i64) -> MediaUrlSignature {
+    let expires = Utc::now().timestamp() + ttl_minutes() * 60;
+    MediaUrlSignature {
+        expires,
+        signature: hex::encode(mac(media_id, expires).finalize().into_bytes()),
+    }
+}
+
+/// Check a signature in constant time; expired ones are refused
+pub fn verify(media_id: i64, signature: &MediaUrlSignature) -> bool {
+    if signature.expires < Utc::now().timestamp() {
+        return false;
+    }
+    let Ok(bytes) = hex::decode(&signature.signature) else {
+        return false;
+    };
+    mac(media_id, signature.expires).verify_slice(&bytes).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_own_signature() {
+        init_for_tests();
+        let signature = sign(7);
+        assert!(signature.expires > Utc::now().timestamp());
+        assert!(verify(7, &signature));
+    }
+
+    #[test]
+    fn refuses_expired_signature() {
+        init_for_tests();
+        let expires = Utc::now().timestamp() - 1;
+        let signature = MediaUrlSignature {
+            expires,
+            signature: hex::encode(mac(7, expires).finalize().into_bytes()),
+        };
+        assert!(!verify(7, &signature));
+    }
+
+    #[test]
+    fn refuses_tampered_signature() {
+        init_for_tests();
+        let signature = sign(7);
+
+        // Another file
+        assert!(!verify(8, &signature));
+
+        // A later expiry
+        let extended = MediaUrlSignature { expires: signature.expires + 3600, signature: signature.signature.clone() };
+        assert!(!verify(7, &extended));
+
+        // A changed or malformed signature
+        let mut flipped = signature.signature.clone().into_bytes();
+        flipped[0] = if flipped[0] == b'0' { b'1' } else { b'0' };
+        let flipped = MediaUrlSignature { expires: signature.expires, signature: String::from_utf8(flipped).unwrap() };
+        assert!(!verify(7, &flipped));
+        assert!(!verify(7, &MediaUrlSignature { signature: "not hex".to_string(), ..signature }));
+    }
+}
@@ -1,3 +1,23 @@
 pub mod activity_logger;
+pub mod api_keys;
 pub mod auth;
+pub mod category_tree;
 pub mod email_service;
+pub mod file_response;
+pub mod file_type;
+pub mod jwt_keys;
+pub mod login_throttle;
+pub mod media_metadata;
+pub mod media_urls;
+pub mod oidc;
+pub mod password_policy;
+pub mod password_reset;
+pub mod permissions;
+pub mod previews;
+pub mod request_meta;
+pub mod rubric;
+pub mod session;
+pub mod session_cookies;
+pub mod test_references;
+pub mod totp;
+pub mod uploads;
@@ -0,0 +1,323 @@
+use crate::{error::AppError, utils::permissions::Role};
+use data_encoding::BASE64URL_NOPAD;
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use rand::RngCore;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::{sync::OnceLock, time::Duration};
+
+/// Limits for calls to the identity provider, so a hung provider can't hold
+/// login requests open indefinitely
+const PROVIDER_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const PROVIDER_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Client shared by discovery, token exchange and key set requests
+fn http_client() -> &'static reqwest::Client {
+    HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .connect_timeout(PROVIDER_CONNECT_TIMEOUT)
+            .timeout(PROVIDER_REQUEST_TIMEOUT)
+            .build()
+            .expect("Failed to build the identity provider HTTP client")
+    })
+}
+
+/// Identity provider settings, read from the environment. SSO is enabled when
+/// OIDC_ISSUER is set.
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    /// Backend callback URL registered with the provider (OIDC_REDIRECT_URI)
+    pub redirect_uri: String,
+    pub scopes: String,
+    /// Claim used as the admin username (OIDC_USERNAME_CLAIM, default `preferred_username`)
+    pub username_claim: String,
+    /// Claim holding the user's groups; dots address nested claims such as `realm_access.roles`
+    pub groups_claim: String,
+    pub super_admin_groups: Vec<String>,
+    pub editor_groups: Vec<String>,
+    pub test_owner_groups: Vec<String>,
+    pub viewer_groups: Vec<String>,
+    /// Role for users in none of the mapped groups; without it they are refused
+    pub default_role: Option<Role>,
+    /// Allow an SSO identity to take over an existing local admin with the same username.
+    /// This trusts the provider's username claim, so only enable it when users can't pick
+    /// their own usernames there; super admins are never linked this way.
+    pub link_existing_admins: bool,
+}
+
+fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|group| group.trim().to_string())
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+impl OidcConfig {
+    /// `Ok(None)` when SSO is disabled; an error when it is enabled but can't work
+    pub fn from_env() -> Result<Option<OidcConfig>, String> {
+        let Some(issuer) = std::env::var("OIDC_ISSUER").ok().filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        let client_id = std::env::var("OIDC_CLIENT_ID")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| "OIDC_CLIENT_ID must be set when OIDC_ISSUER is".to_string())?;
+
+        Ok(Some(OidcConfig {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: std::env::var("OIDC_CLIENT_SECRET").ok().filter(|v| !v.is_empty()),
+            redirect_uri: std::env::var("OIDC_REDIRECT_URI").unwrap_or_else(|_| {
+                "http://localhost:34193/api/admin/oidc/callback".to_string()
+            }),
+            scopes: std::env::var("OIDC_SCOPES")
+                .unwrap_or_else(|_| "openid profile email".to_string()),
+            username_claim: std::env::var("OIDC_USERNAME_CLAIM")
+                .unwrap_or_else(|_| "preferred_username".to_string()),
+            groups_claim: std::env::var("OIDC_GROUPS_CLAIM")
+                .unwrap_or_else(|_| "groups".to_string()),
+            super_admin_groups: env_list("OIDC_SUPER_ADMIN_GROUPS"),
+            editor_groups: env_list("OIDC_EDITOR_GROUPS"),
+            test_owner_groups: env_list("OIDC_TEST_OWNER_GROUPS"),
+            viewer_groups: env_list("OIDC_VIEWER_GROUPS"),
+            default_role: std::env::var("OIDC_DEFAULT_ROLE").ok().and_then(|v| Role::parse(&v)),
+            link_existing_admins: env_flag("OIDC_LINK_EXISTING_ADMINS"),
+        }))
+    }
+
+    /// Whether SSO is configured (and valid)
+    pub fn enabled() -> bool {
+        matches!(OidcConfig::from_env(), Ok(Some(_)))
+    }
+
+    /// Highest role any of the user's groups maps to, or the default role
+    pub fn role_for_groups(&self, groups: &[String]) -> Option<Role> {
+        let mapping = [
+            (Role::SuperAdmin, &self.super_admin_groups),
+            (Role::Editor, &self.editor_groups),
+            (Role::TestOwner, &self.test_owner_groups),
+            (Role::Viewer, &self.viewer_groups),
+        ];
+
+        mapping
+            .into_iter()
+            .find(|(_, mapped)| groups.iter().any(|group| mapped.contains(group)))
+            .map(|(role, _)| role)
+            .or(self.default_role)
+    }
+}
+
+/// Whether password login is turned off in favor of SSO (OIDC_DISABLE_PASSWORD_LOGIN)
+pub fn password_login_disabled() -> bool {
+    env_flag("OIDC_DISABLE_PASSWORD_LOGIN") && OidcConfig::enabled()
+}
+
+/// The parts of the provider's discovery document we use
+#[derive(Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+/// Identity established by a verified ID token
+pub struct OidcIdentity {
+    pub subject: String,
+    pub username: String,
+    pub groups: Vec<String>,
+}
+
+fn provider_error(context: &str, e: impl std::fmt::Display) -> AppError {
+    AppError::InternalServerError(format!("Identity provider {}: {}", context, e))
+}
+
+pub async fn discover(config: &OidcConfig) -> Result<ProviderMetadata, AppError> {
+    let url = format!("{}/.well-known/openid-configuration", config.issuer);
+
+    let metadata: ProviderMetadata = http_client()
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| provider_error("discovery failed", e))?
+        .json()
+        .await
+        .map_err(|e| provider_error("returned an invalid discovery document", e))?;
+
+    if metadata.issuer.trim_end_matches('/') != config.issuer {
+        return Err(provider_error("discovery failed", format!(
+            "issuer mismatch ({} != {})",
+            metadata.issuer, config.issuer
+        )));
+    }
+
+    Ok(metadata)
+}
+
+/// Random URL-safe value for `state`, `nonce` and the PKCE verifier
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64URL_NOPAD.encode(&bytes)
+}
+
+/// S256 PKCE challenge for a verifier
+pub fn pkce_challenge(verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(verifier.as_bytes()))
+}
+
+pub fn authorization_url(
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    state: &str,
+    nonce: &str,
+    code_verifier: &str,
+) -> Result<Url, AppError> {
+    let mut url = Url::parse(&metadata.authorization_endpoint)
+        .map_err(|e| provider_error("has an invalid authorization endpoint", e))?;
+
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &config.client_id)
+        .append_pair("redirect_uri", &config.redirect_uri)
+        .append_pair("scope", &config.scopes)
+        .append_pair("state", state)
+        .append_pair("nonce", nonce)
+        .append_pair("code_challenge", &pkce_challenge(code_verifier))
+        .append_pair("code_challenge_method", "S256");
+
+    Ok(url)
+}
+
+/// Redeem an authorization code and verify the returned ID token
+pub async fn exchange_code(
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    code: &str,
+    code_verifier: &str,
+    nonce: &str,
+) -> Result<OidcIdentity, AppError> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", config.redirect_uri.as_str()),
+        ("code_verifier", code_verifier),
+        ("client_id", config.client_id.as_str()),
+    ];
+    if let Some(secret) = &config.client_secret {
+        form.push(("client_secret", secret.as_str()));
+    }
+
+    let tokens: TokenResponse = http_client()
+        .post(&metadata.token_endpoint)
+        .form(&form)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| AppError::Unauthorized(format!("Identity provider rejected the login: {}", e)))?
+        .json()
+        .await
+        .map_err(|e| provider_error("returned an invalid token response", e))?;
+
+    let id_token = tokens
+        .id_token
+        .ok_or_else(|| provider_error("token response", "no id_token"))?;
+
+    let claims = verify_id_token(config, metadata, &id_token).await?;
+
+    if claims.get("nonce").and_then(|v| v.as_str()) != Some(nonce) {
+        return Err(AppError::Unauthorized("ID token nonce does not match".to_string()));
+    }
+
+    let subject = claims
+        .get("sub")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| AppError::Unauthorized("ID token has no subject".to_string()))?
+        .to_string();
+
+    let username = claim_at(&claims, &config.username_claim)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| AppError::Unauthorized(format!(
+            "ID token has no '{}' claim to use as username",
+            config.username_claim
+        )))?
+        .to_string();
+
+    let groups = match claim_at(&claims, &config.groups_claim) {
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .filter_map(|v| v.as_str().map(str::to_string))
+            .collect(),
+        Some(serde_json::Value::String(group)) => vec![group.clone()],
+        _ => Vec::new(),
+    };
+
+    Ok(OidcIdentity { subject, username, groups })
+}
+
+/// Follow a dotted path such as `realm_access.roles` into the claims
+fn claim_at<'a>(claims: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(claims, |value, key| value.get(key))
+}
+
+async fn verify_id_token(
+    config: &OidcConfig,
+    metadata: &ProviderMetadata,
+    id_token: &str,
+) -> Result<serde_json::Value, AppError> {
+    let header = decode_header(id_token)
+        .map_err(|e| AppError::Unauthorized(format!("Malformed ID token: {}", e)))?;
+
+    // Only provider-signed tokens; HMAC algorithms would let the client secret sign them
+    if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+        return Err(AppError::Unauthorized("ID token must be signed with an asymmetric key".to_string()));
+    }
+
+    let jwks: JwkSet = http_client()
+        .get(&metadata.jwks_uri)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| provider_error("key set could not be fetched", e))?
+        .json()
+        .await
+        .map_err(|e| provider_error("returned an invalid key set", e))?;
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or_else(|| AppError::Unauthorized("ID token signed with an unknown key".to_string()))?;
+
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|e| provider_error("returned an unusable signing key", e))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&config.client_id]);
+    validation.set_issuer(&[&metadata.issuer]);
+
+    let token = decode::<serde_json::Value>(id_token, &key, &validation)
+        .map_err(|e| AppError::Unauthorized(format!("Invalid ID token: {}", e)))?;
+
+    Ok(token.claims)
+}
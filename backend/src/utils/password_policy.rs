@@ -0,0 +1,225 @@
+use crate::{error::AppError, utils::auth::verify_password};
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::SqlitePool;
+
+/// Minimum number of characters in a new password (PASSWORD_MIN_LENGTH)
+pub fn min_length() -> usize {
+    env_i64("PASSWORD_MIN_LENGTH", 8).max(1) as usize
+}
+
+/// How many recent passwords, including the current one, may not be reused (PASSWORD_HISTORY_COUNT, 0 disables)
+fn history_count() -> i64 {
+    env_i64("PASSWORD_HISTORY_COUNT", 5).max(0)
+}
+
+/// Days after which a password must be changed (PASSWORD_MAX_AGE_DAYS, 0 disables)
+fn max_age_days() -> i64 {
+    env_i64("PASSWORD_MAX_AGE_DAYS", 0).max(0)
+}
+
+fn env_i64(name: &str, default: i64) -> i64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(default)
+}
+
+/// Whether a password set at `last_password_change` (or, if it was never changed,
+/// when the account was created) is older than the configured maximum age.
+/// Timestamps are SQLite `datetime('now')` values in UTC. Callers skip this for
+/// SSO-linked admins, whose passwords are managed by the identity provider.
+pub fn is_expired(last_password_change: Option<&str>, created_at: &str) -> bool {
+    let max_age = max_age_days();
+    if max_age == 0 {
+        return false;
+    }
+
+    let set_at = last_password_change.unwrap_or(created_at);
+    match NaiveDateTime::parse_from_str(set_at, "%Y-%m-%d %H:%M:%S") {
+        Ok(set_at) => set_at + Duration::days(max_age) <= Utc::now().naive_utc(),
+        // An unreadable timestamp is treated as expired so the admin gets a fresh one
+        Err(_) => true,
+    }
+}
+
+/// Whether an admin has to change their password before doing anything else:
+/// either the `password_must_change` flag is set or the password has expired.
+pub async fn password_change_required(pool: &SqlitePool, admin_id: i64) -> Result<bool, sqlx::Error> {
+    let row: Option<(bool, Option<String>, String, bool)> = sqlx::query_as(
+        "SELECT password_must_change, last_password_change, created_at, oidc_subject IS NOT NULL
+         FROM admins WHERE id = ?"
+    )
+    .bind(admin_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.is_some_and(|(must_change, last_change, created_at, sso)| {
+        must_change || (!sso && is_expired(last_change.as_deref(), &created_at))
+    }))
+}
+
+/// Check a new password against the policy. When `admin_id` is given (a password
+/// change rather than a new account), the current password and the recent history
+/// are checked for reuse as well.
+pub async fn validate_new_password(
+    pool: &SqlitePool,
+    admin_id: Option<i64>,
+    password: &str,
+) -> Result<(), AppError> {
+    let min_length = min_length();
+    if password.chars().count() < min_length {
+        return Err(AppError::BadRequest(format!(
+            "Password must be at least {} characters long",
+            min_length
+        )));
+    }
+
+    let history = history_count();
+    let Some(admin_id) = admin_id.filter(|_| history > 0) else {
+        return Ok(());
+    };
+
+    // The current hash counts as the newest entry of the history
+    let recent_hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT password_hash FROM admins WHERE id = ?
+         UNION ALL
+         SELECT password_hash FROM (
+             SELECT password_hash FROM admin_password_history
+             WHERE admin_id = ? ORDER BY id DESC LIMIT ?
+         )"
+    )
+    .bind(admin_id)
+    .bind(admin_id)
+    .bind(history - 1)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to load password history: {}", e)))?;
+
+    // Up to `history` bcrypt checks, so keep them off the async workers
+    let candidate = password.to_string();
+    let reused = tokio::task::spawn_blocking(move || {
+        recent_hashes
+            .iter()
+            .any(|hash| verify_password(&candidate, hash).unwrap_or(false))
+    })
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Password history check failed: {}", e)))?;
+
+    if reused {
+        return Err(AppError::BadRequest(format!(
+            "Password must differ from your last {} passwords",
+            history
+        )));
+    }
+
+    Ok(())
+}
+
+/// Remember the hash being replaced and prune history beyond what the policy checks
+pub async fn record_previous_hash(
+    pool: &SqlitePool,
+    admin_id: i64,
+    previous_hash: &str,
+) -> Result<(), sqlx::Error> {
+    let history = history_count();
+    if history <= 1 {
+        // Only the current password is checked, nothing needs to be kept
+        sqlx::query("DELETE FROM admin_password_history WHERE admin_id = ?")
+            .bind(admin_id)
+            .execute(pool)
+            .await?;
+        return Ok(());
+    }
+
+    sqlx::query("INSERT INTO admin_password_history (admin_id, password_hash) VALUES (?, ?)")
+        .bind(admin_id)
+        .bind(previous_hash)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "DELETE FROM admin_password_history
+         WHERE admin_id = ? AND id NOT IN (
+             SELECT id FROM admin_password_history WHERE admin_id = ? ORDER BY id DESC LIMIT ?
+         )"
+    )
+    .bind(admin_id)
+    .bind(admin_id)
+    .bind(history - 1)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    /// A cheap hash; verification doesn't care about the cost
+    fn hash(password: &str) -> String {
+        bcrypt::hash(password, 4).unwrap()
+    }
+
+    fn days_ago(days: i64) -> String {
+        (Utc::now() - Duration::days(days)).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    #[test]
+    fn expiry_follows_the_last_change() {
+        std::env::set_var("PASSWORD_MAX_AGE_DAYS", "30");
+
+        assert!(!is_expired(Some(&days_ago(29)), &days_ago(400)));
+        assert!(is_expired(Some(&days_ago(31)), &days_ago(400)));
+        // Never changed: the account's age counts
+        assert!(!is_expired(None, &days_ago(1)));
+        assert!(is_expired(None, &days_ago(31)));
+        assert!(is_expired(Some("not a date"), &days_ago(1)));
+    }
+
+    /// Change the password of admin 1 the way the handlers do
+    async fn change_password(pool: &SqlitePool, password: &str) -> Result<(), AppError> {
+        validate_new_password(pool, Some(1), password).await?;
+
+        let previous: String = sqlx::query_scalar("SELECT password_hash FROM admins WHERE id = 1")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+        record_previous_hash(pool, 1, &previous).await.unwrap();
+        sqlx::query("UPDATE admins SET password_hash = ? WHERE id = 1")
+            .bind(hash(password))
+            .execute(pool)
+            .await
+            .unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn history_covers_the_current_and_previous_passwords() {
+        let pool = test_pool().await;
+        sqlx::query("INSERT INTO admins (id, username, password_hash) VALUES (1, 'admin', ?)")
+            .bind(hash("password-0"))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        for i in 1..=6 {
+            change_password(&pool, &format!("password-{}", i)).await.unwrap();
+        }
+
+        // The current hash plus history - 1 previous ones make up the history
+        let kept: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM admin_password_history WHERE admin_id = 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(kept, history_count() - 1);
+
+        for reused in ["password-6", "password-2"] {
+            let result = change_password(&pool, reused).await;
+            assert!(matches!(result, Err(AppError::BadRequest(msg)) if msg.contains("last 5")));
+        }
+        // Six changes ago: out of the history again
+        change_password(&pool, "password-1").await.unwrap();
+    }
+}
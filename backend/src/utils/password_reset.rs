@@ -0,0 +1,70 @@
+use crate::error::AppError;
+use sqlx::SqlitePool;
+
+/// Minutes a reset link stays valid (PASSWORD_RESET_TOKEN_TTL_MINUTES)
+pub fn token_ttl_minutes() -> i64 {
+    env_i64("PASSWORD_RESET_TOKEN_TTL_MINUTES", 30).max(1)
+}
+
+/// Reset requests allowed per email address per hour (PASSWORD_RESET_MAX_PER_EMAIL)
+fn max_requests_per_email() -> i64 {
+    env_i64("PASSWORD_RESET_MAX_PER_EMAIL", 3)
+}
+
+/// Reset requests allowed per client IP per hour (PASSWORD_RESET_MAX_PER_IP)
+fn max_requests_per_ip() -> i64 {
+    env_i64("PASSWORD_RESET_MAX_PER_IP", 10)
+}
+
+fn env_i64(name: &str, default: i64) -> i64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(default)
+}
+
+/// Lowercase and check an email address; `None` if it isn't a valid address
+pub fn normalize_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    email.parse::<lettre::Address>().ok().map(|_| email)
+}
+
+/// Count this request against the per-email and per-IP limits and reject it if
+/// either is exceeded. Limits apply to the address as typed, so the outcome
+/// doesn't depend on whether an admin has that address.
+pub async fn check_and_record_request(
+    pool: &SqlitePool,
+    email: &str,
+    ip_address: Option<&str>,
+) -> Result<(), AppError> {
+    // Requests are only needed for the last hour's counts
+    sqlx::query("DELETE FROM password_reset_requests WHERE created_at <= datetime('now', '-1 hour')")
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (by_email, by_ip): (i64, i64) = sqlx::query_as(
+        "SELECT COALESCE(SUM(email = ?), 0), COALESCE(SUM(ip_address IS NOT NULL AND ip_address = ?), 0)
+         FROM password_reset_requests"
+    )
+    .bind(email)
+    .bind(ip_address)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    if by_email >= max_requests_per_email() || by_ip >= max_requests_per_ip() {
+        return Err(AppError::TooManyRequests(
+            "Too many password reset requests. Try again later".to_string(),
+        ));
+    }
+
+    sqlx::query("INSERT INTO password_reset_requests (email, ip_address) VALUES (?, ?)")
+        .bind(email)
+        .bind(ip_address)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    Ok(())
+}
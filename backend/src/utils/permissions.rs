@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+/// Named admin role stored in `admins.role`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read-only analyst: can browse the library and view results
+    Viewer,
+    /// Can create tests and manage the tests they created
+    TestOwner,
+    /// Manages categories, media and every test
+    Editor,
+    /// Everything, including admin accounts
+    SuperAdmin,
+}
+
+/// Capability checked by `middleware::auth::require_permission` on each route.
+/// Also used as an API key scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// List categories, media and tests
+    ViewLibrary,
+    /// View test results
+    ViewResults,
+    /// Create tests and manage tests the admin created
+    ManageOwnTests,
+    /// Manage tests regardless of who created them
+    ManageAllTests,
+    /// Create and delete categories
+    ManageCategories,
+    /// Upload, re-categorize and delete media
+    ManageMedia,
+    /// Read the activity log
+    ViewActivityLogs,
+    /// Create, delete and change roles of admin accounts
+    ManageAdmins,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 8] = [
+        Permission::ViewLibrary,
+        Permission::ViewResults,
+        Permission::ManageOwnTests,
+        Permission::ManageAllTests,
+        Permission::ManageCategories,
+        Permission::ManageMedia,
+        Permission::ViewActivityLogs,
+        Permission::ManageAdmins,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ViewLibrary => "view_library",
+            Permission::ViewResults => "view_results",
+            Permission::ManageOwnTests => "manage_own_tests",
+            Permission::ManageAllTests => "manage_all_tests",
+            Permission::ManageCategories => "manage_categories",
+            Permission::ManageMedia => "manage_media",
+            Permission::ViewActivityLogs => "view_activity_logs",
+            Permission::ManageAdmins => "manage_admins",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Permission> {
+        Permission::ALL.into_iter().find(|permission| permission.as_str() == value)
+    }
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Viewer, Role::TestOwner, Role::Editor, Role::SuperAdmin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::TestOwner => "test_owner",
+            Role::Editor => "editor",
+            Role::SuperAdmin => "super_admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.as_str() == value)
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+
+        match self {
+            Role::Viewer => &[ViewLibrary, ViewResults],
+            Role::TestOwner => &[ViewLibrary, ViewResults, ManageOwnTests],
+            Role::Editor => &[
+                ViewLibrary,
+                ViewResults,
+                ManageOwnTests,
+                ManageAllTests,
+                ManageCategories,
+                ManageMedia,
+                ViewActivityLogs,
+            ],
+            Role::SuperAdmin => &[
+                ViewLibrary,
+                ViewResults,
+                ManageOwnTests,
+                ManageAllTests,
+                ManageCategories,
+                ManageMedia,
+                ViewActivityLogs,
+                ManageAdmins,
+            ],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{storage, utils::uploads::TempUpload};
+use image::{imageops::FilterType, ImageFormat, ImageReader};
+use serde::Serialize;
+use std::{fs::File, path::Path};
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError, formats::FormatOptions,
+    io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// Longest side of an image thumbnail in pixels
+const THUMBNAIL_SIZE: u32 = 320;
+
+/// Number of peaks in a waveform preview
+const WAVEFORM_PEAKS: usize = 800;
+
+/// Audio is first reduced to one peak per this many seconds
+const PEAK_WINDOW_SECONDS: f64 = 0.01;
+
+/// Precomputed waveform of an audio file, stored as JSON next to it
+#[derive(Debug, Serialize)]
+pub struct Waveform {
+    pub duration_seconds: f64,
+    /// Largest absolute sample value over all channels in each slice of the
+    /// file, from 0.0 to 1.0
+    pub peaks: Vec<f32>,
+}
+
+/// Storage key of an image's thumbnail: `<name>.thumb.png` next to the original
+pub fn thumbnail_key(storage_key: &str) -> String {
+    sibling_key(storage_key, "thumb.png")
+}
+
+/// Storage key of an audio file's waveform: `<name>.waveform.json` next to the original
+pub fn waveform_key(storage_key: &str) -> String {
+    sibling_key(storage_key, "waveform.json")
+}
+
+fn sibling_key(storage_key: &str, extension: &str) -> String {
+    let stem = storage_key.rsplit_once('.').map_or(storage_key, |(stem, _)| stem);
+    format!("{}.{}", stem, extension)
+}
+
+/// Key and content type of the preview for a media type, if it has one
+pub fn preview(storage_key: &str, media_type: &str) -> Option<(String, &'static str)> {
+    match media_type {
+        "image" => Some((thumbnail_key(storage_key), "image/png")),
+        "audio" => Some((waveform_key(storage_key), "application/json")),
+        _ => None,
+    }
+}
+
+/// Generate the preview of a media file from a local copy at `source` and
+/// put it into storage: a thumbnail for images, a waveform for audio. Other
+/// media types have none. Rendering runs on the blocking pool.
+pub async fn generate(source: &Path, storage_key: &str, media_type: &str) -> Result<(), String> {
+    let Some((key, content_type)) = preview(storage_key, media_type) else {
+        return Ok(());
+    };
+
+    let output = TempUpload::create().await.map_err(|e| e.to_string())?;
+    let source = source.to_path_buf();
+    let destination = output.path().to_path_buf();
+    let media_type = media_type.to_string();
+    // The local copy is a temp file; the key's extension tells the decoder the format
+    let extension = Path::new(storage_key).extension().and_then(|e| e.to_str()).map(str::to_string);
+
+    tokio::task::spawn_blocking(move || match media_type.as_str() {
+        "image" => write_thumbnail(&source, &destination),
+        _ => write_waveform(&source, &destination, extension.as_deref()),
+    })
+    .await
+    .map_err(|e| format!("Preview task failed: {}", e))??;
+
+    output.store(&key, content_type).await.map_err(|e| e.to_string())
+}
+
+/// Remove the previews of a deleted file
+pub async fn remove(storage_key: &str) {
+    let storage = storage::get();
+    for key in [thumbnail_key(storage_key), waveform_key(storage_key)] {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to delete preview {}: {}", key, e);
+        }
+    }
+}
+
+/// PNG scaled down to fit `THUMBNAIL_SIZE`; smaller images keep their size.
+/// Animated images use the first frame.
+fn write_thumbnail(source: &Path, destination: &Path) -> Result<(), String> {
+    let image = ImageReader::open(source)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+
+    let thumbnail = if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image.resize(THUMBNAIL_SIZE, THUMBNAIL_SIZE, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    thumbnail
+        .save_with_format(destination, ImageFormat::Png)
+        .map_err(|e| e.to_string())
+}
+
+fn write_waveform(source: &Path, destination: &Path, extension: Option<&str>) -> Result<(), String> {
+    let waveform = waveform(source, extension)?;
+    let json = serde_json::to_vec(&waveform).map_err(|e| e.to_string())?;
+    std::fs::write(destination, json).map_err(|e| e.to_string())
+}
+
+/// Decode the first audio track and reduce it to `WAVEFORM_PEAKS` peaks.
+/// Packets that fail to decode are skipped.
+fn waveform(path: &Path, extension: Option<&str>) -> Result<Waveform, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+
+    let mut format = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| e.to_string())?
+        .format;
+    let track = format.default_track().ok_or_else(|| "no audio track".to_string())?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.ok_or_else(|| "unknown sample rate".to_string())?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| e.to_string())?;
+
+    let window = ((sample_rate as f64 * PEAK_WINDOW_SECONDS) as usize).max(1);
+    let mut windows: Vec<f32> = Vec::new();
+    let mut peak = 0.0f32;
+    let mut frames_in_window = 0;
+    let mut total_frames: u64 = 0;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        let spec = *decoded.spec();
+        let channels = spec.channels.count().max(1);
+        let needed = decoded.capacity() * channels;
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= needed => buffer,
+            slot => slot.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+
+        for frame in buffer.samples().chunks(channels) {
+            peak = frame.iter().fold(peak, |max, sample| max.max(sample.abs()));
+            frames_in_window += 1;
+            total_frames += 1;
+            if frames_in_window == window {
+                windows.push(peak);
+                peak = 0.0;
+                frames_in_window = 0;
+            }
+        }
+    }
+    if frames_in_window > 0 {
+        windows.push(peak);
+    }
+
+    let per_peak = windows.len().div_ceil(WAVEFORM_PEAKS).max(1);
+    let peaks = windows
+        .chunks(per_peak)
+        .map(|chunk| {
+            let max = chunk.iter().fold(0.0f32, |max, value| max.max(*value)).min(1.0);
+            (max * 1000.0).round() / 1000.0
+        })
+        .collect();
+
+    Ok(Waveform {
+        duration_seconds: total_frames as f64 / sample_rate as f64,
+        peaks,
+    })
+}
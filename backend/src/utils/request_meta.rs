@@ -0,0 +1,64 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::net::SocketAddr;
+
+/// Whether `X-Real-IP` / `X-Forwarded-For` are set by a reverse proxy in front of
+/// the backend and can be believed (TRUST_PROXY_HEADERS, default false)
+pub fn trust_proxy_headers() -> bool {
+    std::env::var("TRUST_PROXY_HEADERS")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Middleware run on every request: unless proxy headers are trusted, replace the
+/// client's own `X-Real-IP` / `X-Forwarded-For` with the connection's peer address,
+/// so anything reading them (login throttling, activity logs) can't be lied to.
+pub async fn resolve_client_ip(
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !trust_proxy_headers() {
+        let headers = request.headers_mut();
+        headers.remove("X-Forwarded-For");
+        match HeaderValue::from_str(&peer.ip().to_string()) {
+            Ok(ip) => {
+                headers.insert("X-Real-IP", ip);
+            }
+            Err(_) => {
+                headers.remove("X-Real-IP");
+            }
+        }
+    }
+
+    next.run(request).await
+}
+
+/// Best-effort client IP address.
+///
+/// Behind nginx (TRUST_PROXY_HEADERS=true) this is the `X-Real-IP` it sets, or the
+/// first hop of `X-Forwarded-For` when only that is present; otherwise
+/// `resolve_client_ip` has put the peer address in `X-Real-IP`.
+pub fn client_ip(headers: &HeaderMap) -> Option<String> {
+    if let Some(ip) = headers.get("X-Real-IP").and_then(|h| h.to_str().ok()) {
+        return Some(ip.trim().to_string());
+    }
+
+    headers
+        .get("X-Forwarded-For")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|v| v.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+}
+
+pub fn user_agent(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|ua| ua.to_string())
+}
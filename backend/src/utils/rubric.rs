@@ -0,0 +1,121 @@
+use crate::{
+    error::AppError,
+    models::{Category, Rubric, StarLabels},
+    utils::category_tree::TEST_CATEGORY_IDS,
+};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+const MAX_INSTRUCTIONS_LENGTH: usize = 2000;
+const MAX_LABEL_LENGTH: usize = 100;
+
+/// Trim instructions text; blank text means no instructions
+pub fn normalize_instructions(instructions: Option<String>) -> Result<Option<String>, AppError> {
+    let Some(instructions) = instructions.map(|text| text.trim().to_string()).filter(|text| !text.is_empty()) else {
+        return Ok(None);
+    };
+
+    if instructions.chars().count() > MAX_INSTRUCTIONS_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Instructions must be at most {} characters",
+            MAX_INSTRUCTIONS_LENGTH
+        )));
+    }
+
+    Ok(Some(instructions))
+}
+
+/// Check star anchor labels: whole stars from 1 to 5, blank labels dropped.
+/// No labels left means none are stored.
+pub fn normalize_star_labels(labels: Option<StarLabels>) -> Result<Option<StarLabels>, AppError> {
+    let mut normalized = StarLabels::new();
+
+    for (stars, label) in labels.unwrap_or_default() {
+        if !(1..=5).contains(&stars) {
+            return Err(AppError::BadRequest(format!(
+                "Star labels are for 1 to 5 stars, got {}",
+                stars
+            )));
+        }
+
+        let label = label.trim();
+        if label.chars().count() > MAX_LABEL_LENGTH {
+            return Err(AppError::BadRequest(format!(
+                "Star labels must be at most {} characters",
+                MAX_LABEL_LENGTH
+            )));
+        }
+        if !label.is_empty() {
+            normalized.insert(stars, label.to_string());
+        }
+    }
+
+    Ok(Some(normalized).filter(|labels| !labels.is_empty()))
+}
+
+fn rubric_of(category: &Category) -> Option<Rubric> {
+    let star_labels = category.star_labels.as_ref().map(|labels| labels.0.clone()).unwrap_or_default();
+    if category.instructions.is_none() && star_labels.is_empty() {
+        return None;
+    }
+
+    Some(Rubric {
+        category_id: category.id,
+        category_name: category.name.clone(),
+        instructions: category.instructions.clone(),
+        star_labels,
+    })
+}
+
+/// Rubric for each media file of a test, keyed by media id. A media file uses
+/// the rubric of the test category it belongs to; a category without one
+/// inherits it from the nearest ancestor in the test that has one. When a file
+/// is in several of the test's categories, the lowest category id wins.
+pub async fn rubrics_for_test(pool: &SqlitePool, test_id: i64) -> Result<HashMap<i64, Rubric>, AppError> {
+    let categories: HashMap<i64, Category> = sqlx::query_as::<_, Category>(&format!(
+        "SELECT * FROM categories WHERE id IN ({})",
+        TEST_CATEGORY_IDS
+    ))
+    .bind(test_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?
+    .into_iter()
+    .map(|category| (category.id, category))
+    .collect();
+
+    let links: Vec<(i64, i64)> = sqlx::query_as(&format!(
+        "SELECT media_file_id, category_id FROM media_file_categories
+         WHERE category_id IN ({})
+         ORDER BY category_id",
+        TEST_CATEGORY_IDS
+    ))
+    .bind(test_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let mut rubrics = HashMap::new();
+    for (media_file_id, category_id) in links {
+        if rubrics.contains_key(&media_file_id) {
+            continue;
+        }
+
+        // Walk up the tree, staying inside the test's categories; the step limit guards against cycles
+        let mut current = categories.get(&category_id);
+        let mut steps = 0;
+        while let Some(category) = current {
+            if let Some(rubric) = rubric_of(category) {
+                rubrics.insert(media_file_id, rubric);
+                break;
+            }
+            steps += 1;
+            if steps > categories.len() {
+                break;
+            }
+            current = category.parent_id.and_then(|parent_id| categories.get(&parent_id));
+        }
+    }
+
+    Ok(rubrics)
+}
@@ -0,0 +1,345 @@
+use crate::{
+    error::AppError,
+    utils::{
+        auth::{create_jwt, generate_secret_token, hash_token},
+        password_policy,
+        permissions::Role,
+    },
+};
+use sqlx::SqlitePool;
+
+/// Tokens handed to the client after a successful login or refresh
+pub struct IssuedTokens {
+    pub session_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+}
+
+/// Admin state read alongside a session when rotating its refresh token
+pub struct SessionAdmin {
+    pub id: i64,
+    pub username: String,
+    pub role: Role,
+    pub password_must_change: bool,
+}
+
+/// Lifetime of a refresh token in hours (REFRESH_TOKEN_TTL_HOURS, default 7 days).
+/// Every successful refresh slides the expiry forward by this amount.
+pub fn refresh_token_ttl_hours() -> i64 {
+    std::env::var("REFRESH_TOKEN_TTL_HOURS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(168)
+}
+
+fn issue_access_token(
+    username: &str,
+    role: Role,
+    session_id: &str,
+    password_must_change: bool,
+) -> Result<String, AppError> {
+    create_jwt(username, role, session_id, password_must_change)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to create token: {}", e)))
+}
+
+/// Create a new server-side session for an admin and issue its first token pair.
+///
+/// Refresh tokens have the form `<session_id>.<secret>`; only the SHA-256 of the
+/// full token is stored.
+pub async fn create_session(
+    pool: &SqlitePool,
+    admin_id: i64,
+    username: &str,
+    role: Role,
+    password_must_change: bool,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<IssuedTokens, AppError> {
+    let session_id = uuid::Uuid::new_v4().to_string();
+    let refresh_token = format!("{}.{}", session_id, generate_secret_token());
+
+    sqlx::query(
+        "INSERT INTO admin_sessions (id, admin_id, refresh_token_hash, ip_address, user_agent, expires_at)
+         VALUES (?, ?, ?, ?, ?, datetime('now', ?))"
+    )
+    .bind(&session_id)
+    .bind(admin_id)
+    .bind(hash_token(&refresh_token))
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(format!("+{} hours", refresh_token_ttl_hours()))
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to create session: {}", e)))?;
+
+    let access_token = issue_access_token(username, role, &session_id, password_must_change)?;
+
+    Ok(IssuedTokens {
+        session_id,
+        access_token,
+        refresh_token,
+    })
+}
+
+/// Exchange a refresh token for a new token pair, rotating the refresh token.
+///
+/// Presenting a refresh token that has already been rotated away is treated as
+/// token theft: the whole session is revoked.
+pub async fn refresh_session(
+    pool: &SqlitePool,
+    refresh_token: &str,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<(IssuedTokens, SessionAdmin), AppError> {
+    let session_id = refresh_token
+        .split_once('.')
+        .map(|(id, _)| id)
+        .ok_or_else(|| AppError::Unauthorized("Malformed refresh token".to_string()))?;
+
+    #[allow(clippy::type_complexity)]
+    let session: Option<(String, bool, bool, i64, String, String, bool, Option<String>, String, bool, bool)> = sqlx::query_as(
+        "SELECT s.refresh_token_hash, s.revoked_at IS NOT NULL, s.expires_at <= datetime('now'),
+                a.id, a.username, a.role, a.password_must_change, a.last_password_change, a.created_at,
+                a.suspended_at IS NOT NULL, a.oidc_subject IS NOT NULL
+         FROM admin_sessions s
+         INNER JOIN admins a ON a.id = s.admin_id
+         WHERE s.id = ?"
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+
+    let (stored_hash, revoked, expired, admin_id, username, role, must_change, last_change, created_at, suspended, sso) =
+        session.ok_or_else(|| AppError::Unauthorized("Session not found".to_string()))?;
+    let password_must_change =
+        must_change || (!sso && password_policy::is_expired(last_change.as_deref(), &created_at));
+    let role = Role::parse(&role)
+        .ok_or_else(|| AppError::InternalServerError(format!("Unknown role '{}'", role)))?;
+
+    if revoked || expired {
+        return Err(AppError::Unauthorized("Session has expired or been revoked".to_string()));
+    }
+
+    if suspended {
+        return Err(AppError::Unauthorized("Account is suspended".to_string()));
+    }
+
+    let presented_hash = hash_token(refresh_token);
+    if presented_hash != stored_hash {
+        tracing::warn!("Refresh token reuse detected for session {}; revoking", session_id);
+        revoke_session(pool, session_id)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))?;
+        return Err(AppError::Unauthorized("Refresh token has already been used".to_string()));
+    }
+
+    let new_refresh_token = format!("{}.{}", session_id, generate_secret_token());
+
+    // Compare-and-swap on the old hash so two concurrent refreshes cannot both succeed
+    let result = sqlx::query(
+        "UPDATE admin_sessions
+         SET refresh_token_hash = ?, last_used_at = datetime('now'), expires_at = datetime('now', ?),
+             ip_address = COALESCE(?, ip_address), user_agent = COALESCE(?, user_agent)
+         WHERE id = ? AND refresh_token_hash = ? AND revoked_at IS NULL"
+    )
+    .bind(hash_token(&new_refresh_token))
+    .bind(format!("+{} hours", refresh_token_ttl_hours()))
+    .bind(ip_address)
+    .bind(user_agent)
+    .bind(session_id)
+    .bind(&presented_hash)
+    .execute(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Failed to rotate refresh token: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::Unauthorized("Refresh token has already been used".to_string()));
+    }
+
+    let access_token = issue_access_token(&username, role, session_id, password_must_change)?;
+
+    Ok((
+        IssuedTokens {
+            session_id: session_id.to_string(),
+            access_token,
+            refresh_token: new_refresh_token,
+        },
+        SessionAdmin {
+            id: admin_id,
+            username,
+            role,
+            password_must_change,
+        },
+    ))
+}
+
+/// Live admin state behind an access token
+pub struct ActiveSession {
+    pub role: String,
+    pub password_change_required: bool,
+    pub suspended: bool,
+}
+
+/// Look up the admin owning a live session.
+///
+/// Returns `None` when the session was revoked, has expired, or no longer belongs
+/// to an existing admin with the given username. Reading the role and password
+/// state here means changes apply on the next request rather than when the access
+/// token expires.
+pub async fn active_session(
+    pool: &SqlitePool,
+    session_id: &str,
+    username: &str,
+) -> Result<Option<ActiveSession>, sqlx::Error> {
+    let row: Option<(String, bool, Option<String>, String, bool, bool)> = sqlx::query_as(
+        "SELECT a.role, a.password_must_change, a.last_password_change, a.created_at, a.suspended_at IS NOT NULL,
+                a.oidc_subject IS NOT NULL
+         FROM admin_sessions s
+         INNER JOIN admins a ON a.id = s.admin_id
+         WHERE s.id = ? AND a.username = ? AND s.revoked_at IS NULL AND s.expires_at > datetime('now')"
+    )
+    .bind(session_id)
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|(role, must_change, last_change, created_at, suspended, sso)| ActiveSession {
+        role,
+        password_change_required: must_change
+            || (!sso && password_policy::is_expired(last_change.as_deref(), &created_at)),
+        suspended,
+    }))
+}
+
+/// Record activity on a session for the session lists. Writes at most once a
+/// minute per session so busy clients don't cause a write on every request.
+pub async fn touch_session(pool: &SqlitePool, session_id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE admin_sessions SET last_used_at = datetime('now')
+         WHERE id = ? AND last_used_at <= datetime('now', '-1 minute')"
+    )
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn revoke_session(pool: &SqlitePool, session_id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE admin_sessions SET revoked_at = datetime('now') WHERE id = ? AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn revoke_admin_sessions(pool: &SqlitePool, admin_id: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE admin_sessions SET revoked_at = datetime('now') WHERE admin_id = ? AND revoked_at IS NULL"
+    )
+    .bind(admin_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, handlers::auth::logout, models::Claims, utils::jwt_keys};
+    use axum::{extract::State, http::HeaderMap, Extension};
+
+    async fn setup() -> (SqlitePool, IssuedTokens) {
+        jwt_keys::init_for_tests();
+        let pool = test_pool().await;
+
+        let admin_id: i64 = sqlx::query_scalar(
+            "INSERT INTO admins (username, password_hash, role) VALUES ('alice', 'hash', 'editor') RETURNING id"
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let tokens = create_session(&pool, admin_id, "alice", Role::Editor, false, None, None).await.unwrap();
+
+        (pool, tokens)
+    }
+
+    async fn is_revoked(pool: &SqlitePool, session_id: &str) -> bool {
+        sqlx::query_scalar("SELECT revoked_at IS NOT NULL FROM admin_sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn refresh_rotates_the_token() {
+        let (pool, tokens) = setup().await;
+
+        let (rotated, admin) = refresh_session(&pool, &tokens.refresh_token, None, None).await.unwrap();
+        assert_eq!(rotated.session_id, tokens.session_id);
+        assert_ne!(rotated.refresh_token, tokens.refresh_token);
+        assert_eq!((admin.username.as_str(), admin.role), ("alice", Role::Editor));
+
+        // The new token keeps working
+        let (again, _) = refresh_session(&pool, &rotated.refresh_token, None, None).await.unwrap();
+        assert_ne!(again.refresh_token, rotated.refresh_token);
+        assert!(!is_revoked(&pool, &tokens.session_id).await);
+    }
+
+    #[tokio::test]
+    async fn replaying_a_rotated_token_revokes_the_session() {
+        let (pool, tokens) = setup().await;
+        let (rotated, _) = refresh_session(&pool, &tokens.refresh_token, None, None).await.unwrap();
+
+        let replay = refresh_session(&pool, &tokens.refresh_token, None, None).await;
+        assert!(matches!(replay, Err(AppError::Unauthorized(_))));
+        assert!(is_revoked(&pool, &tokens.session_id).await);
+
+        // Whoever holds the current token is logged out as well
+        let current = refresh_session(&pool, &rotated.refresh_token, None, None).await;
+        assert!(matches!(current, Err(AppError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn expired_session_is_rejected() {
+        let (pool, tokens) = setup().await;
+        sqlx::query("UPDATE admin_sessions SET expires_at = datetime('now', '-1 minute') WHERE id = ?")
+            .bind(&tokens.session_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let result = refresh_session(&pool, &tokens.refresh_token, None, None).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+        assert!(active_session(&pool, &tokens.session_id, "alice").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn logout_revokes_the_session() {
+        let (pool, tokens) = setup().await;
+        assert!(active_session(&pool, &tokens.session_id, "alice").await.unwrap().is_some());
+
+        let claims = Claims {
+            sub: "alice".to_string(),
+            exp: usize::MAX,
+            is_super_admin: false,
+            role: Role::Editor,
+            sid: tokens.session_id.clone(),
+            password_must_change: false,
+            api_key_id: None,
+            scopes: None,
+        };
+        logout(State(pool.clone()), Extension(claims), HeaderMap::new()).await.unwrap();
+
+        assert!(is_revoked(&pool, &tokens.session_id).await);
+        assert!(active_session(&pool, &tokens.session_id, "alice").await.unwrap().is_none());
+        let result = refresh_session(&pool, &tokens.refresh_token, None, None).await;
+        assert!(matches!(result, Err(AppError::Unauthorized(_))));
+    }
+}
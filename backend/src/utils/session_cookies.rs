@@ -0,0 +1,172 @@
+use crate::{
+    error::AppError,
+    models::LoginResponse,
+    utils::{
+        auth::{access_token_ttl_secs, generate_secret_token, hash_token},
+        session::refresh_token_ttl_hours,
+    },
+};
+use axum::http::{header, HeaderMap, HeaderValue, Method};
+
+/// HttpOnly cookie carrying the access token
+pub const SESSION_COOKIE: &str = "mr_session";
+/// HttpOnly cookie carrying the refresh token
+pub const REFRESH_COOKIE: &str = "mr_refresh";
+/// Readable cookie the SPA echoes back in `CSRF_HEADER` (double-submit)
+pub const CSRF_COOKIE: &str = "mr_csrf";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+/// HttpOnly cookie tying an SSO login to the browser that started it
+pub const OIDC_STATE_COOKIE: &str = "mr_oidc_state";
+/// The state cookie is only ever needed by the callback
+const OIDC_STATE_PATH: &str = "/api/admin/oidc/callback";
+
+/// Whether admin logins use cookies instead of returning tokens (AUTH_MODE=cookie)
+pub fn enabled() -> bool {
+    std::env::var("AUTH_MODE").is_ok_and(|mode| mode.eq_ignore_ascii_case("cookie"))
+}
+
+/// `Secure` attribute (AUTH_COOKIE_SECURE, default true; disable only for plain-HTTP development)
+fn secure() -> bool {
+    std::env::var("AUTH_COOKIE_SECURE").map_or(true, |v| !matches!(v.to_lowercase().as_str(), "0" | "false" | "no"))
+}
+
+/// `SameSite` attribute (AUTH_COOKIE_SAMESITE: Strict, Lax or None; default Strict)
+fn same_site() -> &'static str {
+    match std::env::var("AUTH_COOKIE_SAMESITE").unwrap_or_default().to_lowercase().as_str() {
+        "lax" => "Lax",
+        "none" => "None",
+        _ => "Strict",
+    }
+}
+
+fn build_cookie(name: &str, value: &str, max_age_secs: i64, http_only: bool) -> String {
+    let mut cookie = format!("{}={}; Path=/; Max-Age={}; SameSite={}", name, value, max_age_secs, same_site());
+    // Needed when the SPA and the API are on different subdomains (AUTH_COOKIE_DOMAIN)
+    if let Ok(domain) = std::env::var("AUTH_COOKIE_DOMAIN") {
+        if !domain.is_empty() {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+    }
+    if http_only {
+        cookie.push_str("; HttpOnly");
+    }
+    // Browsers reject SameSite=None without Secure
+    if secure() || same_site() == "None" {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
+
+fn set_cookie(headers: &mut HeaderMap, cookie: String) {
+    if let Ok(value) = HeaderValue::from_str(&cookie) {
+        headers.append(header::SET_COOKIE, value);
+    }
+}
+
+/// In cookie mode, move the tokens of a login or refresh response into cookies
+/// and hand the SPA a fresh CSRF token instead. Returns the `Set-Cookie` headers
+/// (none in token mode).
+pub fn issue(response: &mut LoginResponse) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if !enabled() {
+        return headers;
+    }
+
+    let (Some(access_token), Some(refresh_token)) = (response.token.take(), response.refresh_token.take()) else {
+        return headers;
+    };
+    let refresh_ttl_secs = refresh_token_ttl_hours() * 3600;
+    let csrf_token = generate_secret_token();
+
+    set_cookie(&mut headers, build_cookie(SESSION_COOKIE, &access_token, access_token_ttl_secs() as i64, true));
+    set_cookie(&mut headers, build_cookie(REFRESH_COOKIE, &refresh_token, refresh_ttl_secs, true));
+    set_cookie(&mut headers, build_cookie(CSRF_COOKIE, &csrf_token, refresh_ttl_secs, false));
+
+    response.csrf_token = Some(csrf_token);
+    headers
+}
+
+/// `Set-Cookie` headers that remove all session cookies (logout)
+pub fn clear() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if enabled() {
+        for (name, http_only) in [(SESSION_COOKIE, true), (REFRESH_COOKIE, true), (CSRF_COOKIE, false)] {
+            set_cookie(&mut headers, build_cookie(name, "", 0, http_only));
+        }
+    }
+    headers
+}
+
+/// Value of a cookie sent by the browser; always `None` unless cookie mode is on
+pub fn get<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    if !enabled() {
+        return None;
+    }
+
+    find(headers, name)
+}
+
+fn find<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+        .filter(|value| !value.is_empty())
+}
+
+/// `Set-Cookie` header holding a hash of the OIDC `state` of a login that is
+/// being started. Set in both auth modes; SameSite=Lax so it survives the
+/// top-level redirect back from the provider.
+pub fn set_oidc_state(state: &str, max_age_secs: i64) -> HeaderMap {
+    oidc_state_cookie(&hash_token(state), max_age_secs)
+}
+
+/// `Set-Cookie` header removing the OIDC state cookie once the callback ran
+pub fn clear_oidc_state() -> HeaderMap {
+    oidc_state_cookie("", 0)
+}
+
+fn oidc_state_cookie(value: &str, max_age_secs: i64) -> HeaderMap {
+    let mut cookie = format!(
+        "{}={}; Path={}; Max-Age={}; SameSite=Lax; HttpOnly",
+        OIDC_STATE_COOKIE, value, OIDC_STATE_PATH, max_age_secs
+    );
+    if secure() {
+        cookie.push_str("; Secure");
+    }
+
+    let mut headers = HeaderMap::new();
+    set_cookie(&mut headers, cookie);
+    headers
+}
+
+/// Whether the browser calling the OIDC callback is the one that started the
+/// login for this `state` (login CSRF protection)
+pub fn verify_oidc_state(headers: &HeaderMap, state: &str) -> bool {
+    find(headers, OIDC_STATE_COOKIE)
+        .is_some_and(|cookie| constant_time_eq(cookie.as_bytes(), hash_token(state).as_bytes()))
+}
+
+/// Double-submit check for requests authenticated by cookie: the CSRF header
+/// must match the CSRF cookie. Safe methods are exempt.
+pub fn verify_csrf(method: &Method, headers: &HeaderMap) -> Result<(), AppError> {
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(());
+    }
+
+    let cookie = get(headers, CSRF_COOKIE);
+    let header = headers.get(CSRF_HEADER).and_then(|h| h.to_str().ok());
+
+    match (cookie, header) {
+        (Some(cookie), Some(header)) if constant_time_eq(cookie.as_bytes(), header.as_bytes()) => Ok(()),
+        _ => Err(AppError::Forbidden("Missing or invalid CSRF token".to_string())),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,92 @@
+use crate::error::AppError;
+use sqlx::SqlitePool;
+
+/// A test that would lose media or collected ratings if a category or media
+/// file were deleted
+#[derive(Debug, sqlx::FromRow)]
+pub struct ReferencingTest {
+    pub id: i64,
+    pub name: String,
+    pub status: String,
+    pub ratings: i64,
+}
+
+/// Tests affected by deleting a category. Deleting it unlinks its media and
+/// detaches its subcategories, so every test on the category or one of its
+/// ancestors loses the media of the whole subtree. A test counts when it is
+/// still open or has ratings for that media.
+pub async fn tests_using_category(pool: &SqlitePool, category_id: i64) -> Result<Vec<ReferencingTest>, AppError> {
+    sqlx::query_as::<_, ReferencingTest>(
+        "WITH RECURSIVE
+             ancestors(id) AS (
+                 SELECT ?
+                 UNION
+                 SELECT c.parent_id FROM categories c INNER JOIN ancestors a ON c.id = a.id WHERE c.parent_id IS NOT NULL
+             ),
+             subtree(id) AS (
+                 SELECT ?
+                 UNION
+                 SELECT c.id FROM categories c INNER JOIN subtree s ON c.parent_id = s.id
+             ),
+             affected_media(id) AS (
+                 SELECT media_file_id FROM media_file_categories WHERE category_id IN (SELECT id FROM subtree)
+             )
+         SELECT t.id, t.name, t.status,
+                (SELECT COUNT(*) FROM ratings r
+                 INNER JOIN test_users tu ON tu.id = r.test_user_id
+                 WHERE tu.test_id = t.id AND r.media_file_id IN (SELECT id FROM affected_media)) AS ratings
+         FROM tests t
+         WHERE t.id IN (SELECT test_id FROM test_categories WHERE category_id IN (SELECT id FROM ancestors))
+           AND (ratings > 0
+                OR (t.status = 'open'
+                    AND (EXISTS (SELECT 1 FROM affected_media)
+                         OR t.id IN (SELECT test_id FROM test_categories WHERE category_id = ?))))
+         ORDER BY t.created_at"
+    )
+    .bind(category_id)
+    .bind(category_id)
+    .bind(category_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))
+}
+
+/// Tests affected by deleting a media file: open tests that include it through
+/// their categories, and any test with ratings for it
+pub async fn tests_using_media(pool: &SqlitePool, media_file_id: i64) -> Result<Vec<ReferencingTest>, AppError> {
+    sqlx::query_as::<_, ReferencingTest>(
+        "WITH RECURSIVE media_categories(id) AS (
+             SELECT category_id FROM media_file_categories WHERE media_file_id = ?
+             UNION
+             SELECT c.parent_id FROM categories c INNER JOIN media_categories m ON c.id = m.id WHERE c.parent_id IS NOT NULL
+         )
+         SELECT t.id, t.name, t.status,
+                (SELECT COUNT(*) FROM ratings r
+                 INNER JOIN test_users tu ON tu.id = r.test_user_id
+                 WHERE tu.test_id = t.id AND r.media_file_id = ?) AS ratings
+         FROM tests t
+         WHERE ratings > 0
+            OR (t.status = 'open'
+                AND t.id IN (SELECT test_id FROM test_categories WHERE category_id IN (SELECT id FROM media_categories)))
+         ORDER BY t.created_at"
+    )
+    .bind(media_file_id)
+    .bind(media_file_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| AppError::InternalServerError(format!("Database error: {}", e)))
+}
+
+/// The refusal returned when a delete without `force` would affect tests
+pub fn conflict(item: &str, tests: &[ReferencingTest]) -> AppError {
+    let listed: Vec<String> = tests
+        .iter()
+        .map(|t| format!("'{}' (id {}, {}, {} ratings)", t.name, t.id, t.status, t.ratings))
+        .collect();
+
+    AppError::Conflict(format!(
+        "{} is used by tests that are open or have ratings: {}. Delete with force=true to archive it instead",
+        item,
+        listed.join(", ")
+    ))
+}
@@ -0,0 +1,281 @@
+use crate::{
+    error::AppError,
+    storage::{self, Storage},
+};
+use axum::extract::multipart::Field;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+};
+
+/// Directory for in-progress uploads inside UPLOAD_DIR, so finished files can
+/// be moved into local storage with a rename on the same filesystem
+const TEMP_SUBDIR: &str = ".incoming";
+
+/// Where local storage keeps media, and where uploads are staged for every
+/// backend (UPLOAD_DIR, default ../uploads)
+pub fn upload_dir() -> String {
+    std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "../uploads".to_string())
+}
+
+/// Largest accepted file in bytes (MAX_UPLOAD_FILE_MB, default 250)
+pub fn max_file_bytes() -> u64 {
+    std::env::var("MAX_UPLOAD_FILE_MB")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|mb| *mb > 0)
+        .unwrap_or(250)
+        * 1024
+        * 1024
+}
+
+/// Request body limit: 250MB, or enough for one file of the per-file limit plus
+/// the rest of the form if that is larger
+pub fn max_request_bytes() -> usize {
+    (250 * 1024 * 1024).max(max_file_bytes() as usize + 1024 * 1024)
+}
+
+fn temp_dir() -> PathBuf {
+    Path::new(&upload_dir()).join(TEMP_SUBDIR)
+}
+
+/// Remove uploads left behind by a previous run that stopped mid-request
+pub async fn clean_temp_dir() {
+    let dir = temp_dir();
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Err(e) = tokio::fs::remove_file(entry.path()).await {
+            tracing::warn!("Failed to remove stale upload {}: {}", entry.path().display(), e);
+        }
+    }
+}
+
+/// Record the size of media files uploaded before sizes were stored. Each is
+/// looked up in storage once; files missing from storage stay unknown.
+pub async fn backfill_file_sizes(pool: SqlitePool) {
+    let files: Vec<(i64, String)> = match sqlx::query_as(
+        "SELECT id, storage_key FROM media_files WHERE file_size IS NULL"
+    )
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::warn!("Failed to list media files without a size: {}", e);
+            return;
+        }
+    };
+
+    let mut recorded = 0;
+    for (id, storage_key) in &files {
+        match storage::get().size(storage_key).await {
+            Ok(Some(size)) => {
+                let updated = sqlx::query("UPDATE media_files SET file_size = ? WHERE id = ?")
+                    .bind(size as i64)
+                    .bind(id)
+                    .execute(&pool)
+                    .await;
+                match updated {
+                    Ok(_) => recorded += 1,
+                    Err(e) => tracing::warn!("Failed to record the size of media {}: {}", id, e),
+                }
+            }
+            Ok(None) => tracing::warn!("Media {} ({}) is missing from storage", id, storage_key),
+            Err(e) => tracing::warn!("Failed to read the size of media {}: {}", id, e),
+        }
+    }
+
+    if !files.is_empty() {
+        tracing::info!("Recorded the size of {} of {} media file(s)", recorded, files.len());
+    }
+}
+
+/// Record the content hash of media files uploaded before hashes were stored,
+/// so duplicate detection covers them. A file whose content is already in the
+/// library is marked as a duplicate of that file instead. Every such file is
+/// read once; files missing from storage stay unhashed.
+pub async fn backfill_content_hashes(pool: SqlitePool) {
+    let files: Vec<(i64, String)> = match sqlx::query_as(
+        "SELECT id, storage_key FROM media_files WHERE content_hash IS NULL AND duplicate_of IS NULL"
+    )
+    .fetch_all(&pool)
+    .await
+    {
+        Ok(files) => files,
+        Err(e) => {
+            tracing::warn!("Failed to list media files without a content hash: {}", e);
+            return;
+        }
+    };
+
+    let mut recorded = 0;
+    for (id, storage_key) in &files {
+        let hash = match storage::get().open(storage_key).await {
+            Ok(reader) => hash_reader(reader).await,
+            Err(e) => Err(e),
+        };
+        match hash {
+            Ok(hash) => {
+                // content_hash is unique, so a copy points to the file holding it
+                let updated = sqlx::query(
+                    "UPDATE media_files SET
+                         duplicate_of = (SELECT original.id FROM media_files original WHERE original.content_hash = ?1),
+                         content_hash = CASE
+                             WHEN EXISTS (SELECT 1 FROM media_files original WHERE original.content_hash = ?1) THEN NULL
+                             ELSE ?1
+                         END
+                     WHERE id = ?2"
+                )
+                .bind(&hash)
+                .bind(id)
+                .execute(&pool)
+                .await;
+                match updated {
+                    Ok(_) => recorded += 1,
+                    Err(e) => tracing::warn!("Failed to record the content hash of media {}: {}", id, e),
+                }
+            }
+            Err(e) => tracing::warn!("Failed to hash media {} ({}): {}", id, storage_key, e),
+        }
+    }
+
+    if !files.is_empty() {
+        tracing::info!("Recorded the content hash of {} of {} media file(s)", recorded, files.len());
+    }
+}
+
+/// A file in the temp directory: an upload, or a preview or download on its
+/// way to or from storage. The file is deleted when this is dropped (if
+/// storage didn't move it away), so a failed or aborted request leaves
+/// nothing behind.
+pub struct TempUpload {
+    path: PathBuf,
+    pub size: u64,
+    /// Hex SHA-256 of the content, computed while it was written (uploads only)
+    pub sha256: String,
+}
+
+impl TempUpload {
+    /// A new, not yet written temp file
+    pub async fn create() -> std::io::Result<TempUpload> {
+        let dir = temp_dir();
+        tokio::fs::create_dir_all(&dir).await?;
+
+        Ok(TempUpload {
+            path: dir.join(format!("{}.part", uuid::Uuid::new_v4())),
+            size: 0,
+            sha256: String::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Up to `limit` bytes from the start of the file
+    pub async fn head(&self, limit: usize) -> Result<Vec<u8>, AppError> {
+        let file = File::open(&self.path)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Failed to read file: {}", e)))?;
+        let mut head = Vec::with_capacity(limit);
+        file.take(limit as u64)
+            .read_to_end(&mut head)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Failed to read file: {}", e)))?;
+        Ok(head)
+    }
+
+    /// Put the finished file into storage under `key`
+    pub async fn store(self, key: &str, content_type: &str) -> std::io::Result<()> {
+        storage::get().put_file(key, &self.path, content_type).await
+    }
+}
+
+impl Drop for TempUpload {
+    /// Deletes the file on the blocking pool when dropped inside the runtime,
+    /// so a slow disk doesn't stall the worker thread
+    fn drop(&mut self) {
+        let path = std::mem::take(&mut self.path);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || {
+                    let _ = std::fs::remove_file(path);
+                });
+            }
+            Err(_) => {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
+/// Copy an object out of storage into a temp file, for work that needs a
+/// local file (decoding, moving between backends)
+pub async fn download_to_temp(storage: &dyn Storage, key: &str) -> std::io::Result<TempUpload> {
+    let mut reader = storage.open(key).await?;
+    let mut download = TempUpload::create().await?;
+    let mut file = File::create(&download.path).await?;
+    download.size = tokio::io::copy(&mut reader, &mut file).await?;
+    file.sync_all().await?;
+    Ok(download)
+}
+
+/// Stream a multipart file field to a temp file chunk by chunk, hashing it on
+/// the way and rejecting it as soon as it grows past `max_file_bytes`
+pub async fn stream_to_temp(field: &mut Field<'_>, filename: &str) -> Result<TempUpload, AppError> {
+    let mut upload = TempUpload::create()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to create upload directory: {}", e)))?;
+    let mut hasher = Sha256::new();
+    let mut file = File::create(&upload.path)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to create file: {}", e)))?;
+
+    let limit = max_file_bytes();
+    while let Some(chunk) = field.chunk().await.map_err(|e| {
+        AppError::BadRequest(format!("Failed to read file data for '{}': {}", filename, e))
+    })? {
+        upload.size += chunk.len() as u64;
+        if upload.size > limit {
+            return Err(AppError::PayloadTooLarge(format!(
+                "File '{}' is larger than the {} MB upload limit",
+                filename,
+                limit / 1024 / 1024
+            )));
+        }
+
+        hasher.update(&chunk);
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Failed to write file: {}", e)))?;
+    }
+
+    file.sync_all()
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("Failed to write file: {}", e)))?;
+
+    upload.sha256 = hex::encode(hasher.finalize());
+    Ok(upload)
+}
+
+/// Hex SHA-256 of a stored object, read in chunks
+pub async fn hash_reader(mut reader: impl AsyncRead + Unpin) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
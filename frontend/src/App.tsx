@@ -3,6 +3,8 @@ import { isAuthenticated } from './utils/auth';
 import AdminLogin from './pages/AdminLogin';
 import AdminDashboard from './pages/AdminDashboard';
 import ChangePassword from './pages/ChangePassword';
+import ForgotPassword from './pages/ForgotPassword';
+import ResetPassword from './pages/ResetPassword';
 import UserTest from './pages/UserTest';
 
 function ProtectedRoute({ children }: { children: React.ReactNode }) {
@@ -15,6 +17,9 @@ function App() {
       <Routes>
         <Route path="/" element={<Navigate to="/admin/login" />} />
         <Route path="/admin/login" element={<AdminLogin />} />
+        <Route path="/admin/login/sso" element={<AdminLogin />} />
+        <Route path="/admin/forgot-password" element={<ForgotPassword />} />
+        <Route path="/admin/reset-password" element={<ResetPassword />} />
         <Route
           path="/admin/change-password"
           element={
@@ -0,0 +1,29 @@
+import EmailSettings from './EmailSettings';
+import TwoFactorSettings from './TwoFactorSettings';
+import ApiKeys from './ApiKeys';
+import Sessions from './Sessions';
+
+export default function Account() {
+  return (
+    <div style={styles.container}>
+      <h2 style={styles.title}>My Account</h2>
+      <EmailSettings />
+      <TwoFactorSettings />
+      <Sessions />
+      <ApiKeys />
+    </div>
+  );
+}
+
+const styles = {
+  container: {
+    maxWidth: '800px',
+    margin: '0 auto',
+  },
+  title: {
+    fontSize: '1.5rem',
+    fontWeight: 'bold',
+    marginBottom: '1.5rem',
+    color: '#1f2937',
+  },
+};
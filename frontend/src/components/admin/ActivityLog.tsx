@@ -255,6 +255,11 @@ export default function ActivityLog() {
                         {log.admin_username && (
                           <span style={styles.adminBadge}>Admin: {log.admin_username}</span>
                         )}
+                        {log.api_key_id && (
+                          <span style={styles.adminBadge}>
+                            API key: {log.api_key_name || `#${log.api_key_id}`}
+                          </span>
+                        )}
                         {log.user_email && (
                           <span style={styles.userBadge}>User: {log.user_email}</span>
                         )}
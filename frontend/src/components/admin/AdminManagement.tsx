@@ -1,13 +1,23 @@
 import { useState, useEffect } from 'react';
-import { MdPersonAdd, MdDelete } from 'react-icons/md';
+import { MdPersonAdd, MdDelete, MdLockOpen, MdPhonelinkErase, MdLockReset, MdBlock, MdCheckCircle, MdEmail } from 'react-icons/md';
 import api from '../../utils/api';
-import { formatDate } from '../../utils/dateFormatters';
-import type { Admin } from '../../types';
+import Sessions from './Sessions';
+import { formatDate, formatDateTime } from '../../utils/dateFormatters';
+import type { Admin, AdminRole, PasswordResetResponse } from '../../types';
+
+const ROLE_OPTIONS: { value: AdminRole; label: string }[] = [
+  { value: 'viewer', label: 'Viewer' },
+  { value: 'test_owner', label: 'Test Owner' },
+  { value: 'editor', label: 'Editor' },
+  { value: 'super_admin', label: 'Super Admin' },
+];
 
 export default function AdminManagement() {
   const [admins, setAdmins] = useState<Admin[]>([]);
   const [newUsername, setNewUsername] = useState('');
   const [newPassword, setNewPassword] = useState('');
+  const [newEmail, setNewEmail] = useState('');
+  const [newRole, setNewRole] = useState<AdminRole>('editor');
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState('');
   const [success, setSuccess] = useState('');
@@ -35,15 +45,21 @@ export default function AdminManagement() {
       await api.post('/admin/users', {
         username: newUsername,
         password: newPassword,
+        role: newRole,
+        email: newEmail || null,
       });
 
       setSuccess('Admin created successfully');
       setNewUsername('');
       setNewPassword('');
+      setNewEmail('');
+      setNewRole('editor');
       fetchAdmins();
     } catch (err: any) {
       if (err.response?.status === 409) {
-        setError('Username already exists');
+        setError(err.response.data?.details || 'Username already exists');
+      } else if (err.response?.status === 400) {
+        setError(err.response.data?.details || 'Password does not meet the password policy');
       } else {
         setError('Failed to create admin');
       }
@@ -52,6 +68,115 @@ export default function AdminManagement() {
     }
   };
 
+  const handleRoleChange = async (id: number, role: AdminRole) => {
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.patch(`/admin/users/${id}/role`, { role });
+      setSuccess('Role updated successfully');
+      fetchAdmins();
+    } catch (err: any) {
+      if (err.response?.status === 403) {
+        setError('You cannot change your own role');
+      } else {
+        setError('Failed to update role');
+      }
+    }
+  };
+
+  const handleUnlock = async (id: number) => {
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.post(`/admin/users/${id}/unlock`);
+      setSuccess('Account unlocked successfully');
+      fetchAdmins();
+    } catch (err) {
+      setError('Failed to unlock account');
+    }
+  };
+
+  const handleTotpRequired = async (id: number, required: boolean) => {
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.put(`/admin/users/${id}/totp-required`, { required });
+      setSuccess(required ? 'Two-factor authentication is now required' : 'Two-factor authentication is now optional');
+      fetchAdmins();
+    } catch (err) {
+      setError('Failed to update two-factor requirement');
+    }
+  };
+
+  const handleResetTotp = async (id: number) => {
+    if (!confirm('Remove this admin\'s authenticator? They will be signed out and must enroll again if 2FA is required.')) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.delete(`/admin/users/${id}/totp`);
+      setSuccess('Two-factor authentication reset successfully');
+      fetchAdmins();
+    } catch (err) {
+      setError('Failed to reset two-factor authentication');
+    }
+  };
+
+  const handleResetPassword = async (admin: Admin) => {
+    if (!confirm(`Reset the password for ${admin.username}? They will be signed out and must choose a new password.`)) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      const response = await api.post<PasswordResetResponse>(`/admin/users/${admin.id}/reset-password`);
+      setSuccess(`Temporary password for ${admin.username}: ${response.data.temporary_password} (shown only once)`);
+      fetchAdmins();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to reset password');
+    }
+  };
+
+  const handleEditEmail = async (admin: Admin) => {
+    const email = prompt(`Email address for ${admin.username} (used for password reset links). Leave empty to remove it.`, admin.email || '');
+    if (email === null) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.put(`/admin/users/${admin.id}/email`, { email: email.trim() || null });
+      setSuccess('Email updated successfully');
+      fetchAdmins();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to update email');
+    }
+  };
+
+  const handleSuspend = async (admin: Admin) => {
+    const suspend = !admin.suspended_at;
+    if (suspend && !confirm(`Suspend ${admin.username}? They will be signed out and unable to log in until reactivated.`)) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.post(`/admin/users/${admin.id}/${suspend ? 'suspend' : 'reactivate'}`);
+      setSuccess(suspend ? 'Admin suspended successfully' : 'Admin reactivated successfully');
+      fetchAdmins();
+    } catch (err: any) {
+      setError(err.response?.data?.details || (suspend ? 'Failed to suspend admin' : 'Failed to reactivate admin'));
+    }
+  };
+
   const handleDelete = async (id: number) => {
     if (!confirm('Are you sure you want to delete this admin?')) {
       return;
@@ -100,8 +225,28 @@ export default function AdminManagement() {
               required
               style={styles.input}
               disabled={loading}
-              minLength={6}
+              minLength={8}
+            />
+            <input
+              type="email"
+              placeholder="Email (optional)"
+              value={newEmail}
+              onChange={(e) => setNewEmail(e.target.value)}
+              style={styles.input}
+              disabled={loading}
             />
+            <select
+              value={newRole}
+              onChange={(e) => setNewRole(e.target.value as AdminRole)}
+              style={styles.select}
+              disabled={loading}
+            >
+              {ROLE_OPTIONS.map((option) => (
+                <option key={option.value} value={option.value}>
+                  {option.label}
+                </option>
+              ))}
+            </select>
             <button type="submit" disabled={loading} style={styles.createButton} className="icon-button touch-target">
               <MdPersonAdd />
               <span className="icon-button-text">{loading ? 'Creating...' : 'Create Admin'}</span>
@@ -123,11 +268,93 @@ export default function AdminManagement() {
                   {admin.is_super_admin && (
                     <span style={styles.superAdminBadge}>Super Admin</span>
                   )}
+                  {admin.locked_until && (
+                    <span style={styles.lockedBadge}>
+                      Locked until {formatDateTime(admin.locked_until)}
+                    </span>
+                  )}
+                  {admin.totp_enabled && (
+                    <span style={styles.totpBadge}>2FA</span>
+                  )}
+                  {admin.sso && (
+                    <span style={styles.totpBadge}>SSO</span>
+                  )}
+                  {admin.suspended_at && (
+                    <span style={styles.lockedBadge}>
+                      Suspended since {formatDateTime(admin.suspended_at)}
+                    </span>
+                  )}
+                  {admin.email && (
+                    <span style={styles.adminDate}>{admin.email}</span>
+                  )}
                   <span style={styles.adminDate}>
                     Created: {formatDate(admin.created_at)}
                   </span>
                 </div>
                 <div style={styles.adminActions}>
+                  {admin.locked_until && (
+                    <button
+                      onClick={() => handleUnlock(admin.id)}
+                      style={styles.unlockButton}
+                      className="icon-button touch-target"
+                    >
+                      <MdLockOpen />
+                      <span className="icon-button-text">Unlock</span>
+                    </button>
+                  )}
+                  <label style={styles.totpToggle}>
+                    <input
+                      type="checkbox"
+                      checked={admin.totp_required}
+                      onChange={(e) => handleTotpRequired(admin.id, e.target.checked)}
+                    />
+                    Require 2FA
+                  </label>
+                  {admin.totp_enabled && (
+                    <button
+                      onClick={() => handleResetTotp(admin.id)}
+                      style={styles.unlockButton}
+                      className="icon-button touch-target"
+                    >
+                      <MdPhonelinkErase />
+                      <span className="icon-button-text">Reset 2FA</span>
+                    </button>
+                  )}
+                  <button
+                    onClick={() => handleEditEmail(admin)}
+                    style={styles.unlockButton}
+                    className="icon-button touch-target"
+                  >
+                    <MdEmail />
+                    <span className="icon-button-text">Email</span>
+                  </button>
+                  <button
+                    onClick={() => handleResetPassword(admin)}
+                    style={styles.unlockButton}
+                    className="icon-button touch-target"
+                  >
+                    <MdLockReset />
+                    <span className="icon-button-text">Reset Password</span>
+                  </button>
+                  <button
+                    onClick={() => handleSuspend(admin)}
+                    style={admin.suspended_at ? styles.reactivateButton : styles.unlockButton}
+                    className="icon-button touch-target"
+                  >
+                    {admin.suspended_at ? <MdCheckCircle /> : <MdBlock />}
+                    <span className="icon-button-text">{admin.suspended_at ? 'Reactivate' : 'Suspend'}</span>
+                  </button>
+                  <select
+                    value={admin.role}
+                    onChange={(e) => handleRoleChange(admin.id, e.target.value as AdminRole)}
+                    style={styles.select}
+                  >
+                    {ROLE_OPTIONS.map((option) => (
+                      <option key={option.value} value={option.value}>
+                        {option.label}
+                      </option>
+                    ))}
+                  </select>
                   {admin.is_super_admin ? (
                     <span style={styles.cannotDelete}>Cannot Delete</span>
                   ) : (
@@ -146,6 +373,8 @@ export default function AdminManagement() {
           </div>
         )}
       </div>
+
+      <Sessions all />
     </div>
   );
 }
@@ -213,6 +442,13 @@ const styles = {
     borderRadius: '4px',
     fontSize: '0.875rem',
   },
+  select: {
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+    backgroundColor: 'white',
+  },
   createButton: {
     padding: '0.5rem 1rem',
     backgroundColor: '#3b82f6',
@@ -263,6 +499,29 @@ const styles = {
     fontSize: '0.75rem',
     fontWeight: '500',
   },
+  lockedBadge: {
+    padding: '0.25rem 0.5rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    fontSize: '0.75rem',
+    fontWeight: '500',
+  },
+  totpBadge: {
+    padding: '0.25rem 0.5rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    fontSize: '0.75rem',
+    fontWeight: '500',
+  },
+  totpToggle: {
+    display: 'flex',
+    alignItems: 'center',
+    gap: '0.25rem',
+    fontSize: '0.813rem',
+    color: '#374151',
+  },
   adminDate: {
     color: '#6b7280',
     fontSize: '0.813rem',
@@ -270,6 +529,28 @@ const styles = {
   adminActions: {
     display: 'flex',
     gap: '0.5rem',
+    flexWrap: 'wrap' as const,
+    alignItems: 'center',
+  },
+  reactivateButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#10b981',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.813rem',
+    fontWeight: '500',
+  },
+  unlockButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#f59e0b',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.813rem',
+    fontWeight: '500',
   },
   deleteButton: {
     padding: '0.5rem 1rem',
@@ -0,0 +1,352 @@
+import { useState, useEffect } from 'react';
+import { MdVpnKey, MdDelete } from 'react-icons/md';
+import api from '../../utils/api';
+import { formatDateTime } from '../../utils/dateFormatters';
+import type { AdminRole, ApiKey, CreatedApiKey, Permission } from '../../types';
+
+const SCOPE_OPTIONS: { value: Permission; label: string }[] = [
+  { value: 'view_library', label: 'View library' },
+  { value: 'view_results', label: 'View results' },
+  { value: 'manage_own_tests', label: 'Manage own tests' },
+  { value: 'manage_all_tests', label: 'Manage all tests' },
+  { value: 'manage_categories', label: 'Manage categories' },
+  { value: 'manage_media', label: 'Manage media' },
+  { value: 'view_activity_logs', label: 'View activity log' },
+  { value: 'manage_admins', label: 'Manage admins' },
+];
+
+// Mirrors Role::permissions in the backend; a key can't be given more than its owner has
+const ROLE_SCOPES: Record<AdminRole, Permission[]> = {
+  viewer: ['view_library', 'view_results'],
+  test_owner: ['view_library', 'view_results', 'manage_own_tests'],
+  editor: SCOPE_OPTIONS.map((option) => option.value).filter((scope) => scope !== 'manage_admins'),
+  super_admin: SCOPE_OPTIONS.map((option) => option.value),
+};
+
+export default function ApiKeys() {
+  const role = (localStorage.getItem('role') || 'viewer') as AdminRole;
+  const availableScopes = SCOPE_OPTIONS.filter((option) => ROLE_SCOPES[role]?.includes(option.value));
+
+  const [keys, setKeys] = useState<ApiKey[]>([]);
+  const [name, setName] = useState('');
+  const [scopes, setScopes] = useState<Permission[]>([]);
+  const [expiresInDays, setExpiresInDays] = useState(90);
+  const [createdKey, setCreatedKey] = useState<string | null>(null);
+  const [error, setError] = useState('');
+  const [success, setSuccess] = useState('');
+  const [loading, setLoading] = useState(false);
+
+  useEffect(() => {
+    fetchKeys();
+  }, []);
+
+  const fetchKeys = async () => {
+    try {
+      const response = await api.get<ApiKey[]>('/admin/api-keys');
+      setKeys(response.data);
+    } catch (err) {
+      setError('Failed to load API keys');
+    }
+  };
+
+  const toggleScope = (scope: Permission) => {
+    setScopes(scopes.includes(scope) ? scopes.filter((s) => s !== scope) : [...scopes, scope]);
+  };
+
+  const handleCreate = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError('');
+    setSuccess('');
+    setCreatedKey(null);
+
+    if (scopes.length === 0) {
+      setError('Select at least one scope');
+      return;
+    }
+
+    setLoading(true);
+    try {
+      const response = await api.post<CreatedApiKey>('/admin/api-keys', {
+        name,
+        scopes,
+        expires_in_days: expiresInDays,
+      });
+      setCreatedKey(response.data.api_key);
+      setName('');
+      setScopes([]);
+      fetchKeys();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to create API key');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleRevoke = async (key: ApiKey) => {
+    if (!confirm(`Revoke the API key "${key.name}"? Anything using it will stop working.`)) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.delete(`/admin/api-keys/${key.id}`);
+      setSuccess('API key revoked');
+      fetchKeys();
+    } catch (err) {
+      setError('Failed to revoke API key');
+    }
+  };
+
+  const keyStatus = (key: ApiKey) => {
+    if (key.revoked_at) return 'Revoked';
+    if (new Date(key.expires_at.replace(' ', 'T') + 'Z') <= new Date()) return 'Expired';
+    return null;
+  };
+
+  return (
+    <div style={styles.section}>
+      <h3 style={styles.sectionTitle}>API Keys</h3>
+      <p style={styles.hint}>
+        Keys let scripts call the admin API as you, limited to the selected scopes. Send them in an
+        <code> X-API-Key</code> header.
+      </p>
+
+      {error && <div style={styles.error}>{error}</div>}
+      {success && <div style={styles.success}>{success}</div>}
+
+      {createdKey && (
+        <div style={styles.field}>
+          <p style={styles.hint}>Copy this key now. It will not be shown again.</p>
+          <code style={styles.secret}>{createdKey}</code>
+        </div>
+      )}
+
+      <form onSubmit={handleCreate} style={styles.field}>
+        <div style={styles.formRow}>
+          <input
+            type="text"
+            placeholder="Key name (e.g. upload pipeline)"
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            required
+            style={styles.input}
+            disabled={loading}
+          />
+          <label style={styles.inlineLabel}>
+            Expires in
+            <input
+              type="number"
+              min={1}
+              value={expiresInDays}
+              onChange={(e) => setExpiresInDays(Number(e.target.value))}
+              style={styles.daysInput}
+              disabled={loading}
+            />
+            days
+          </label>
+        </div>
+        <div style={styles.scopeList}>
+          {availableScopes.map((option) => (
+            <label key={option.value} style={styles.inlineLabel}>
+              <input
+                type="checkbox"
+                checked={scopes.includes(option.value)}
+                onChange={() => toggleScope(option.value)}
+                disabled={loading}
+              />
+              {option.label}
+            </label>
+          ))}
+        </div>
+        <button type="submit" disabled={loading} style={styles.button} className="icon-button touch-target">
+          <MdVpnKey />
+          <span className="icon-button-text">{loading ? 'Creating...' : 'Create API Key'}</span>
+        </button>
+      </form>
+
+      {keys.length === 0 ? (
+        <p style={styles.empty}>No API keys yet</p>
+      ) : (
+        <div style={styles.keyList}>
+          {keys.map((key) => {
+            const status = keyStatus(key);
+            return (
+              <div key={key.id} style={styles.keyItem}>
+                <div style={styles.keyInfo}>
+                  <span style={styles.keyName}>{key.name}</span>
+                  <code style={styles.keyPrefix}>{key.key_prefix}…</code>
+                  {status && <span style={styles.statusBadge}>{status}</span>}
+                  <span style={styles.keyMeta}>Scopes: {key.scopes.join(', ')}</span>
+                  <span style={styles.keyMeta}>
+                    Expires {formatDateTime(key.expires_at)} · Last used{' '}
+                    {key.last_used_at ? formatDateTime(key.last_used_at) : 'never'}
+                  </span>
+                </div>
+                {!key.revoked_at && (
+                  <button
+                    onClick={() => handleRevoke(key)}
+                    style={styles.deleteButton}
+                    className="icon-button touch-target"
+                  >
+                    <MdDelete />
+                    <span className="icon-button-text">Revoke</span>
+                  </button>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+    </div>
+  );
+}
+
+const styles = {
+  section: {
+    backgroundColor: 'white',
+    padding: 'clamp(1rem, 2vw, 1.5rem)',
+    borderRadius: '8px',
+    boxShadow: '0 1px 3px rgba(0, 0, 0, 0.1)',
+    marginBottom: '1.5rem',
+  },
+  sectionTitle: {
+    fontSize: '1.125rem',
+    fontWeight: '600',
+    marginBottom: '1rem',
+    color: '#374151',
+  },
+  hint: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: '0 0 1rem 0',
+  },
+  error: {
+    padding: '0.75rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  success: {
+    padding: '0.75rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  field: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.75rem',
+    marginBottom: '1rem',
+  },
+  formRow: {
+    display: 'flex',
+    gap: '0.75rem',
+    alignItems: 'center',
+    flexWrap: 'wrap' as const,
+  },
+  scopeList: {
+    display: 'flex',
+    gap: '0.75rem',
+    flexWrap: 'wrap' as const,
+  },
+  inlineLabel: {
+    display: 'flex',
+    alignItems: 'center',
+    gap: '0.25rem',
+    fontSize: '0.813rem',
+    color: '#374151',
+  },
+  input: {
+    flex: '1',
+    minWidth: '200px',
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+  },
+  daysInput: {
+    width: '4.5rem',
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+  },
+  secret: {
+    padding: '0.5rem',
+    backgroundColor: '#f3f4f6',
+    borderRadius: '4px',
+    fontFamily: 'monospace',
+    wordBreak: 'break-all' as const,
+  },
+  button: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#3b82f6',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+    alignSelf: 'flex-start' as const,
+  },
+  empty: {
+    textAlign: 'center' as const,
+    color: '#6b7280',
+    padding: '1rem',
+  },
+  keyList: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.75rem',
+  },
+  keyItem: {
+    display: 'flex',
+    justifyContent: 'space-between',
+    alignItems: 'center',
+    gap: '0.75rem',
+    padding: '0.75rem',
+    border: '1px solid #e5e7eb',
+    borderRadius: '4px',
+    flexWrap: 'wrap' as const,
+  },
+  keyInfo: {
+    display: 'flex',
+    flexWrap: 'wrap' as const,
+    alignItems: 'center',
+    gap: '0.5rem',
+  },
+  keyName: {
+    fontWeight: '500',
+    color: '#1f2937',
+  },
+  keyPrefix: {
+    fontFamily: 'monospace',
+    fontSize: '0.813rem',
+    color: '#4b5563',
+  },
+  keyMeta: {
+    fontSize: '0.75rem',
+    color: '#6b7280',
+  },
+  statusBadge: {
+    padding: '0.25rem 0.5rem',
+    backgroundColor: '#f3f4f6',
+    color: '#4b5563',
+    borderRadius: '4px',
+    fontSize: '0.75rem',
+    fontWeight: '500',
+  },
+  deleteButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#ef4444',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.813rem',
+    fontWeight: '500',
+  },
+};
@@ -1,19 +1,31 @@
 import { useState, useEffect } from 'react';
-import { MdAdd, MdDelete, MdVisibility } from 'react-icons/md';
+import { MdAdd, MdDelete, MdVisibility, MdEdit, MdMergeType } from 'react-icons/md';
 import api, { getMediaUrl } from '../../utils/api';
 import { formatDate } from '../../utils/dateFormatters';
-import type { Category, MediaFile } from '../../types';
+import { flattenCategories } from '../../utils/categories';
+import { deleteOrArchive } from '../../utils/deletion';
+import type { Category, CategoryNode, CategoryStats, FlatCategory, MediaFile } from '../../types';
 import Modal from '../Modal';
 
 export default function Categories() {
-  const [categories, setCategories] = useState<Category[]>([]);
+  const [categories, setCategories] = useState<FlatCategory[]>([]);
   const [newCategoryName, setNewCategoryName] = useState('');
   const [newCategoryMediaType, setNewCategoryMediaType] = useState<'audio' | 'video' | 'image' | 'text'>('audio');
+  const [newCategoryParentId, setNewCategoryParentId] = useState<number | ''>('');
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState('');
   const [selectedCategory, setSelectedCategory] = useState<Category | null>(null);
   const [categoryMediaFiles, setCategoryMediaFiles] = useState<MediaFile[]>([]);
   const [modalLoading, setModalLoading] = useState(false);
+  const [categoryStats, setCategoryStats] = useState<CategoryStats | null>(null);
+  const [editingCategory, setEditingCategory] = useState<FlatCategory | null>(null);
+  const [editName, setEditName] = useState('');
+  const [editMediaType, setEditMediaType] = useState<Category['media_type']>('audio');
+  const [editParentId, setEditParentId] = useState<number | ''>('');
+  const [editInstructions, setEditInstructions] = useState('');
+  const [editStarLabels, setEditStarLabels] = useState<string[]>(['', '', '', '', '']);
+  const [mergeTargetId, setMergeTargetId] = useState<number | ''>('');
+  const [editError, setEditError] = useState('');
 
   useEffect(() => {
     fetchCategories();
@@ -21,8 +33,8 @@ export default function Categories() {
 
   const fetchCategories = async () => {
     try {
-      const response = await api.get<Category[]>('/admin/categories');
-      setCategories(response.data);
+      const response = await api.get<CategoryNode[]>('/admin/categories');
+      setCategories(flattenCategories(response.data));
     } catch (err) {
       setError('Failed to fetch categories');
     }
@@ -38,10 +50,12 @@ export default function Categories() {
     try {
       await api.post('/admin/categories', {
         name: newCategoryName,
-        media_type: newCategoryMediaType
+        media_type: newCategoryMediaType,
+        parent_id: newCategoryParentId === '' ? null : newCategoryParentId,
       });
       setNewCategoryName('');
       setNewCategoryMediaType('audio');
+      setNewCategoryParentId('');
       fetchCategories();
     } catch (err: any) {
       const errorMessage = err.response?.data?.details || err.response?.data?.error || 'Failed to create category';
@@ -56,8 +70,12 @@ export default function Categories() {
     setModalLoading(true);
 
     try {
-      const response = await api.get<MediaFile[]>(`/admin/media?category_id=${category.id}`);
-      setCategoryMediaFiles(response.data);
+      const [mediaResponse, statsResponse] = await Promise.all([
+        api.get<MediaFile[]>(`/admin/media?category_id=${category.id}`),
+        api.get<CategoryStats>(`/admin/categories/${category.id}/stats`),
+      ]);
+      setCategoryMediaFiles(mediaResponse.data);
+      setCategoryStats(statsResponse.data);
     } catch (err) {
       setError('Failed to fetch media files');
     } finally {
@@ -68,20 +86,112 @@ export default function Categories() {
   const handleCloseModal = () => {
     setSelectedCategory(null);
     setCategoryMediaFiles([]);
+    setCategoryStats(null);
   };
 
   const handleDeleteMedia = async (mediaId: number) => {
     if (!confirm('Are you sure you want to delete this media file?')) return;
 
     try {
-      await api.delete(`/admin/media/${mediaId}`);
+      if (!(await deleteOrArchive(`/admin/media/${mediaId}`))) return;
       // Refresh the media files list
       if (selectedCategory) {
         const response = await api.get<MediaFile[]>(`/admin/media?category_id=${selectedCategory.id}`);
         setCategoryMediaFiles(response.data);
       }
-    } catch (err) {
-      setError('Failed to delete media file');
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to delete media file');
+    }
+  };
+
+  // Categories a category can be moved under: same media type, not itself or one of its subcategories
+  const parentOptions = (mediaType: Category['media_type'], category?: FlatCategory | null) =>
+    categories.filter(
+      (candidate) =>
+        candidate.media_type === mediaType &&
+        (!category || (candidate.id !== category.id && !candidate.path.startsWith(`${category.path} > `)))
+    );
+
+  const handleEditOpen = (category: FlatCategory) => {
+    setEditingCategory(category);
+    setEditName(category.name);
+    setEditMediaType(category.media_type);
+    setEditParentId(category.parent_id ?? '');
+    setEditInstructions(category.instructions ?? '');
+    setEditStarLabels([1, 2, 3, 4, 5].map((stars) => category.star_labels?.[stars] ?? ''));
+    setMergeTargetId('');
+    setEditError('');
+  };
+
+  const handleEditClose = () => {
+    setEditingCategory(null);
+    setEditError('');
+  };
+
+  const handleUpdate = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!editingCategory || !editName.trim()) return;
+
+    setLoading(true);
+    setEditError('');
+
+    try {
+      await api.patch(`/admin/categories/${editingCategory.id}`, {
+        name: editName,
+        media_type: editMediaType,
+        parent_id: editParentId === '' ? null : editParentId,
+      });
+      handleEditClose();
+      fetchCategories();
+    } catch (err: any) {
+      setEditError(err.response?.data?.details || 'Failed to update category');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleRubricSave = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!editingCategory) return;
+
+    setLoading(true);
+    setEditError('');
+
+    // Blank labels are dropped by the server
+    const starLabels = Object.fromEntries(editStarLabels.map((label, i) => [String(i + 1), label]));
+
+    try {
+      await api.patch(`/admin/categories/${editingCategory.id}`, {
+        instructions: editInstructions,
+        star_labels: starLabels,
+      });
+      handleEditClose();
+      fetchCategories();
+    } catch (err: any) {
+      setEditError(err.response?.data?.details || 'Failed to save rubric');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleMerge = async () => {
+    if (!editingCategory || mergeTargetId === '') return;
+    const target = categories.find((category) => category.id === mergeTargetId);
+    if (!confirm(`Move all media, tests and subcategories from "${editingCategory.name}" into "${target?.path}" and delete "${editingCategory.name}"?`)) {
+      return;
+    }
+
+    setLoading(true);
+    setEditError('');
+
+    try {
+      await api.post(`/admin/categories/${editingCategory.id}/merge`, { target_id: mergeTargetId });
+      handleEditClose();
+      fetchCategories();
+    } catch (err: any) {
+      setEditError(err.response?.data?.details || 'Failed to merge categories');
+    } finally {
+      setLoading(false);
     }
   };
 
@@ -89,10 +199,10 @@ export default function Categories() {
     if (!confirm('Are you sure you want to delete this category?')) return;
 
     try {
-      await api.delete(`/admin/categories/${id}`);
+      if (!(await deleteOrArchive(`/admin/categories/${id}`))) return;
       fetchCategories();
-    } catch (err) {
-      setError('Failed to delete category');
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to delete category');
     }
   };
 
@@ -113,7 +223,10 @@ export default function Categories() {
         />
         <select
           value={newCategoryMediaType}
-          onChange={(e) => setNewCategoryMediaType(e.target.value as 'audio' | 'video' | 'image' | 'text')}
+          onChange={(e) => {
+            setNewCategoryMediaType(e.target.value as 'audio' | 'video' | 'image' | 'text');
+            setNewCategoryParentId('');
+          }}
           style={styles.select}
           disabled={loading}
         >
@@ -122,6 +235,19 @@ export default function Categories() {
           <option value="image">Image</option>
           <option value="text">Text</option>
         </select>
+        <select
+          value={newCategoryParentId}
+          onChange={(e) => setNewCategoryParentId(e.target.value ? Number(e.target.value) : '')}
+          style={styles.select}
+          disabled={loading}
+        >
+          <option value="">No parent (top level)</option>
+          {parentOptions(newCategoryMediaType).map((category) => (
+            <option key={category.id} value={category.id}>
+              {category.path}
+            </option>
+          ))}
+        </select>
         <button type="submit" disabled={loading} style={styles.button} className="icon-button touch-target">
           <MdAdd />
           <span className="icon-button-text">{loading ? 'Creating...' : 'Create Category'}</span>
@@ -133,7 +259,7 @@ export default function Categories() {
           <p style={styles.empty}>No categories yet. Create one above!</p>
         ) : (
           categories.map((category) => (
-            <div key={category.id} style={styles.item}>
+            <div key={category.id} style={{ ...styles.item, marginLeft: `${category.depth * 1.5}rem` }}>
               <div
                 style={styles.itemClickable}
                 onClick={() => handleCategoryClick(category)}
@@ -146,6 +272,17 @@ export default function Categories() {
                   Created: {formatDate(category.created_at)}
                 </p>
               </div>
+              <button
+                onClick={(e) => {
+                  e.stopPropagation();
+                  handleEditOpen(category);
+                }}
+                style={styles.editButton}
+                className="icon-button touch-target"
+              >
+                <MdEdit />
+                <span className="icon-button-text">Edit</span>
+              </button>
               <button
                 onClick={(e) => {
                   e.stopPropagation();
@@ -167,6 +304,23 @@ export default function Categories() {
         onClose={handleCloseModal}
         title={`Media Files in "${selectedCategory?.name}"`}
       >
+        {categoryStats && !modalLoading && (
+          <div style={styles.stats}>
+            <p style={styles.statsLine}>
+              {categoryStats.media_count} files, {(categoryStats.total_bytes / 1024 / 1024).toFixed(2)} MB on disk
+              {categoryStats.missing_files > 0 && ` (${categoryStats.missing_files} missing)`}
+              {categoryStats.media_by_mime_type.length > 0 &&
+                ` · ${categoryStats.media_by_mime_type.map((m) => `${m.mime_type}: ${m.count}`).join(', ')}`}
+            </p>
+            <p style={styles.statsLine}>
+              {categoryStats.tests.length === 0
+                ? 'Not used by any test'
+                : `Used by ${categoryStats.tests
+                    .map((t) => `${t.name} (${t.status}, ${t.ratings} ratings)`)
+                    .join(', ')} · ${categoryStats.total_ratings} ratings in total`}
+            </p>
+          </div>
+        )}
         {modalLoading ? (
           <p style={styles.loading}>Loading media files...</p>
         ) : categoryMediaFiles.length === 0 ? (
@@ -182,7 +336,7 @@ export default function Categories() {
                 </span>
                 <div style={styles.mediaActions} className="button-group">
                   <a
-                    href={getMediaUrl(media.id)}
+                    href={getMediaUrl(media)}
                     target="_blank"
                     rel="noopener noreferrer"
                     style={styles.viewButton}
@@ -205,6 +359,119 @@ export default function Categories() {
           </div>
         )}
       </Modal>
+
+      <Modal
+        isOpen={editingCategory !== null}
+        onClose={handleEditClose}
+        title={`Edit "${editingCategory?.name}"`}
+      >
+        {editError && <div style={styles.error}>{editError}</div>}
+        <form onSubmit={handleUpdate} style={styles.form} className="button-group">
+          <input
+            type="text"
+            value={editName}
+            onChange={(e) => setEditName(e.target.value)}
+            placeholder="Category name"
+            style={styles.input}
+            disabled={loading}
+          />
+          <select
+            value={editMediaType}
+            onChange={(e) => {
+              setEditMediaType(e.target.value as Category['media_type']);
+              setEditParentId('');
+            }}
+            style={styles.select}
+            disabled={loading}
+          >
+            <option value="audio">Audio</option>
+            <option value="video">Video</option>
+            <option value="image">Image</option>
+            <option value="text">Text</option>
+          </select>
+          <select
+            value={editParentId}
+            onChange={(e) => setEditParentId(e.target.value ? Number(e.target.value) : '')}
+            style={styles.select}
+            disabled={loading}
+          >
+            <option value="">No parent (top level)</option>
+            {parentOptions(editMediaType, editingCategory).map((category) => (
+              <option key={category.id} value={category.id}>
+                {category.path}
+              </option>
+            ))}
+          </select>
+          <button type="submit" disabled={loading} style={styles.button} className="icon-button touch-target">
+            <MdEdit />
+            <span className="icon-button-text">{loading ? 'Saving...' : 'Save'}</span>
+          </button>
+        </form>
+
+        <h4 style={styles.subheading}>Rating rubric</h4>
+        <p style={styles.itemDate}>
+          Shown to participants next to each media file from this category. Subcategories without their own rubric use this one.
+        </p>
+        <form onSubmit={handleRubricSave} style={styles.rubricForm}>
+          <textarea
+            value={editInstructions}
+            onChange={(e) => setEditInstructions(e.target.value)}
+            placeholder="Instructions, e.g. Rate the pitch accuracy of the vocals"
+            rows={3}
+            style={styles.textarea}
+            disabled={loading}
+          />
+          {editStarLabels.map((label, i) => (
+            <input
+              key={i}
+              type="text"
+              value={label}
+              onChange={(e) =>
+                setEditStarLabels(editStarLabels.map((value, j) => (j === i ? e.target.value : value)))
+              }
+              placeholder={`${i + 1} star${i === 0 ? '' : 's'} means... (optional)`}
+              style={styles.input}
+              disabled={loading}
+            />
+          ))}
+          <button type="submit" disabled={loading} style={styles.button} className="icon-button touch-target">
+            <MdEdit />
+            <span className="icon-button-text">{loading ? 'Saving...' : 'Save rubric'}</span>
+          </button>
+        </form>
+
+        <h4 style={styles.subheading}>Merge into another category</h4>
+        <p style={styles.itemDate}>
+          Moves every media file, test and subcategory from this category into the one you pick, then deletes this category.
+        </p>
+        <div style={styles.form} className="button-group">
+          <select
+            value={mergeTargetId}
+            onChange={(e) => setMergeTargetId(e.target.value ? Number(e.target.value) : '')}
+            style={styles.select}
+            disabled={loading}
+          >
+            <option value="">Select a category</option>
+            {categories
+              .filter((category) => category.id !== editingCategory?.id && category.media_type === editingCategory?.media_type)
+              .map((category) => (
+                <option key={category.id} value={category.id}>
+                  {category.path}
+                </option>
+              ))}
+          </select>
+          <button
+            type="button"
+            onClick={handleMerge}
+            disabled={loading || mergeTargetId === ''}
+            style={styles.button}
+            className="icon-button touch-target"
+          >
+            <MdMergeType />
+            <span className="icon-button-text">Merge</span>
+          </button>
+        </div>
+      </Modal>
     </div>
   );
 }
@@ -309,6 +576,21 @@ const styles = {
     fontSize: '0.75rem',
     color: '#6b7280',
   },
+  subheading: {
+    fontSize: '1rem',
+    fontWeight: '600',
+    marginBottom: '0.25rem',
+    color: '#374151',
+  },
+  editButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#6b7280',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+  },
   deleteButton: {
     padding: '0.5rem 1rem',
     backgroundColor: '#ef4444',
@@ -323,6 +605,30 @@ const styles = {
     color: '#6b7280',
     padding: '2rem',
   },
+  rubricForm: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.5rem',
+    marginBottom: '1.5rem',
+  },
+  textarea: {
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+    fontFamily: 'inherit',
+    resize: 'vertical' as const,
+  },
+  stats: {
+    marginBottom: '1rem',
+    paddingBottom: '0.75rem',
+    borderBottom: '1px solid #e5e7eb',
+  },
+  statsLine: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: '0.25rem 0',
+  },
   mediaList: {
     display: 'flex',
     flexDirection: 'column' as const,
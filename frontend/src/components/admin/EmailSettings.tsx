@@ -0,0 +1,131 @@
+import { useState, useEffect } from 'react';
+import { MdEmail } from 'react-icons/md';
+import api from '../../utils/api';
+import type { AccountInfo } from '../../types';
+
+export default function EmailSettings() {
+  const [account, setAccount] = useState<AccountInfo | null>(null);
+  const [email, setEmail] = useState('');
+  const [error, setError] = useState('');
+  const [success, setSuccess] = useState('');
+  const [loading, setLoading] = useState(false);
+
+  useEffect(() => {
+    fetchAccount();
+  }, []);
+
+  const fetchAccount = async () => {
+    try {
+      const response = await api.get<AccountInfo>('/admin/account');
+      setAccount(response.data);
+      setEmail(response.data.email || '');
+    } catch (err) {
+      setError('Failed to load account details');
+    }
+  };
+
+  const handleSave = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError('');
+    setSuccess('');
+    setLoading(true);
+
+    try {
+      await api.put('/admin/account/email', { email: email.trim() || null });
+      setSuccess(email.trim() ? 'Email saved' : 'Email removed');
+      fetchAccount();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to save email');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div style={styles.section}>
+      <h3 style={styles.sectionTitle}>Email</h3>
+      <p style={styles.hint}>
+        {account?.sso
+          ? 'Your account signs in through SSO, so password resets are handled by your identity provider.'
+          : 'Password reset links are sent to this address if you forget your password.'}
+      </p>
+
+      {error && <div style={styles.error}>{error}</div>}
+      {success && <div style={styles.success}>{success}</div>}
+
+      <form onSubmit={handleSave} style={styles.formRow}>
+        <input
+          type="email"
+          placeholder="you@example.com"
+          value={email}
+          onChange={(e) => setEmail(e.target.value)}
+          style={styles.input}
+          disabled={loading}
+        />
+        <button type="submit" disabled={loading} style={styles.button} className="icon-button touch-target">
+          <MdEmail />
+          <span className="icon-button-text">{loading ? 'Saving...' : 'Save Email'}</span>
+        </button>
+      </form>
+    </div>
+  );
+}
+
+const styles = {
+  section: {
+    backgroundColor: 'white',
+    padding: 'clamp(1rem, 2vw, 1.5rem)',
+    borderRadius: '8px',
+    boxShadow: '0 1px 3px rgba(0, 0, 0, 0.1)',
+    marginBottom: '1.5rem',
+  },
+  sectionTitle: {
+    fontSize: '1.125rem',
+    fontWeight: '600',
+    marginBottom: '1rem',
+    color: '#374151',
+  },
+  hint: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: '0 0 1rem 0',
+  },
+  error: {
+    padding: '0.75rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  success: {
+    padding: '0.75rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  formRow: {
+    display: 'flex',
+    gap: '0.75rem',
+    alignItems: 'center',
+    flexWrap: 'wrap' as const,
+  },
+  input: {
+    flex: '1',
+    minWidth: '200px',
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+  },
+  button: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#3b82f6',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+  },
+};
@@ -0,0 +1,84 @@
+import { useState, useEffect } from 'react';
+import api, { getThumbnailUrl, getWaveformUrl } from '../../utils/api';
+import type { MediaFile, Waveform } from '../../types';
+
+interface MediaPreviewProps {
+  media: Pick<MediaFile, 'id' | 'filename' | 'media_type' | 'url_signature'>;
+}
+
+const WIDTH = 120;
+const HEIGHT = 32;
+
+// Small preview for admin lists: the server-generated thumbnail of an image or
+// waveform of an audio file. Renders nothing for other media or when the
+// preview hasn't been generated.
+export default function MediaPreview({ media }: MediaPreviewProps) {
+  const [thumbnailFailed, setThumbnailFailed] = useState(false);
+  const [waveform, setWaveform] = useState<Waveform | null>(null);
+
+  useEffect(() => {
+    if (media.media_type !== 'audio') return;
+
+    let cancelled = false;
+    api.get<Waveform>(getWaveformUrl(media))
+      .then((response) => {
+        if (!cancelled) setWaveform(response.data);
+      })
+      .catch(() => {
+        if (!cancelled) setWaveform(null);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [media.id, media.media_type, media.url_signature?.signature]);
+
+  if (media.media_type === 'image' && !thumbnailFailed) {
+    return (
+      <img
+        src={getThumbnailUrl(media)}
+        alt={media.filename}
+        loading="lazy"
+        style={styles.thumbnail}
+        onError={() => setThumbnailFailed(true)}
+      />
+    );
+  }
+
+  if (media.media_type === 'audio' && waveform && waveform.peaks.length > 0) {
+    const barWidth = WIDTH / waveform.peaks.length;
+    return (
+      <svg width={WIDTH} height={HEIGHT} style={styles.waveform} aria-label={`Waveform of ${media.filename}`}>
+        {waveform.peaks.map((peak, index) => {
+          const height = Math.max(1, peak * HEIGHT);
+          return (
+            <rect
+              key={index}
+              x={index * barWidth}
+              y={(HEIGHT - height) / 2}
+              width={Math.max(barWidth, 0.5)}
+              height={height}
+              fill="#3b82f6"
+            />
+          );
+        })}
+      </svg>
+    );
+  }
+
+  return null;
+}
+
+const styles = {
+  thumbnail: {
+    width: '48px',
+    height: '48px',
+    objectFit: 'cover' as const,
+    borderRadius: '4px',
+    border: '1px solid #e5e7eb',
+  },
+  waveform: {
+    display: 'block',
+    backgroundColor: '#f9fafb',
+    borderRadius: '4px',
+  },
+};
@@ -1,12 +1,16 @@
 import { useState, useEffect } from 'react';
-import { MdUpload, MdVisibility, MdEdit, MdDelete, MdClose } from 'react-icons/md';
+import { MdUpload, MdVisibility, MdEdit, MdDelete, MdClose, MdContentCopy } from 'react-icons/md';
 import api, { getMediaUrl } from '../../utils/api';
 import { formatDate } from '../../utils/dateFormatters';
+import { flattenCategories } from '../../utils/categories';
+import { deleteOrArchive } from '../../utils/deletion';
+import { formatMetadata } from '../../utils/mediaMetadata';
 import Modal from '../Modal';
-import type { Category, MediaFile } from '../../types';
+import MediaPreview from './MediaPreview';
+import type { CategoryNode, DuplicateGroup, FlatCategory, MediaFile, UploadMediaResponse } from '../../types';
 
 export default function MediaUpload() {
-  const [categories, setCategories] = useState<Category[]>([]);
+  const [categories, setCategories] = useState<FlatCategory[]>([]);
   const [mediaFiles, setMediaFiles] = useState<MediaFile[]>([]);
   const [selectedCategoryIds, setSelectedCategoryIds] = useState<number[]>([]);
   const [files, setFiles] = useState<File[]>([]);
@@ -16,6 +20,9 @@ export default function MediaUpload() {
   const [editingMedia, setEditingMedia] = useState<MediaFile | null>(null);
   const [editCategoryIds, setEditCategoryIds] = useState<number[]>([]);
   const [modalOpen, setModalOpen] = useState(false);
+  const [onDuplicate, setOnDuplicate] = useState<'reject' | 'reuse'>('reject');
+  const [notice, setNotice] = useState('');
+  const [duplicates, setDuplicates] = useState<DuplicateGroup[] | null>(null);
 
   // Determine accept attribute based on selected categories
   const getAcceptAttribute = () => {
@@ -35,8 +42,8 @@ export default function MediaUpload() {
 
   const fetchCategories = async () => {
     try {
-      const response = await api.get<Category[]>('/admin/categories');
-      setCategories(response.data);
+      const response = await api.get<CategoryNode[]>('/admin/categories');
+      setCategories(flattenCategories(response.data));
     } catch (err) {
       setError('Failed to fetch categories');
     }
@@ -66,15 +73,26 @@ export default function MediaUpload() {
 
     setLoading(true);
     setError('');
+    setNotice('');
 
     try {
       const formData = new FormData();
       formData.append('category_ids', selectedCategoryIds.join(','));
+      // Options must come before the files, which the server reads as a stream
+      formData.append('on_duplicate', onDuplicate);
       files.forEach((file) => {
         formData.append('file', file);
       });
 
-      await api.post('/admin/media/upload', formData);
+      const response = await api.post<UploadMediaResponse>('/admin/media/upload', formData);
+      const reused = response.data.reused.length;
+      if (reused > 0) {
+        setNotice(`${reused} file(s) were already in the library; the selected categories were added to them.`);
+      }
+      // The other files were stored regardless
+      if (response.data.failed.length > 0) {
+        setError(response.data.failed.map(f => `${f.filename}: ${f.error}`).join('; '));
+      }
 
       setFiles([]);
       setSelectedCategoryIds([]);
@@ -96,10 +114,19 @@ export default function MediaUpload() {
     if (!confirm('Are you sure you want to delete this media file?')) return;
 
     try {
-      await api.delete(`/admin/media/${id}`);
+      if (!(await deleteOrArchive(`/admin/media/${id}`))) return;
       fetchMedia();
-    } catch (err) {
-      setError('Failed to delete media file');
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to delete media file');
+    }
+  };
+
+  const handleShowDuplicates = async () => {
+    try {
+      const response = await api.get<DuplicateGroup[]>('/admin/media/duplicates');
+      setDuplicates(response.data);
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to load duplicates report');
     }
   };
 
@@ -138,6 +165,7 @@ export default function MediaUpload() {
       <h2 style={styles.heading}>Media Upload & Management</h2>
 
       {error && <div style={styles.error}>{error}</div>}
+      {notice && <div style={styles.notice}>{notice}</div>}
 
       <form onSubmit={handleUpload} style={styles.uploadForm}>
         <div style={styles.uploadSection}>
@@ -152,7 +180,7 @@ export default function MediaUpload() {
                   disabled={loading}
                 />
                 <span style={styles.checkboxLabel}>
-                  {cat.name} ({cat.media_type})
+                  {cat.path} ({cat.media_type})
                 </span>
               </label>
             ))}
@@ -171,6 +199,19 @@ export default function MediaUpload() {
           />
         </div>
 
+        <div style={styles.uploadSection}>
+          <label style={styles.label}>If a file is already in the library:</label>
+          <select
+            value={onDuplicate}
+            onChange={(e) => setOnDuplicate(e.target.value as 'reject' | 'reuse')}
+            style={styles.select}
+            disabled={loading}
+          >
+            <option value="reject">Reject the upload</option>
+            <option value="reuse">Add the categories to the existing file</option>
+          </select>
+        </div>
+
         <button
           type="submit"
           disabled={loading || files.length === 0 || selectedCategoryIds.length === 0}
@@ -226,6 +267,15 @@ export default function MediaUpload() {
           <option value="image">Image</option>
           <option value="text">Text</option>
         </select>
+        <button
+          type="button"
+          onClick={handleShowDuplicates}
+          style={styles.duplicatesButton}
+          className="icon-button touch-target"
+        >
+          <MdContentCopy />
+          <span className="icon-button-text">Duplicates Report</span>
+        </button>
       </div>
 
       <div style={styles.list}>
@@ -235,7 +285,13 @@ export default function MediaUpload() {
           mediaFiles.map((media) => (
             <div key={media.id} style={styles.listItem}>
               <span style={styles.badge}>{media.media_type}</span>
-              <span style={styles.itemTitle}>{media.filename}</span>
+              <MediaPreview media={media} />
+              <span style={styles.itemTitle}>
+                {media.filename}
+                {media.metadata && (
+                  <span style={styles.itemMetadata}>{formatMetadata(media.metadata)}</span>
+                )}
+              </span>
               <div style={styles.categoryBadges}>
                 {media.categories.map(cat => (
                   <span key={cat.id} style={styles.categoryBadge}>
@@ -248,7 +304,7 @@ export default function MediaUpload() {
               </span>
               <div style={styles.itemActions} className="button-group">
                 <a
-                  href={getMediaUrl(media.id)}
+                  href={getMediaUrl(media)}
                   target="_blank"
                   rel="noopener noreferrer"
                   style={styles.viewButton}
@@ -295,7 +351,7 @@ export default function MediaUpload() {
                     onChange={() => handleEditCategoryToggle(cat.id)}
                   />
                   <span style={styles.checkboxLabel}>
-                    {cat.name} ({cat.media_type})
+                    {cat.path} ({cat.media_type})
                   </span>
                 </label>
               ))}
@@ -314,6 +370,38 @@ export default function MediaUpload() {
           </div>
         )}
       </Modal>
+
+      <Modal
+        isOpen={duplicates !== null}
+        onClose={() => setDuplicates(null)}
+        title="Duplicate Files"
+      >
+        {duplicates && (
+          duplicates.length === 0 ? (
+            <p style={styles.empty}>No files in the library have the same content.</p>
+          ) : (
+            <div style={styles.modalContent}>
+              {duplicates.map((group) => (
+                <div key={group.content_hash} style={styles.duplicateGroup}>
+                  <div style={styles.duplicateHash}>SHA-256 {group.content_hash.slice(0, 16)}…</div>
+                  {group.media.map((media) => (
+                    <div key={media.id} style={styles.fileItem}>
+                      <div style={styles.fileInfo}>
+                        <span style={styles.fileName}>
+                          {media.filename} (id {media.id}){media.archived_at ? ' – archived' : ''}
+                        </span>
+                        <span style={styles.fileSize}>
+                          {media.categories.map(cat => cat.name).join(', ') || 'No categories'} · {formatDate(media.uploaded_at)}
+                        </span>
+                      </div>
+                    </div>
+                  ))}
+                </div>
+              ))}
+            </div>
+          )
+        )}
+      </Modal>
     </div>
   );
 }
@@ -447,6 +535,12 @@ const styles = {
     flex: 1,
     minWidth: '200px',
   },
+  itemMetadata: {
+    display: 'block',
+    fontSize: '0.75rem',
+    fontWeight: '400',
+    color: '#6b7280',
+  },
   categoryBadges: {
     display: 'flex',
     gap: '0.25rem',
@@ -561,4 +655,34 @@ const styles = {
     display: 'flex',
     flexDirection: 'column' as const,
   },
+  notice: {
+    padding: '0.75rem',
+    backgroundColor: '#ecfdf5',
+    color: '#065f46',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+    fontSize: '0.875rem',
+  },
+  duplicatesButton: {
+    marginLeft: 'auto',
+    padding: '0.5rem 0.75rem',
+    backgroundColor: '#6b7280',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+  },
+  duplicateGroup: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.5rem',
+    marginBottom: '1rem',
+  },
+  duplicateHash: {
+    fontSize: '0.75rem',
+    fontFamily: 'monospace',
+    color: '#6b7280',
+  },
 };
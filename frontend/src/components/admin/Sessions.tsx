@@ -0,0 +1,225 @@
+import { useState, useEffect } from 'react';
+import { MdLogout } from 'react-icons/md';
+import api from '../../utils/api';
+import { removeToken } from '../../utils/auth';
+import { formatDateTime } from '../../utils/dateFormatters';
+import type { AdminSession } from '../../types';
+
+interface SessionsProps {
+  // Super admins: every admin's sessions instead of just your own
+  all?: boolean;
+}
+
+export default function Sessions({ all = false }: SessionsProps) {
+  const [sessions, setSessions] = useState<AdminSession[]>([]);
+  const [error, setError] = useState('');
+  const [success, setSuccess] = useState('');
+
+  useEffect(() => {
+    fetchSessions();
+  }, [all]);
+
+  const fetchSessions = async () => {
+    try {
+      const response = await api.get<AdminSession[]>(all ? '/admin/sessions' : '/admin/account/sessions');
+      setSessions(response.data);
+    } catch (err) {
+      setError('Failed to load sessions');
+    }
+  };
+
+  const handleTerminate = async (session: AdminSession) => {
+    const prompt = session.current
+      ? 'End this session? You will be logged out.'
+      : `End the session of ${session.username} from ${session.ip_address || 'an unknown address'}?`;
+    if (!confirm(prompt)) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.delete(`/admin/sessions/${session.id}`);
+      if (session.current) {
+        removeToken();
+        window.location.href = '/admin/login';
+        return;
+      }
+      setSuccess('Session ended');
+      fetchSessions();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to end session');
+    }
+  };
+
+  const handleTerminateOthers = async () => {
+    if (!confirm('Sign out of every other device and browser?')) {
+      return;
+    }
+    setError('');
+    setSuccess('');
+
+    try {
+      await api.post('/admin/account/sessions/revoke-others');
+      setSuccess('Other sessions ended');
+      fetchSessions();
+    } catch (err: any) {
+      setError(err.response?.data?.details || 'Failed to end sessions');
+    }
+  };
+
+  const otherSessions = sessions.filter((session) => !session.current).length;
+
+  return (
+    <div style={styles.section}>
+      <h3 style={styles.sectionTitle}>{all ? 'Active Sessions' : 'Your Sessions'}</h3>
+      <p style={styles.hint}>
+        {all
+          ? 'Everyone currently signed in. Ending a session signs that browser out on its next request.'
+          : 'Browsers and devices where you are signed in.'}
+      </p>
+
+      {error && <div style={styles.error}>{error}</div>}
+      {success && <div style={styles.success}>{success}</div>}
+
+      {!all && otherSessions > 0 && (
+        <button onClick={handleTerminateOthers} style={styles.button} className="icon-button touch-target">
+          <MdLogout />
+          <span className="icon-button-text">Sign Out Other Sessions</span>
+        </button>
+      )}
+
+      {sessions.length === 0 ? (
+        <p style={styles.empty}>No active sessions</p>
+      ) : (
+        <div style={styles.sessionList}>
+          {sessions.map((session) => (
+            <div key={session.id} style={styles.sessionItem}>
+              <div style={styles.sessionInfo}>
+                {all && <span style={styles.sessionName}>{session.username}</span>}
+                <span style={styles.sessionAgent}>{session.user_agent || 'Unknown browser'}</span>
+                {session.current && <span style={styles.currentBadge}>This session</span>}
+                <span style={styles.sessionMeta}>
+                  {session.ip_address || 'Unknown IP'} · Signed in {formatDateTime(session.created_at)} · Last active{' '}
+                  {formatDateTime(session.last_used_at)}
+                </span>
+              </div>
+              <button
+                onClick={() => handleTerminate(session)}
+                style={styles.deleteButton}
+                className="icon-button touch-target"
+              >
+                <MdLogout />
+                <span className="icon-button-text">End</span>
+              </button>
+            </div>
+          ))}
+        </div>
+      )}
+    </div>
+  );
+}
+
+const styles = {
+  section: {
+    backgroundColor: 'white',
+    padding: 'clamp(1rem, 2vw, 1.5rem)',
+    borderRadius: '8px',
+    boxShadow: '0 1px 3px rgba(0, 0, 0, 0.1)',
+    marginBottom: '1.5rem',
+  },
+  sectionTitle: {
+    fontSize: '1.125rem',
+    fontWeight: '600',
+    marginBottom: '1rem',
+    color: '#374151',
+  },
+  hint: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: '0 0 1rem 0',
+  },
+  error: {
+    padding: '0.75rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  success: {
+    padding: '0.75rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  button: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#3b82f6',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+    marginBottom: '1rem',
+  },
+  empty: {
+    textAlign: 'center' as const,
+    color: '#6b7280',
+    padding: '1rem',
+  },
+  sessionList: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.75rem',
+  },
+  sessionItem: {
+    display: 'flex',
+    justifyContent: 'space-between',
+    alignItems: 'center',
+    gap: '0.75rem',
+    padding: '0.75rem',
+    border: '1px solid #e5e7eb',
+    borderRadius: '4px',
+    flexWrap: 'wrap' as const,
+  },
+  sessionInfo: {
+    display: 'flex',
+    flexWrap: 'wrap' as const,
+    alignItems: 'center',
+    gap: '0.5rem',
+    minWidth: 0,
+  },
+  sessionName: {
+    fontWeight: '500',
+    color: '#1f2937',
+  },
+  sessionAgent: {
+    fontSize: '0.813rem',
+    color: '#374151',
+    wordBreak: 'break-word' as const,
+  },
+  sessionMeta: {
+    fontSize: '0.75rem',
+    color: '#6b7280',
+  },
+  currentBadge: {
+    padding: '0.25rem 0.5rem',
+    backgroundColor: '#dbeafe',
+    color: '#1e40af',
+    borderRadius: '4px',
+    fontSize: '0.75rem',
+    fontWeight: '500',
+  },
+  deleteButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#ef4444',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.813rem',
+    fontWeight: '500',
+  },
+};
@@ -2,8 +2,10 @@ import { useState, useEffect } from 'react';
 import { MdBarChart, MdPerson } from 'react-icons/md';
 import api from '../../utils/api';
 import { formatDateTime } from '../../utils/dateFormatters';
+import { formatMetadata } from '../../utils/mediaMetadata';
 import type { Test, TestResultsResponse } from '../../types';
 import StarRating from '../StarRating';
+import MediaPreview from './MediaPreview';
 
 export default function TestResults() {
   const [tests, setTests] = useState<Test[]>([]);
@@ -119,7 +121,11 @@ export default function TestResults() {
                     {results.aggregated.map((stat) => (
                       <div key={stat.media_file.id} style={styles.tableRow}>
                         <div style={styles.tableCell}>
+                          <MediaPreview media={stat.media_file} />
                           {stat.media_file.filename}
+                          {stat.media_file.metadata && (
+                            <span style={styles.mediaMetadata}>{formatMetadata(stat.media_file.metadata)}</span>
+                          )}
                         </div>
                         <div style={styles.tableCell}>
                           <span style={styles.badge}>
@@ -158,6 +164,9 @@ export default function TestResults() {
                       <div style={styles.mediaInfo}>
                         <span style={styles.mediaFilename}>{item.media_file.filename}</span>
                         <span style={styles.mediaBadge}>{item.media_file.media_type}</span>
+                        {item.media_file.metadata && (
+                          <span style={styles.mediaMetadata}>{formatMetadata(item.media_file.metadata)}</span>
+                        )}
                       </div>
                       <div style={styles.ratingBody}>
                         <div style={styles.ratingStars}>
@@ -321,6 +330,11 @@ const styles = {
     fontWeight: '500',
     flex: 1,
   },
+  mediaMetadata: {
+    display: 'block',
+    fontSize: '0.75rem',
+    color: '#6b7280',
+  },
   mediaBadge: {
     display: 'inline-block',
     padding: '0.25rem 0.5rem',
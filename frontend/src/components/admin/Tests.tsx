@@ -4,11 +4,12 @@ import toast from 'react-hot-toast';
 import api from '../../utils/api';
 import Modal from '../Modal';
 import { formatDate, formatDateTime } from '../../utils/dateFormatters';
-import type { Category, Test, TestUser, TestUserResponse } from '../../types';
+import { flattenCategories } from '../../utils/categories';
+import type { CategoryNode, FlatCategory, Test, TestUser, TestUserResponse } from '../../types';
 
 export default function Tests() {
   const [tests, setTests] = useState<Test[]>([]);
-  const [categories, setCategories] = useState<Category[]>([]);
+  const [categories, setCategories] = useState<FlatCategory[]>([]);
   const [testName, setTestName] = useState('');
   const [testDescription, setTestDescription] = useState('');
   const [loopMedia, setLoopMedia] = useState(true);
@@ -38,8 +39,8 @@ export default function Tests() {
 
   const fetchCategories = async () => {
     try {
-      const response = await api.get<Category[]>('/admin/categories');
-      setCategories(response.data);
+      const response = await api.get<CategoryNode[]>('/admin/categories');
+      setCategories(flattenCategories(response.data));
     } catch (err) {
       setError('Failed to fetch categories');
     }
@@ -221,7 +222,7 @@ export default function Tests() {
             <option value="">Select Category</option>
             {categories.map((cat) => (
               <option key={cat.id} value={cat.id}>
-                {cat.name}
+                {cat.path}
               </option>
             ))}
           </select>
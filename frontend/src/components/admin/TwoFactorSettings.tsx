@@ -0,0 +1,266 @@
+import { useState, useEffect } from 'react';
+import { MdSecurity } from 'react-icons/md';
+import api from '../../utils/api';
+import type { RecoveryCodesResponse, TotpSetupResponse } from '../../types';
+
+interface TotpStatus {
+  enabled: boolean;
+  required: boolean;
+  recovery_codes_remaining: number;
+}
+
+export default function TwoFactorSettings() {
+  const [status, setStatus] = useState<TotpStatus | null>(null);
+  const [setup, setSetup] = useState<TotpSetupResponse | null>(null);
+  const [recoveryCodes, setRecoveryCodes] = useState<string[] | null>(null);
+  const [code, setCode] = useState('');
+  const [error, setError] = useState('');
+  const [success, setSuccess] = useState('');
+  const [loading, setLoading] = useState(false);
+
+  useEffect(() => {
+    fetchStatus();
+  }, []);
+
+  const fetchStatus = async () => {
+    try {
+      const response = await api.get<TotpStatus>('/admin/totp');
+      setStatus(response.data);
+    } catch (err) {
+      setError('Failed to load two-factor status');
+    }
+  };
+
+  const run = async (action: () => Promise<void>, failure: string) => {
+    setError('');
+    setSuccess('');
+    setLoading(true);
+    try {
+      await action();
+    } catch (err: any) {
+      setError(err.response?.data?.details || failure);
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleStartSetup = () =>
+    run(async () => {
+      const response = await api.post<TotpSetupResponse>('/admin/totp/setup');
+      setSetup(response.data);
+      setRecoveryCodes(null);
+    }, 'Failed to start enrollment');
+
+  const handleEnable = (e: React.FormEvent) => {
+    e.preventDefault();
+    return run(async () => {
+      const response = await api.post<RecoveryCodesResponse>('/admin/totp/enable', { code: code.trim() });
+      setRecoveryCodes(response.data.recovery_codes);
+      setSetup(null);
+      setCode('');
+      setSuccess('Two-factor authentication enabled');
+      fetchStatus();
+    }, 'Invalid authentication code');
+  };
+
+  const handleDisable = (e: React.FormEvent) => {
+    e.preventDefault();
+    return run(async () => {
+      await api.post('/admin/totp/disable', { code: code.trim() });
+      setCode('');
+      setRecoveryCodes(null);
+      setSuccess('Two-factor authentication disabled');
+      fetchStatus();
+    }, 'Invalid authentication code');
+  };
+
+  const handleRegenerate = () =>
+    run(async () => {
+      const response = await api.post<RecoveryCodesResponse>('/admin/totp/recovery-codes', { code: code.trim() });
+      setRecoveryCodes(response.data.recovery_codes);
+      setCode('');
+      setSuccess('New recovery codes generated');
+      fetchStatus();
+    }, 'Invalid authentication code');
+
+  return (
+    <div style={styles.section}>
+      <h3 style={styles.sectionTitle}>Two-Factor Authentication</h3>
+
+      {error && <div style={styles.error}>{error}</div>}
+      {success && <div style={styles.success}>{success}</div>}
+
+      {recoveryCodes && (
+        <div style={styles.field}>
+          <p style={styles.hint}>
+            Save these recovery codes somewhere safe. Each can be used once if you lose your
+            authenticator app, and they will not be shown again.
+          </p>
+          <pre style={styles.codes}>{recoveryCodes.join('\n')}</pre>
+        </div>
+      )}
+
+      {status && !status.enabled && !setup && (
+        <div style={styles.field}>
+          <p style={styles.hint}>
+            Protect your account with a code from an authenticator app in addition to your password.
+          </p>
+          <button onClick={handleStartSetup} disabled={loading} style={styles.button} className="icon-button touch-target">
+            <MdSecurity />
+            <span className="icon-button-text">Set Up Two-Factor Authentication</span>
+          </button>
+        </div>
+      )}
+
+      {setup && (
+        <form onSubmit={handleEnable} style={styles.field}>
+          <p style={styles.hint}>
+            Add this key to your authenticator app, then enter the 6-digit code it shows.
+          </p>
+          <code style={styles.secret}>{setup.secret}</code>
+          <a href={setup.otpauth_uri} style={styles.link}>Open in authenticator app</a>
+          <div style={styles.formRow}>
+            <input
+              type="text"
+              placeholder="6-digit code"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+              required
+              autoComplete="one-time-code"
+              inputMode="numeric"
+              style={styles.input}
+              disabled={loading}
+            />
+            <button type="submit" disabled={loading} style={styles.button}>
+              Enable
+            </button>
+          </div>
+        </form>
+      )}
+
+      {status?.enabled && (
+        <form onSubmit={handleDisable} style={styles.field}>
+          <p style={styles.hint}>
+            Two-factor authentication is on. {status.recovery_codes_remaining} recovery codes remaining.
+            {status.required && ' It is required for your account and cannot be turned off.'}
+          </p>
+          <div style={styles.formRow}>
+            <input
+              type="text"
+              placeholder="Current 6-digit code"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+              required
+              autoComplete="one-time-code"
+              inputMode="numeric"
+              style={styles.input}
+              disabled={loading}
+            />
+            <button type="button" onClick={handleRegenerate} disabled={loading || !code} style={styles.button}>
+              New Recovery Codes
+            </button>
+            {!status.required && (
+              <button type="submit" disabled={loading} style={styles.dangerButton}>
+                Disable
+              </button>
+            )}
+          </div>
+        </form>
+      )}
+    </div>
+  );
+}
+
+const styles = {
+  section: {
+    backgroundColor: 'white',
+    padding: 'clamp(1rem, 2vw, 1.5rem)',
+    borderRadius: '8px',
+    boxShadow: '0 1px 3px rgba(0, 0, 0, 0.1)',
+    marginBottom: '1.5rem',
+  },
+  sectionTitle: {
+    fontSize: '1.125rem',
+    fontWeight: '600',
+    marginBottom: '1rem',
+    color: '#374151',
+  },
+  error: {
+    padding: '0.75rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  success: {
+    padding: '0.75rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    marginBottom: '1rem',
+  },
+  field: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.75rem',
+    marginBottom: '1rem',
+  },
+  formRow: {
+    display: 'flex',
+    gap: '0.75rem',
+    alignItems: 'center',
+    flexWrap: 'wrap' as const,
+  },
+  hint: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: 0,
+  },
+  secret: {
+    padding: '0.5rem',
+    backgroundColor: '#f3f4f6',
+    borderRadius: '4px',
+    fontFamily: 'monospace',
+    wordBreak: 'break-all' as const,
+  },
+  codes: {
+    padding: '0.75rem',
+    backgroundColor: '#f3f4f6',
+    borderRadius: '4px',
+    fontFamily: 'monospace',
+    margin: 0,
+  },
+  link: {
+    fontSize: '0.875rem',
+    color: '#3b82f6',
+  },
+  input: {
+    flex: '1',
+    minWidth: '160px',
+    padding: '0.5rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+  },
+  button: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#3b82f6',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+    alignSelf: 'flex-start' as const,
+  },
+  dangerButton: {
+    padding: '0.5rem 1rem',
+    backgroundColor: '#ef4444',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    cursor: 'pointer',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+  },
+};
@@ -1,17 +1,18 @@
 import { useRef, useEffect, useState } from 'react';
-import { getMediaUrl } from '../../utils/api';
+import { getTestMediaUrl } from '../../utils/api';
 import type { MediaFile } from '../../types';
 
 interface MediaPlayerProps {
   media: MediaFile;
+  token: string;
   loop?: boolean;
 }
 
-export default function MediaPlayer({ media, loop = false }: MediaPlayerProps) {
+export default function MediaPlayer({ media, token, loop = false }: MediaPlayerProps) {
   const audioRef = useRef<HTMLAudioElement>(null);
   const videoRef = useRef<HTMLVideoElement>(null);
   const [wasPlaying, setWasPlaying] = useState(false);
-  const mediaUrl = getMediaUrl(media.id);
+  const mediaUrl = getTestMediaUrl(token, media.id);
 
   // Reset audio/video when media changes
   useEffect(() => {
@@ -71,7 +72,7 @@ export default function MediaPlayer({ media, loop = false }: MediaPlayerProps) {
     switch (media.media_type) {
       case 'audio':
         return (
-          <audio ref={audioRef} key={media.id} controls loop={loop} style={styles.media}>
+          <audio ref={audioRef} key={media.id} controls loop={loop} preload="metadata" style={styles.media}>
             <source src={mediaUrl} type={media.mime_type} />
             Your browser does not support the audio element.
           </audio>
@@ -79,7 +80,7 @@ export default function MediaPlayer({ media, loop = false }: MediaPlayerProps) {
 
       case 'video':
         return (
-          <video ref={videoRef} key={media.id} controls loop={loop} style={styles.media}>
+          <video ref={videoRef} key={media.id} controls loop={loop} preload="metadata" style={styles.media}>
             <source src={mediaUrl} type={media.mime_type} />
             Your browser does not support the video element.
           </video>
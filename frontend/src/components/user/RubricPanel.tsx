@@ -0,0 +1,65 @@
+import type { Rubric } from '../../types';
+
+interface RubricPanelProps {
+  rubric: Rubric;
+}
+
+// Rating guidance of the category the current media file comes from
+export default function RubricPanel({ rubric }: RubricPanelProps) {
+  const labels = Object.entries(rubric.star_labels).sort(([a], [b]) => Number(a) - Number(b));
+
+  return (
+    <div style={styles.container}>
+      <div style={styles.heading}>How to rate {rubric.category_name}</div>
+      {rubric.instructions && <p style={styles.instructions}>{rubric.instructions}</p>}
+      {labels.length > 0 && (
+        <ul style={styles.labels}>
+          {labels.map(([stars, label]) => (
+            <li key={stars} style={styles.label}>
+              <span style={styles.stars}>{'★'.repeat(Number(stars))}</span>
+              {label}
+            </li>
+          ))}
+        </ul>
+      )}
+    </div>
+  );
+}
+
+const styles = {
+  container: {
+    backgroundColor: '#eff6ff',
+    border: '1px solid #bfdbfe',
+    borderRadius: '8px',
+    padding: '1rem',
+    marginBottom: '1rem',
+  },
+  heading: {
+    fontSize: '0.938rem',
+    fontWeight: '600',
+    color: '#1e40af',
+    marginBottom: '0.5rem',
+  },
+  instructions: {
+    fontSize: '0.875rem',
+    color: '#1f2937',
+    lineHeight: '1.5',
+    whiteSpace: 'pre-wrap' as const,
+    margin: '0 0 0.5rem 0',
+  },
+  labels: {
+    listStyle: 'none',
+    padding: 0,
+    margin: 0,
+  },
+  label: {
+    fontSize: '0.875rem',
+    color: '#374151',
+    padding: '0.125rem 0',
+  },
+  stars: {
+    display: 'inline-block',
+    minWidth: '5.5rem',
+    color: '#f59e0b',
+  },
+};
@@ -1,24 +1,35 @@
 import { useState } from 'react';
 import { useNavigate } from 'react-router-dom';
+import api from '../utils/api';
 import { removeToken } from '../utils/auth';
-import { MdCategory, MdPermMedia, MdAssignment, MdBarChart, MdPeople, MdHistory, MdLogout } from 'react-icons/md';
+import { MdCategory, MdPermMedia, MdAssignment, MdBarChart, MdPeople, MdHistory, MdAccountCircle, MdLogout } from 'react-icons/md';
 import Categories from '../components/admin/Categories';
 import MediaUpload from '../components/admin/MediaUpload';
 import Tests from '../components/admin/Tests';
 import TestResults from '../components/admin/TestResults';
 import AdminManagement from '../components/admin/AdminManagement';
 import ActivityLog from '../components/admin/ActivityLog';
+import Account from '../components/admin/Account';
 
-type Tab = 'categories' | 'media' | 'tests' | 'results' | 'admins' | 'activity-log';
+type Tab = 'categories' | 'media' | 'tests' | 'results' | 'admins' | 'activity-log' | 'account';
 
 export default function AdminDashboard() {
   const [activeTab, setActiveTab] = useState<Tab>('categories');
   const navigate = useNavigate();
   const isSuperAdmin = localStorage.getItem('is_super_admin') === 'true';
+  const role = localStorage.getItem('role');
+  const canViewActivityLog = role === 'editor' || role === 'super_admin';
 
-  const handleLogout = () => {
+  const handleLogout = async () => {
+    try {
+      // Revoke the server-side session so the token can't be reused
+      await api.post('/admin/logout');
+    } catch {
+      // Already expired or revoked; clear local state regardless
+    }
     removeToken();
     localStorage.removeItem('is_super_admin');
+    localStorage.removeItem('role');
     navigate('/admin/login');
   };
 
@@ -90,16 +101,29 @@ export default function AdminDashboard() {
             <span className="icon-button-text">Admin Management</span>
           </button>
         )}
+        {canViewActivityLog && (
+          <button
+            onClick={() => setActiveTab('activity-log')}
+            style={{
+              ...styles.tab,
+              ...(activeTab === 'activity-log' ? styles.activeTab : {}),
+            }}
+            className="icon-button"
+          >
+            <MdHistory />
+            <span className="icon-button-text">Activity Log</span>
+          </button>
+        )}
         <button
-          onClick={() => setActiveTab('activity-log')}
+          onClick={() => setActiveTab('account')}
           style={{
             ...styles.tab,
-            ...(activeTab === 'activity-log' ? styles.activeTab : {}),
+            ...(activeTab === 'account' ? styles.activeTab : {}),
           }}
           className="icon-button"
         >
-          <MdHistory />
-          <span className="icon-button-text">Activity Log</span>
+          <MdAccountCircle />
+          <span className="icon-button-text">Account</span>
         </button>
       </div>
 
@@ -109,7 +133,8 @@ export default function AdminDashboard() {
         {activeTab === 'tests' && <Tests />}
         {activeTab === 'results' && <TestResults />}
         {activeTab === 'admins' && isSuperAdmin && <AdminManagement />}
-        {activeTab === 'activity-log' && <ActivityLog />}
+        {activeTab === 'activity-log' && canViewActivityLog && <ActivityLog />}
+        {activeTab === 'account' && <Account />}
       </div>
     </div>
   );
@@ -1,82 +1,274 @@
-import { useState } from 'react';
-import { useNavigate } from 'react-router-dom';
-import api from '../utils/api';
-import { setToken } from '../utils/auth';
-import type { LoginResponse } from '../types';
+import { useState, useEffect } from 'react';
+import { Link, useNavigate, useSearchParams } from 'react-router-dom';
+import api, { API_BASE_URL } from '../utils/api';
+import { storeSession } from '../utils/auth';
+import type { LoginResponse, MfaChallengeResponse, OidcConfig, TotpSetupResponse } from '../types';
 
 export default function AdminLogin() {
   const [username, setUsername] = useState('');
   const [password, setPassword] = useState('');
   const [error, setError] = useState('');
   const [loading, setLoading] = useState(false);
+  const [challenge, setChallenge] = useState<MfaChallengeResponse | null>(null);
+  const [enrollment, setEnrollment] = useState<TotpSetupResponse | null>(null);
+  const [code, setCode] = useState('');
+  const [useRecoveryCode, setUseRecoveryCode] = useState(false);
+  const [pendingLogin, setPendingLogin] = useState<LoginResponse | null>(null);
+  const [oidcConfig, setOidcConfig] = useState<OidcConfig | null>(null);
+  const [searchParams] = useSearchParams();
   const navigate = useNavigate();
 
+  const completeLogin = (data: LoginResponse) => {
+    storeSession(data);
+    localStorage.setItem('is_super_admin', data.is_super_admin.toString());
+    localStorage.setItem('role', data.role);
+
+    // Check if password must be changed
+    if (data.password_must_change) {
+      navigate('/admin/change-password');
+    } else {
+      navigate('/admin/dashboard');
+    }
+  };
+
+  const showLoginError = (err: any, fallback: string) => {
+    if (err.response?.status === 429) {
+      setError(err.response.data?.details || 'Too many failed login attempts. Please try again later.');
+    } else if (err.response?.status === 403) {
+      const details = err.response.data?.details;
+      setError(
+        !details || details === 'Account is suspended'
+          ? 'This account is suspended. Contact a super admin.'
+          : details
+      );
+    } else {
+      setError(fallback);
+    }
+  };
+
+  useEffect(() => {
+    api.get<OidcConfig>('/admin/oidc/config')
+      .then((response) => setOidcConfig(response.data))
+      .catch(() => setOidcConfig(null));
+
+    const ssoError = searchParams.get('sso_error');
+    if (ssoError) {
+      setError(ssoError);
+    }
+
+    // Back from the identity provider with a single-use ticket
+    const ticket = searchParams.get('ticket');
+    if (ticket) {
+      setLoading(true);
+      api.post<LoginResponse>('/admin/oidc/exchange', { ticket })
+        .then((response) => completeLogin(response.data))
+        .catch((err) => {
+          showLoginError(err, 'Single sign-on failed. Please try again.');
+          navigate('/admin/login', { replace: true });
+        })
+        .finally(() => setLoading(false));
+    }
+  }, []);
+
+  const handleSsoLogin = () => {
+    window.location.href = `${API_BASE_URL}/admin/oidc/login`;
+  };
+
+  const passwordLoginEnabled = oidcConfig?.password_login_enabled ?? true;
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setError('');
     setLoading(true);
 
     try {
-      const response = await api.post<LoginResponse>('/admin/login', {
+      const response = await api.post<LoginResponse | MfaChallengeResponse>('/admin/login', {
         username,
         password,
       });
 
-      setToken(response.data.token);
-      localStorage.setItem('is_super_admin', response.data.is_super_admin.toString());
+      if ('mfa_required' in response.data) {
+        const mfa = response.data;
+        setChallenge(mfa);
+        if (mfa.enrollment_required) {
+          const setup = await api.post<TotpSetupResponse>('/admin/login/mfa/setup', {
+            mfa_token: mfa.mfa_token,
+          });
+          setEnrollment(setup.data);
+        }
+      } else {
+        completeLogin(response.data);
+      }
+    } catch (err: any) {
+      showLoginError(err, 'Invalid username or password');
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const handleMfaSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    if (!challenge) return;
+    setError('');
+    setLoading(true);
 
-      // Check if password must be changed
-      if (response.data.password_must_change) {
-        navigate('/admin/change-password');
+    try {
+      const response = await api.post<LoginResponse>('/admin/login/mfa', {
+        mfa_token: challenge.mfa_token,
+        ...(useRecoveryCode ? { recovery_code: code.trim() } : { code: code.trim() }),
+      });
+
+      // Recovery codes are only shown once, right after enrollment
+      if (response.data.recovery_codes?.length) {
+        setPendingLogin(response.data);
+      } else {
+        completeLogin(response.data);
+      }
+    } catch (err: any) {
+      if (err.response?.status === 401 && err.response.data?.details?.includes('MFA token')) {
+        setChallenge(null);
+        setEnrollment(null);
+        setCode('');
+        setError('Your sign-in expired. Please enter your password again.');
       } else {
-        navigate('/admin/dashboard');
+        showLoginError(err, 'Invalid authentication code');
       }
-    } catch (err) {
-      setError('Invalid username or password');
     } finally {
       setLoading(false);
     }
   };
 
+  if (pendingLogin) {
+    return (
+      <div style={styles.container}>
+        <div style={styles.card}>
+          <h1 style={styles.title}>Save Your Recovery Codes</h1>
+          <p style={styles.hint}>
+            Each code can be used once to sign in if you lose access to your authenticator app.
+            They will not be shown again.
+          </p>
+          <pre style={styles.codes}>{pendingLogin.recovery_codes?.join('\n')}</pre>
+          <button type="button" style={styles.button} onClick={() => completeLogin(pendingLogin)}>
+            I have saved these codes
+          </button>
+        </div>
+      </div>
+    );
+  }
+
+  if (challenge) {
+    return (
+      <div style={styles.container}>
+        <div style={styles.card}>
+          <h1 style={styles.title}>Two-Factor Authentication</h1>
+          <form onSubmit={handleMfaSubmit} style={styles.form}>
+            {error && <div style={styles.error}>{error}</div>}
+
+            {enrollment && (
+              <div style={styles.field}>
+                <p style={styles.hint}>
+                  Your account requires two-factor authentication. Add this key to your authenticator
+                  app, then enter the 6-digit code it shows.
+                </p>
+                <code style={styles.secret}>{enrollment.secret}</code>
+                <a href={enrollment.otpauth_uri} style={styles.link}>Open in authenticator app</a>
+              </div>
+            )}
+
+            <div style={styles.field}>
+              <label style={styles.label}>
+                {useRecoveryCode ? 'Recovery code' : 'Authentication code'}
+              </label>
+              <input
+                type="text"
+                value={code}
+                onChange={(e) => setCode(e.target.value)}
+                required
+                autoFocus
+                autoComplete="one-time-code"
+                inputMode={useRecoveryCode ? 'text' : 'numeric'}
+                style={styles.input}
+                disabled={loading}
+              />
+            </div>
+
+            <button type="submit" disabled={loading} style={styles.button}>
+              {loading ? 'Verifying...' : 'Verify'}
+            </button>
+
+            {!enrollment && (
+              <button
+                type="button"
+                style={styles.linkButton}
+                onClick={() => {
+                  setUseRecoveryCode(!useRecoveryCode);
+                  setCode('');
+                }}
+              >
+                {useRecoveryCode ? 'Use authenticator code instead' : 'Use a recovery code instead'}
+              </button>
+            )}
+          </form>
+        </div>
+      </div>
+    );
+  }
+
   return (
     <div style={styles.container}>
       <div style={styles.card}>
         <h1 style={styles.title}>Admin Login</h1>
-        <form onSubmit={handleSubmit} style={styles.form}>
-          {error && <div style={styles.error}>{error}</div>}
-
-          <div style={styles.field}>
-            <label style={styles.label}>Username</label>
-            <input
-              type="text"
-              value={username}
-              onChange={(e) => setUsername(e.target.value)}
-              required
-              style={styles.input}
-              disabled={loading}
-            />
-          </div>
-
-          <div style={styles.field}>
-            <label style={styles.label}>Password</label>
-            <input
-              type="password"
-              value={password}
-              onChange={(e) => setPassword(e.target.value)}
-              required
-              style={styles.input}
-              disabled={loading}
-            />
-          </div>
+        {!passwordLoginEnabled && error && <div style={styles.error}>{error}</div>}
+        {passwordLoginEnabled && (
+          <form onSubmit={handleSubmit} style={styles.form}>
+            {error && <div style={styles.error}>{error}</div>}
+
+            <div style={styles.field}>
+              <label style={styles.label}>Username</label>
+              <input
+                type="text"
+                value={username}
+                onChange={(e) => setUsername(e.target.value)}
+                required
+                style={styles.input}
+                disabled={loading}
+              />
+            </div>
+
+            <div style={styles.field}>
+              <label style={styles.label}>Password</label>
+              <input
+                type="password"
+                value={password}
+                onChange={(e) => setPassword(e.target.value)}
+                required
+                style={styles.input}
+                disabled={loading}
+              />
+            </div>
 
+            <button
+              type="submit"
+              disabled={loading}
+              style={styles.button}
+            >
+              {loading ? 'Logging in...' : 'Login'}
+            </button>
+            <Link to="/admin/forgot-password" style={styles.link}>
+              Forgot password?
+            </Link>
+          </form>
+        )}
+        {oidcConfig?.enabled && (
           <button
-            type="submit"
+            type="button"
+            onClick={handleSsoLogin}
             disabled={loading}
-            style={styles.button}
+            style={styles.ssoButton}
           >
-            {loading ? 'Logging in...' : 'Login'}
+            Sign in with SSO
           </button>
-        </form>
+        )}
       </div>
     </div>
   );
@@ -138,6 +330,48 @@ const styles = {
     cursor: 'pointer',
     marginTop: '0.5rem',
   },
+  hint: {
+    fontSize: '0.875rem',
+    color: '#4b5563',
+    margin: 0,
+  },
+  secret: {
+    padding: '0.5rem',
+    backgroundColor: '#f3f4f6',
+    borderRadius: '4px',
+    fontFamily: 'monospace',
+    wordBreak: 'break-all' as const,
+  },
+  codes: {
+    padding: '0.75rem',
+    backgroundColor: '#f3f4f6',
+    borderRadius: '4px',
+    fontFamily: 'monospace',
+    margin: '1rem 0',
+  },
+  link: {
+    fontSize: '0.875rem',
+    color: '#3b82f6',
+  },
+  ssoButton: {
+    width: '100%',
+    padding: '0.75rem',
+    backgroundColor: 'white',
+    color: '#3b82f6',
+    border: '1px solid #3b82f6',
+    borderRadius: '4px',
+    fontSize: '1rem',
+    fontWeight: '500',
+    cursor: 'pointer',
+    marginTop: '1rem',
+  },
+  linkButton: {
+    background: 'none',
+    border: 'none',
+    color: '#3b82f6',
+    fontSize: '0.875rem',
+    cursor: 'pointer',
+  },
   error: {
     padding: '0.75rem',
     backgroundColor: '#fee2e2',
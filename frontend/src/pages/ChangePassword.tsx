@@ -21,8 +21,8 @@ export default function ChangePassword() {
       return;
     }
 
-    if (newPassword.length < 6) {
-      setError('New password must be at least 6 characters long');
+    if (newPassword.length < 8) {
+      setError('New password must be at least 8 characters long');
       return;
     }
 
@@ -51,6 +51,9 @@ export default function ChangePassword() {
     } catch (err: any) {
       if (err.response?.status === 401) {
         setError('Current password is incorrect');
+      } else if (err.response?.status === 400) {
+        // Password policy violation (length, recently used password)
+        setError(err.response.data?.details || 'New password does not meet the password policy');
       } else {
         setError('Failed to change password. Please try again.');
       }
@@ -0,0 +1,164 @@
+import { useState } from 'react';
+import { Link } from 'react-router-dom';
+import api from '../utils/api';
+import type { ForgotPasswordResponse } from '../types';
+
+export default function ForgotPassword() {
+  const [email, setEmail] = useState('');
+  const [message, setMessage] = useState('');
+  const [error, setError] = useState('');
+  const [loading, setLoading] = useState(false);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+    setError('');
+    setLoading(true);
+
+    try {
+      const response = await api.post<ForgotPasswordResponse>('/admin/password-reset/request', { email });
+      setMessage(response.data.message);
+    } catch (err: any) {
+      if (err.response?.status === 429) {
+        setError('Too many reset requests. Please try again later.');
+      } else {
+        setError('Failed to request a password reset. Please try again.');
+      }
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  return (
+    <div style={styles.container}>
+      <div style={styles.card}>
+        <h1 style={styles.title}>Forgot Password</h1>
+        <p style={styles.subtitle}>
+          Enter the email address of your admin account and we'll send you a link to choose a new password.
+        </p>
+
+        {error && <div style={styles.error}>{error}</div>}
+
+        {message ? (
+          <div style={styles.success}>{message}</div>
+        ) : (
+          <form onSubmit={handleSubmit} style={styles.form}>
+            <div style={styles.inputGroup}>
+              <label style={styles.label}>Email</label>
+              <input
+                type="email"
+                value={email}
+                onChange={(e) => setEmail(e.target.value)}
+                required
+                style={styles.input}
+                disabled={loading}
+                autoFocus
+              />
+            </div>
+
+            <button
+              type="submit"
+              style={{
+                ...styles.button,
+                ...(loading ? styles.buttonDisabled : {}),
+              }}
+              disabled={loading}
+            >
+              {loading ? 'Sending...' : 'Send Reset Link'}
+            </button>
+          </form>
+        )}
+
+        <Link to="/admin/login" style={styles.link}>
+          Back to login
+        </Link>
+      </div>
+    </div>
+  );
+}
+
+const styles = {
+  container: {
+    minHeight: '100vh',
+    display: 'flex',
+    alignItems: 'center',
+    justifyContent: 'center',
+    backgroundColor: '#f3f4f6',
+    padding: '1rem',
+  },
+  card: {
+    backgroundColor: 'white',
+    padding: 'clamp(1.5rem, 3vw, 2rem)',
+    borderRadius: '8px',
+    boxShadow: '0 2px 4px rgba(0, 0, 0, 0.1)',
+    maxWidth: '400px',
+    width: '100%',
+  },
+  title: {
+    fontSize: '1.5rem',
+    fontWeight: 'bold',
+    marginBottom: '0.5rem',
+    color: '#1f2937',
+  },
+  subtitle: {
+    fontSize: '0.875rem',
+    color: '#6b7280',
+    marginBottom: '1.5rem',
+  },
+  error: {
+    padding: '0.75rem',
+    backgroundColor: '#fee2e2',
+    color: '#991b1b',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+    marginBottom: '1rem',
+  },
+  success: {
+    padding: '0.75rem',
+    backgroundColor: '#d1fae5',
+    color: '#065f46',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+    marginBottom: '1rem',
+  },
+  form: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '1rem',
+    marginBottom: '1rem',
+  },
+  inputGroup: {
+    display: 'flex',
+    flexDirection: 'column' as const,
+    gap: '0.25rem',
+  },
+  label: {
+    fontSize: '0.875rem',
+    fontWeight: '500',
+    color: '#374151',
+  },
+  input: {
+    padding: '0.5rem 0.75rem',
+    border: '1px solid #d1d5db',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+  },
+  button: {
+    padding: '0.75rem',
+    backgroundColor: '#3b82f6',
+    color: 'white',
+    border: 'none',
+    borderRadius: '4px',
+    fontSize: '0.875rem',
+    fontWeight: '500',
+    cursor: 'pointer',
+    marginTop: '0.5rem',
+  },
+  buttonDisabled: {
+    backgroundColor: '#9ca3af',
+    cursor: 'not-allowed',
+  },
+  link: {
+    fontSize: '0.875rem',
+    color: '#3b82f6',
+  },
+};
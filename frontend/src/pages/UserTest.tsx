@@ -5,6 +5,7 @@ import toast from 'react-hot-toast';
 import api from '../utils/api';
 import MediaPlayer from '../components/user/MediaPlayer';
 import RatingInput from '../components/user/RatingInput';
+import RubricPanel from '../components/user/RubricPanel';
 import type { TestDetailsResponse, Rating } from '../types';
 
 export default function UserTest() {
@@ -247,7 +248,9 @@ export default function UserTest() {
           )}
         </div>
 
-        <MediaPlayer media={currentMedia} loop={testData.test.loop_media} />
+        <MediaPlayer media={currentMedia} token={token ?? ''} loop={testData.test.loop_media} />
+
+        {currentMedia.rubric && <RubricPanel rubric={currentMedia.rubric} />}
 
         {error && <div style={styles.errorMessage}>{error}</div>}
         {successMessage && <div style={styles.successMessage}>{successMessage}</div>}
@@ -3,18 +3,86 @@ export interface Category {
   name: string;
   media_type: 'audio' | 'video' | 'image' | 'text';
   created_at: string;
+  parent_id: number | null;
+  archived_at: string | null;
+  instructions: string | null;
+  // Star value ("1".."5") to anchor label
+  star_labels: Record<string, string> | null;
+}
+
+export interface CategoryNode extends Category {
+  children: CategoryNode[];
+}
+
+// A category from the flattened tree, with its nesting depth and full path
+export interface FlatCategory extends Category {
+  depth: number;
+  path: string;
+}
+
+export interface CategoryStats {
+  category: Category;
+  media_count: number;
+  media_by_mime_type: { mime_type: string; count: number }[];
+  total_bytes: number;
+  missing_files: number;
+  tests: { id: number; name: string; status: string; ratings: number }[];
+  total_ratings: number;
 }
 
 export interface MediaFile {
   id: number;
   filename: string;
-  file_path: string;
   media_type: 'audio' | 'video' | 'image' | 'text';
   mime_type: string;
   uploaded_at: string;
+  archived_at: string | null;
+  content_hash: string | null;
+  metadata: MediaMetadata | null;
+  // Set in admin responses: query for the signed /admin/media/:id/... URLs
+  url_signature?: MediaUrlSignature;
   categories: Category[];
 }
 
+export interface MediaUrlSignature {
+  expires: number;
+  signature: string;
+}
+
+export interface Waveform {
+  duration_seconds: number;
+  peaks: number[];
+}
+
+export interface MediaMetadata {
+  duration_seconds?: number;
+  sample_rate?: number;
+  channels?: number;
+  bit_depth?: number;
+  width?: number;
+  height?: number;
+  color_space?: string;
+  characters?: number;
+  words?: number;
+  lines?: number;
+}
+
+export interface UploadFailure {
+  filename: string;
+  error: string;
+}
+
+export interface UploadMediaResponse {
+  uploaded: number[];
+  reused: number[];
+  failed: UploadFailure[];
+}
+
+export interface DuplicateGroup {
+  content_hash: string;
+  media: MediaFile[];
+}
+
 export interface Test {
   id: number;
   name: string;
@@ -34,11 +102,20 @@ export interface TestUser {
   completed_at: string | null;
 }
 
+export type AdminRole = 'viewer' | 'test_owner' | 'editor' | 'super_admin';
+
 export interface Admin {
   id: number;
   username: string;
   is_super_admin: boolean;
+  role: AdminRole;
   created_at: string;
+  locked_until: string | null;
+  totp_enabled: boolean;
+  totp_required: boolean;
+  suspended_at: string | null;
+  email: string | null;
+  sso: boolean;
 }
 
 export interface Rating {
@@ -55,9 +132,21 @@ export interface TestUserResponse {
   link: string;
 }
 
+export interface Rubric {
+  category_id: number;
+  category_name: string;
+  instructions: string | null;
+  star_labels: Record<string, string>;
+}
+
+// A media file in a test, with the rubric of the category it comes from
+export interface TestMediaFile extends MediaFile {
+  rubric: Rubric | null;
+}
+
 export interface TestDetailsResponse {
   test: Test;
-  media_files: MediaFile[];
+  media_files: TestMediaFile[];
 }
 
 export interface RatingWithUser {
@@ -79,9 +168,39 @@ export interface TestResultsResponse {
 }
 
 export interface LoginResponse {
-  token: string;
+  // token and refresh_token are omitted in cookie mode, which returns csrf_token instead
+  token?: string;
+  refresh_token?: string;
+  csrf_token?: string;
+  expires_in: number;
   is_super_admin: boolean;
+  role: AdminRole;
   password_must_change: boolean;
+  recovery_codes?: string[];
+}
+
+export interface PasswordResetResponse {
+  temporary_password: string;
+}
+
+export interface MfaChallengeResponse {
+  mfa_required: true;
+  enrollment_required: boolean;
+  mfa_token: string;
+}
+
+export interface OidcConfig {
+  enabled: boolean;
+  password_login_enabled: boolean;
+}
+
+export interface TotpSetupResponse {
+  secret: string;
+  otpauth_uri: string;
+}
+
+export interface RecoveryCodesResponse {
+  recovery_codes: string[];
 }
 
 export interface ChangePasswordRequest {
@@ -89,6 +208,33 @@ export interface ChangePasswordRequest {
   new_password: string;
 }
 
+export interface ForgotPasswordResponse {
+  message: string;
+}
+
+export interface ResetPasswordRequest {
+  token: string;
+  new_password: string;
+}
+
+export interface AdminSession {
+  id: string;
+  admin_id: number;
+  username: string;
+  ip_address: string | null;
+  user_agent: string | null;
+  created_at: string;
+  last_used_at: string;
+  expires_at: string;
+  current: boolean;
+}
+
+export interface AccountInfo {
+  username: string;
+  email: string | null;
+  sso: boolean;
+}
+
 export interface ActivityLog {
   id: number;
   admin_username: string | null;
@@ -100,6 +246,35 @@ export interface ActivityLog {
   ip_address: string | null;
   user_agent: string | null;
   timestamp: string;
+  api_key_id: number | null;
+  api_key_name: string | null;
+}
+
+export type Permission =
+  | 'view_library'
+  | 'view_results'
+  | 'manage_own_tests'
+  | 'manage_all_tests'
+  | 'manage_categories'
+  | 'manage_media'
+  | 'view_activity_logs'
+  | 'manage_admins';
+
+export interface ApiKey {
+  id: number;
+  admin_id: number;
+  name: string;
+  key_prefix: string;
+  scopes: Permission[];
+  expires_at: string;
+  last_used_at: string | null;
+  last_used_ip: string | null;
+  created_at: string;
+  revoked_at: string | null;
+}
+
+export interface CreatedApiKey extends ApiKey {
+  api_key: string;
 }
 
 export interface ActivityLogResponse {
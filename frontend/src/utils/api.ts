@@ -1,18 +1,29 @@
-import axios from 'axios';
+import axios, { type AxiosRequestConfig } from 'axios';
+import { getCsrfToken, getRefreshToken, removeToken, storeSession } from './auth';
+import type { LoginResponse, MediaFile } from '../types';
 
-const API_BASE_URL = import.meta.env.VITE_API_BASE_URL || 'http://localhost:3000/api';
+export const API_BASE_URL = import.meta.env.VITE_API_BASE_URL || 'http://localhost:3000/api';
 
+// withCredentials sends the session cookies when the server runs in cookie mode
 const api = axios.create({
   baseURL: API_BASE_URL,
+  withCredentials: true,
 });
 
-// Add JWT token to requests
+const isSafeMethod = (method?: string) => ['get', 'head', 'options'].includes((method || 'get').toLowerCase());
+
+// Add JWT token (or, in cookie mode, the CSRF token) to requests
 api.interceptors.request.use((config) => {
   const token = localStorage.getItem('token');
   if (token) {
     config.headers.Authorization = `Bearer ${token}`;
   }
 
+  const csrfToken = getCsrfToken();
+  if (csrfToken && !isSafeMethod(config.method)) {
+    config.headers['X-CSRF-Token'] = csrfToken;
+  }
+
   // Don't set Content-Type for FormData - let browser set it with boundary
   if (config.data instanceof FormData) {
     delete config.headers['Content-Type'];
@@ -21,21 +32,86 @@ api.interceptors.request.use((config) => {
   return config;
 });
 
-// Handle 401 errors
+// Single in-flight refresh shared by every request that hits a 401 at the same time
+let refreshPromise: Promise<void> | null = null;
+
+const refreshAccessToken = (): Promise<void> => {
+  if (!refreshPromise) {
+    const refreshToken = getRefreshToken();
+    const csrfToken = getCsrfToken();
+    refreshPromise = (refreshToken || csrfToken
+      ? axios
+          .post<LoginResponse>(
+            `${API_BASE_URL}/admin/refresh`,
+            // In cookie mode the refresh token is sent as a cookie
+            refreshToken ? { refresh_token: refreshToken } : {},
+            { withCredentials: true, headers: csrfToken ? { 'X-CSRF-Token': csrfToken } : {} }
+          )
+          .then((response) => {
+            storeSession(response.data);
+          })
+      : Promise.reject(new Error('No refresh token'))
+    ).finally(() => {
+      refreshPromise = null;
+    });
+  }
+  return refreshPromise;
+};
+
+// Handle 401 errors: try to refresh the access token once, then give up and log out
 api.interceptors.response.use(
   (response) => response,
-  (error) => {
-    if (error.response?.status === 401) {
-      localStorage.removeItem('token');
+  async (error) => {
+    const original = error.config as (AxiosRequestConfig & { _retried?: boolean }) | undefined;
+    const isAuthEndpoint = original?.url?.startsWith('/admin/login') || original?.url?.startsWith('/admin/refresh');
+
+    if (error.response?.status === 401 && original && !original._retried && !isAuthEndpoint) {
+      original._retried = true;
+      try {
+        await refreshAccessToken();
+        return api(original);
+      } catch {
+        // Fall through to logout below
+      }
+    }
+
+    if (error.response?.status === 401 && !isAuthEndpoint) {
+      removeToken();
       window.location.href = '/admin/login';
     }
+
+    // The server refuses everything but change-password until a required change is done
+    if (
+      error.response?.status === 403 &&
+      error.response.data?.details === 'Password change required' &&
+      window.location.pathname !== '/admin/change-password'
+    ) {
+      window.location.href = '/admin/change-password';
+    }
     return Promise.reject(error);
   }
 );
 
-// Helper function to get media URL
-export const getMediaUrl = (mediaId: number): string => {
-  return `${API_BASE_URL}/media/${mediaId}/serve`;
+// Participants fetch media through their test token
+export const getTestMediaUrl = (token: string, mediaId: number): string => {
+  return `${API_BASE_URL}/test/${encodeURIComponent(token)}/media/${mediaId}/serve`;
 };
 
+// Admin media URLs carry the expiring signature the server attached to the media
+type SignedMedia = Pick<MediaFile, 'id' | 'url_signature'>;
+
+const signedMediaUrl = (media: SignedMedia, object: 'serve' | 'thumbnail' | 'waveform'): string => {
+  const signature = media.url_signature;
+  const query = signature
+    ? `?expires=${signature.expires}&signature=${encodeURIComponent(signature.signature)}`
+    : '';
+  return `${API_BASE_URL}/admin/media/${media.id}/${object}${query}`;
+};
+
+export const getMediaUrl = (media: SignedMedia): string => signedMediaUrl(media, 'serve');
+
+export const getThumbnailUrl = (media: SignedMedia): string => signedMediaUrl(media, 'thumbnail');
+
+export const getWaveformUrl = (media: SignedMedia): string => signedMediaUrl(media, 'waveform');
+
 export default api;
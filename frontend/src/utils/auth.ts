@@ -1,3 +1,5 @@
+import type { LoginResponse } from '../types';
+
 export const setToken = (token: string) => {
   localStorage.setItem('token', token);
 };
@@ -6,10 +8,43 @@ export const getToken = (): string | null => {
   return localStorage.getItem('token');
 };
 
+export const setRefreshToken = (refreshToken: string) => {
+  localStorage.setItem('refresh_token', refreshToken);
+};
+
+export const getRefreshToken = (): string | null => {
+  return localStorage.getItem('refresh_token');
+};
+
+// Cookie mode (AUTH_MODE=cookie on the server): the tokens live in HttpOnly cookies
+// and only the CSRF token is kept here, to be echoed in the X-CSRF-Token header
+export const setCsrfToken = (csrfToken: string) => {
+  localStorage.setItem('csrf_token', csrfToken);
+};
+
+export const getCsrfToken = (): string | null => {
+  return localStorage.getItem('csrf_token');
+};
+
+// Store whatever credentials a login or refresh response carries
+export const storeSession = (data: LoginResponse) => {
+  if (data.token) {
+    setToken(data.token);
+  }
+  if (data.refresh_token) {
+    setRefreshToken(data.refresh_token);
+  }
+  if (data.csrf_token) {
+    setCsrfToken(data.csrf_token);
+  }
+};
+
 export const removeToken = () => {
   localStorage.removeItem('token');
+  localStorage.removeItem('refresh_token');
+  localStorage.removeItem('csrf_token');
 };
 
 export const isAuthenticated = (): boolean => {
-  return !!getToken();
+  return !!getToken() || !!getCsrfToken();
 };
@@ -0,0 +1,14 @@
+/**
+ * Helpers for the category tree returned by GET /admin/categories
+ */
+import type { CategoryNode, FlatCategory } from '../types';
+
+/**
+ * Flatten the category tree depth-first, keeping each category's depth and
+ * its full path (e.g. "Vocals > Female > Pop") for pickers and lists
+ */
+export const flattenCategories = (nodes: CategoryNode[], depth = 0, parentPath = ''): FlatCategory[] =>
+  nodes.flatMap(({ children, ...category }) => {
+    const path = parentPath ? `${parentPath} > ${category.name}` : category.name;
+    return [{ ...category, depth, path }, ...flattenCategories(children, depth + 1, path)];
+  });
@@ -0,0 +1,25 @@
+/**
+ * Deleting categories and media that tests depend on
+ */
+import api from './api';
+
+/**
+ * Delete a category or media file. When the server refuses because tests still
+ * use it (409), show the tests it named and offer to archive it instead.
+ * Resolves to false if the admin declined to archive.
+ */
+export const deleteOrArchive = async (url: string): Promise<boolean> => {
+  try {
+    await api.delete(url);
+    return true;
+  } catch (err: any) {
+    if (err.response?.status !== 409) throw err;
+
+    const details = err.response.data?.details || 'This item is used by tests.';
+    if (!confirm(`${details}\n\nArchive it instead? It will be hidden here but kept for those tests and their results.`)) {
+      return false;
+    }
+    await api.delete(url, { params: { force: true } });
+    return true;
+  }
+};
@@ -0,0 +1,37 @@
+import type { MediaMetadata } from '../types';
+
+/**
+ * Format a duration in seconds as m:ss (or h:mm:ss)
+ * @param seconds - Duration in seconds
+ * @returns Formatted duration (e.g., "3:07")
+ */
+export const formatDuration = (seconds: number): string => {
+  const total = Math.round(seconds);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = String(total % 60).padStart(2, '0');
+  return h > 0 ? `${h}:${String(m).padStart(2, '0')}:${s}` : `${m}:${s}`;
+};
+
+/**
+ * Summarize the technical metadata of a media file on one line
+ * @param metadata - Metadata extracted by the server on upload
+ * @returns Summary (e.g., "0:42 · 44.1 kHz · 2 ch · 16-bit"), empty if nothing is known
+ */
+export const formatMetadata = (metadata: MediaMetadata | null): string => {
+  if (!metadata) return '';
+
+  const parts: string[] = [];
+  if (metadata.duration_seconds !== undefined) parts.push(formatDuration(metadata.duration_seconds));
+  if (metadata.width !== undefined && metadata.height !== undefined) {
+    parts.push(`${metadata.width}×${metadata.height}`);
+  }
+  if (metadata.color_space) parts.push(metadata.color_space.toUpperCase());
+  if (metadata.sample_rate !== undefined) parts.push(`${metadata.sample_rate / 1000} kHz`);
+  if (metadata.channels !== undefined) parts.push(`${metadata.channels} ch`);
+  if (metadata.bit_depth !== undefined) parts.push(`${metadata.bit_depth}-bit`);
+  if (metadata.words !== undefined) parts.push(`${metadata.words} words`);
+  if (metadata.characters !== undefined) parts.push(`${metadata.characters} chars`);
+  if (metadata.lines !== undefined) parts.push(`${metadata.lines} lines`);
+  return parts.join(' · ');
+};